@@ -1,3 +1,6 @@
+use crate::query::{FilterMode, NodePage, NodeQuery, NodeStream, PageCursor};
+use crate::search_budget::CancellationToken;
+use crate::traversal::{TraversalOptions, TraversalStream};
 use async_trait::async_trait;
 use nodespace_core_types::{Node, NodeId, NodeSpaceResult};
 
@@ -7,6 +10,8 @@ pub trait DataStore {
     async fn store_node(&self, node: Node) -> NodeSpaceResult<NodeId>;
     async fn get_node(&self, id: &NodeId) -> NodeSpaceResult<Option<Node>>;
     async fn update_node(&self, node: Node) -> NodeSpaceResult<()>;
+    /// Insert the node, or replace the stored row with the same id, as one table version
+    async fn upsert_node(&self, node: Node) -> NodeSpaceResult<NodeId>;
     async fn update_node_with_embedding(
         &self,
         node: Node,
@@ -14,12 +19,75 @@ pub trait DataStore {
     ) -> NodeSpaceResult<()>;
     async fn delete_node(&self, id: &NodeId) -> NodeSpaceResult<()>;
     async fn query_nodes(&self, query: &str) -> NodeSpaceResult<Vec<Node>>;
+    /// Structured query with type, hierarchy, timestamp and metadata predicates
+    async fn find_nodes(&self, query: NodeQuery) -> NodeSpaceResult<Vec<Node>>;
+    /// One page of `find_nodes` results; pass `next_cursor` back to fetch the next page
+    ///
+    /// Pages follow the query's `order_by` (id order without one) and resume
+    /// after the last row returned, so writes between pages don't shift them.
+    async fn find_nodes_page(
+        &self,
+        query: NodeQuery,
+        page_size: usize,
+        cursor: Option<&PageCursor>,
+    ) -> NodeSpaceResult<NodePage>;
+    /// Stream every match batch by batch instead of collecting the table into memory
+    async fn stream_nodes(&self, query: NodeQuery) -> NodeSpaceResult<NodeStream<'_>>;
+
+    // Batch operations for bulk imports - one table write per call instead of per node
+    async fn store_nodes(&self, nodes: Vec<Node>) -> NodeSpaceResult<Vec<NodeId>>;
+    /// Results are in the same order as `ids`, with `None` for ids that don't exist
+    async fn get_nodes(&self, ids: &[NodeId]) -> NodeSpaceResult<Vec<Option<Node>>>;
+    async fn update_nodes(&self, nodes: Vec<Node>) -> NodeSpaceResult<()>;
+    async fn delete_nodes(&self, ids: &[NodeId]) -> NodeSpaceResult<()>;
+
+    /// Link `from` to `to` with a typed edge
+    ///
+    /// The hierarchy types `"contains"`, `"parent"`, `"child"`,
+    /// `"parent_child"` and `"has_child"` (any case) move `to` and its
+    /// descendants to the end of `from`'s children. Any other type is stored
+    /// as an edge in the relationships table; before that table existed every
+    /// type was written to the hierarchy.
     async fn create_relationship(
         &self,
         from: &NodeId,
         to: &NodeId,
         rel_type: &str,
     ) -> NodeSpaceResult<()>;
+    /// Store a typed edge with JSON properties, replacing those of an existing
+    /// edge with the same endpoints and type
+    async fn create_relationship_with_properties(
+        &self,
+        from: &NodeId,
+        to: &NodeId,
+        rel_type: &str,
+        properties: serde_json::Value,
+    ) -> NodeSpaceResult<()>;
+    /// Typed edges touching `node_id` in `direction`, optionally of one type,
+    /// oldest first
+    async fn get_relationships(
+        &self,
+        node_id: &NodeId,
+        direction: RelationshipDirection,
+        rel_type: Option<&str>,
+    ) -> NodeSpaceResult<Vec<Relationship>>;
+    /// Remove the edge, if any; deleting a node removes all of its edges
+    async fn delete_relationship(
+        &self,
+        from: &NodeId,
+        to: &NodeId,
+        rel_type: &str,
+    ) -> NodeSpaceResult<()>;
+    /// Nodes whose `mentions` include `node_id`, whether set by the caller or
+    /// found as `[[id]]` / `@id` in their text
+    async fn get_backlinks(&self, node_id: &NodeId) -> NodeSpaceResult<Vec<Node>>;
+    /// Walk out from `start` along `options.edges`, streaming each reachable
+    /// node once with the path that led to it
+    async fn traverse(
+        &self,
+        start: &NodeId,
+        options: TraversalOptions,
+    ) -> NodeSpaceResult<TraversalStream<'_>>;
 
     // Vector search capabilities
     async fn store_node_with_embedding(
@@ -27,14 +95,63 @@ pub trait DataStore {
         node: Node,
         embedding: Vec<f32>,
     ) -> NodeSpaceResult<NodeId>;
+    /// Nearest neighbours of `embedding` as `(node, score)`, most similar first
+    ///
+    /// The score is a similarity derived from the store's distance metric, so
+    /// higher is always better; for `LanceDataStore` see `DistanceMetric`.
     async fn search_similar_nodes(
         &self,
         embedding: Vec<f32>,
         limit: usize,
     ) -> NodeSpaceResult<Vec<(Node, f32)>>;
+    /// `search_similar_nodes` re-ranked for variety with MMR and source caps
+    ///
+    /// Scores are still similarity to `embedding`, but results are in
+    /// selection order rather than strictly descending.
+    async fn search_similar_nodes_diverse(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+        diversity: DiversityConfig,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>>;
+    /// `search_similar_nodes` restricted to nodes matching `filter`
+    ///
+    /// `filter` ordering and paging are ignored; results are most similar first.
+    async fn filtered_vector_search(
+        &self,
+        embedding: Vec<f32>,
+        filter: NodeQuery,
+        limit: usize,
+        mode: FilterMode,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>>;
     async fn update_node_embedding(&self, id: &NodeId, embedding: Vec<f32>) -> NodeSpaceResult<()>;
 
-    // Semantic search with provided embedding vector
+    /// Keyword search over node content, ranked by BM25, best first
+    ///
+    /// Bare words are ranked terms, `"quoted words"` must match as a phrase,
+    /// `word*` matches word prefixes and `type:task` restricts node types.
+    /// The BM25 score is returned as `SearchResult::score`.
+    async fn full_text_search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<SearchResult>>;
+
+    /// Full-text match on `text` and ANN search on `embedding`, fused into one ranking
+    ///
+    /// `text` uses the `full_text_search` syntax; its `type:` filters apply to
+    /// both sides. The two ranked lists are merged with `config.fusion_strategy`,
+    /// weighted by `semantic_weight` and `keyword_weight`. BM25 scores are
+    /// divided by the best keyword hit before fusion so both sides are on a
+    /// 0-1 scale; `RelevanceFactors::keyword_score` keeps the raw BM25.
+    async fn hybrid_text_vector_search(
+        &self,
+        text: &str,
+        embedding: Vec<f32>,
+        config: HybridSearchConfig,
+    ) -> NodeSpaceResult<Vec<SearchResult>>;
+
+    // Semantic search with provided embedding vector, scored like search_similar_nodes
     async fn semantic_search_with_embedding(
         &self,
         embedding: Vec<f32>,
@@ -57,7 +174,7 @@ pub trait DataStore {
         node_id: &NodeId,
     ) -> NodeSpaceResult<Option<MultiLevelEmbeddings>>;
 
-    // NEW: Embedding-specific search methods
+    // NEW: Embedding-specific search methods, scored like search_similar_nodes
     async fn search_by_individual_embedding(
         &self,
         embedding: Vec<f32>,
@@ -102,6 +219,33 @@ pub trait DataStore {
         root_id: &NodeId,
         node_type: &str,
     ) -> NodeSpaceResult<Vec<Node>>;
+
+    // Sibling order, stored as `before_sibling_id` back links
+    /// Children of `parent_id` in sibling order
+    ///
+    /// Fails with `SiblingCycle`, `DanglingSiblingLink`, `DuplicateSiblingHeads`
+    /// or `DuplicateSiblingLinks` when the links don't form a single chain.
+    async fn get_ordered_children(&self, parent_id: &NodeId) -> NodeSpaceResult<Vec<Node>>;
+    /// Store a new node as the child of `parent_id` at `position` (0 = first;
+    /// past the end appends), relinking its new neighbour in the same write
+    async fn insert_child_at(
+        &self,
+        parent_id: &NodeId,
+        node: Node,
+        position: usize,
+    ) -> NodeSpaceResult<NodeId>;
+    /// Move `node_id` directly before `anchor_id`, under the anchor's parent
+    async fn move_node_before(&self, node_id: &NodeId, anchor_id: &NodeId) -> NodeSpaceResult<()>;
+    /// Move `node_id` directly after `anchor_id`, under the anchor's parent
+    async fn move_node_after(&self, node_id: &NodeId, anchor_id: &NodeId) -> NodeSpaceResult<()>;
+
+    /// Nearest neighbours of `embedding` with their ancestors, siblings and
+    /// children, deduplicated across hits and trimmed to `options.budget`
+    async fn retrieve_context(
+        &self,
+        embedding: Vec<f32>,
+        options: ContextOptions,
+    ) -> NodeSpaceResult<ContextWindow>;
 }
 
 // Cross-modal types for implementation
@@ -134,17 +278,148 @@ pub enum NodeType {
 
 #[derive(Debug, Clone)]
 pub struct HybridSearchConfig {
-    pub semantic_weight: f64,            // 0.0-1.0, semantic similarity
-    pub structural_weight: f64,          // 0.0-1.0, relationship proximity
-    pub temporal_weight: f64,            // 0.0-1.0, time-based relevance
-    pub individual_weight: f64,          // 0.0-1.0, individual embedding weight
-    pub contextual_weight: f64,          // 0.0-1.0, contextual embedding weight
-    pub hierarchical_weight: f64,        // 0.0-1.0, hierarchical embedding weight
-    pub max_results: usize,              // Maximum results to return
-    pub min_similarity_threshold: f64,   // Minimum similarity score
-    pub enable_cross_modal: bool,        // Allow text→image search
-    pub enable_cross_level_fusion: bool, // Combine scores across embedding levels
-    pub search_timeout_ms: u64,          // Maximum search time
+    pub semantic_weight: f64,              // 0.0-1.0, semantic similarity
+    pub structural_weight: f64,            // 0.0-1.0, relationship proximity
+    pub temporal_weight: f64,              // 0.0-1.0, time-based relevance
+    pub cross_modal_weight: f64,           // 0.0-1.0, image bonus when enable_cross_modal
+    pub individual_weight: f64,            // 0.0-1.0, individual embedding weight
+    pub contextual_weight: f64,            // 0.0-1.0, contextual embedding weight
+    pub hierarchical_weight: f64,          // 0.0-1.0, hierarchical embedding weight
+    pub max_results: usize,                // Maximum results to return
+    pub min_similarity_threshold: f64,     // Minimum similarity score
+    pub enable_cross_modal: bool,          // Allow text→image search
+    pub enable_cross_level_fusion: bool,   // Combine scores across embedding levels
+    pub search_timeout_ms: u64,            // Maximum search time
+    pub anchor_node_ids: Vec<NodeId>,      // Context nodes for structural proximity
+    pub max_structural_hops: usize,        // Hops beyond which structural score is 0
+    pub temporal_scoring: TemporalScoring, // Shape of the time-based relevance curve
+    pub temporal_field: TemporalField,     // Timestamp the temporal score is computed from
+    pub temporal_reference: Option<chrono::DateTime<chrono::Utc>>, // Defaults to now
+    pub fusion_strategy: FusionStrategy,   // How embedding levels are merged
+    pub keyword_weight: f64,               // 0.0-1.0, full-text match in hybrid text search
+    pub on_timeout: SearchTimeoutBehavior, // What to return when search_timeout_ms passes
+    pub cancellation: Option<CancellationToken>, // Aborts the search when cancelled
+    pub diversity: Option<DiversityConfig>, // MMR re-ranking and per-source caps
+}
+
+/// Re-ranking that trades some relevance for more varied results
+///
+/// Candidates are over-fetched, then picked greedily by maximal marginal
+/// relevance: `lambda * relevance - (1 - lambda) * max_similarity_to_picked`,
+/// using the stored `vector` embeddings. Relevance is scaled by the best
+/// candidate's so fused and raw similarity scores behave the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiversityConfig {
+    /// 1.0 is pure relevance, 0.0 pure novelty; `None` keeps relevance order
+    pub mmr_lambda: Option<f32>,
+    /// At most this many results from one source
+    pub source_cap: Option<SourceCap>,
+    /// Candidates fetched per requested result before re-ranking
+    pub candidate_multiplier: usize,
+}
+
+impl Default for DiversityConfig {
+    fn default() -> Self {
+        Self {
+            mmr_lambda: Some(0.7),
+            source_cap: None,
+            candidate_multiplier: 4,
+        }
+    }
+}
+
+/// Limit on results sharing a document or a parent
+///
+/// Nodes without a `root_id` / `parent_id` count as their own source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceCap {
+    PerRoot(usize),
+    PerParent(usize),
+}
+
+/// What a hybrid search returns when it runs past `search_timeout_ms`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchTimeoutBehavior {
+    /// Rank the candidates gathered so far and flag each result's explanation
+    /// as `truncated`; fails with `SearchTimeout` if nothing was found yet
+    #[default]
+    ReturnPartial,
+    /// Fail with `DataStoreError::SearchTimeout`
+    Error,
+}
+
+/// How ranked hits from separate retrieval levels are merged into one score
+///
+/// Each level (individual, contextual, hierarchical embedding) runs as its own
+/// ANN query; a node only contributes on the levels it was retrieved from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FusionStrategy {
+    /// Weighted mean of the node's similarities, normalized over the levels it
+    /// was found on, so a missing level doesn't count as zero similarity
+    #[default]
+    WeightedSum,
+    /// Sum of `weight / (k + rank)` over levels, with 1-based ranks; `k = 60`
+    /// is the usual choice
+    ReciprocalRank { k: f32 },
+    /// Best similarity on any level
+    Max,
+}
+
+/// How the temporal score falls off with distance from the reference time
+///
+/// Distance is absolute, so dates after the reference (e.g. upcoming journal
+/// days) are scored the same as dates the same distance before it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum TemporalScoring {
+    /// 1.0 within a day, 0.8 within a week, 0.5 otherwise or without a timestamp
+    #[default]
+    Stepped,
+    /// Halves every `half_life`: `0.5^(distance / half_life)`
+    ExponentialDecay { half_life: chrono::Duration },
+    /// Falls linearly from 1.0 at the reference to 0.0 at `window` away
+    LinearWindow { window: chrono::Duration },
+    /// No temporal signal; every node scores 0.0
+    None,
+}
+
+/// Which timestamp of a node the temporal score is computed from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum TemporalField {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    /// Top-level metadata key holding an RFC 3339 timestamp or `YYYY-MM-DD` date.
+    /// Date nodes don't persist metadata, so for them the node content is used.
+    Metadata(String),
+}
+
+impl Default for HybridSearchConfig {
+    fn default() -> Self {
+        Self {
+            semantic_weight: 0.7,
+            structural_weight: 0.2,
+            temporal_weight: 0.1,
+            cross_modal_weight: 0.1,
+            individual_weight: 0.6,
+            contextual_weight: 0.3,
+            hierarchical_weight: 0.1,
+            max_results: 50,
+            min_similarity_threshold: 0.1,
+            enable_cross_modal: true,
+            enable_cross_level_fusion: true,
+            search_timeout_ms: 2000,
+            anchor_node_ids: Vec::new(),
+            max_structural_hops: 3,
+            temporal_scoring: TemporalScoring::default(),
+            temporal_field: TemporalField::default(),
+            temporal_reference: None,
+            fusion_strategy: FusionStrategy::default(),
+            keyword_weight: 0.3,
+            on_timeout: SearchTimeoutBehavior::default(),
+            cancellation: None,
+            diversity: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -152,11 +427,62 @@ pub struct SearchResult {
     pub node: Node,
     pub score: f32,
     pub relevance_factors: RelevanceFactors,
+    pub explanation: SearchExplanation,
+}
+
+/// Why a search result matched and how its score was computed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchExplanation {
+    /// Every signal that went into `SearchResult::score`
+    pub components: Vec<ScoreComponent>,
+    /// How `components` were merged; `None` when the score is their plain sum
+    pub fusion: Option<FusionStrategy>,
+    /// Keyword matches in the node content
+    pub highlights: Vec<Highlight>,
+    /// Conditions candidates had to pass: LanceDB SQL predicates and score thresholds
+    pub filters: Vec<String>,
+    /// The search hit its deadline, so only the candidates gathered in time were ranked
+    pub truncated: bool,
+}
+
+/// One signal's part in a search score
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreComponent {
+    pub signal: ScoreSignal,
+    /// Value the signal entered the computation with (similarity, BM25, 0-1 score)
+    pub score: f32,
+    pub weight: f32,
+    /// 1-based position in the signal's own ranking, for ranked retrieval
+    pub rank: Option<usize>,
+    /// Amount added to `SearchResult::score`
+    pub contribution: f32,
+}
+
+/// Source of a `ScoreComponent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreSignal {
+    Individual,
+    Contextual,
+    Hierarchical,
+    Keyword,
+    Structural,
+    Temporal,
+    CrossModal,
+}
+
+/// Matched span of `node.content` in characters (not bytes), `end` exclusive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+    /// Query term, phrase or `prefix*` that matched
+    pub term: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct RelevanceFactors {
-    pub semantic_score: f32,
+    pub semantic_score: f32, // Best embedding similarity; per-level scores are in the explanation
+    pub keyword_score: f32,  // BM25 of the full-text match, 0.0 when not matched by keywords
     pub structural_score: f32,
     pub temporal_score: f32,
     pub cross_modal_score: Option<f32>,
@@ -178,3 +504,84 @@ pub struct QueryEmbeddings {
     pub contextual: Option<Vec<f32>>,
     pub hierarchical: Option<Vec<f32>>,
 }
+
+/// What `retrieve_context` gathers around each hit
+#[derive(Debug, Clone)]
+pub struct ContextOptions {
+    pub max_hits: usize,         // Nearest neighbours to build context for
+    pub include_ancestors: bool, // Parent chain up to the root
+    pub sibling_window: usize,   // Siblings on either side, in `before_sibling_id` order
+    pub include_children: bool,  // Direct children, in `before_sibling_id` order
+    pub budget: ContextBudget,   // Size limit for the whole window
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            max_hits: 5,
+            include_ancestors: true,
+            sibling_window: 1,
+            include_children: true,
+            budget: ContextBudget::Unlimited,
+        }
+    }
+}
+
+/// Size limit for a context window, measured on node content
+///
+/// Hits are admitted first, best first, then each hit's context: ancestors
+/// nearest first, siblings nearest first, then children. Assembly stops at the
+/// first node that doesn't fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContextBudget {
+    #[default]
+    Unlimited,
+    Characters(usize),
+    /// Estimated at one token per four characters
+    Tokens(usize),
+}
+
+/// Hits with their surrounding hierarchy, each node included at most once
+#[derive(Debug, Clone, Default)]
+pub struct ContextWindow {
+    pub hits: Vec<ContextHit>,
+    /// Budget units used, 0 for `ContextBudget::Unlimited`
+    pub used: usize,
+    /// Some hits or context nodes were left out to stay within the budget
+    pub truncated: bool,
+}
+
+/// One search hit and the context kept for it
+///
+/// Nodes already shown with an earlier hit (or that are hits themselves) are
+/// left out, so lists can be shorter than requested.
+#[derive(Debug, Clone)]
+pub struct ContextHit {
+    pub node: Node,
+    pub score: f32,
+    pub ancestors: Vec<Node>,       // Root first, parent last
+    pub siblings_before: Vec<Node>, // Document order
+    pub siblings_after: Vec<Node>,  // Document order
+    pub children: Vec<Node>,        // Document order
+}
+
+/// A typed edge between two nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relationship {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub rel_type: String,
+    pub properties: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+/// Which edges of a node `get_relationships` returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RelationshipDirection {
+    /// Edges starting at the node
+    #[default]
+    Outgoing,
+    /// Edges ending at the node
+    Incoming,
+    Both,
+}
@@ -83,6 +83,47 @@ pub enum DataStoreError {
 
     #[error("Feature not implemented: {0}")]
     NotImplemented(String),
+
+    #[error("Search timed out after {elapsed_ms}ms, limit is {timeout_ms}ms")]
+    SearchTimeout { elapsed_ms: u64, timeout_ms: u64 },
+
+    #[error("Search cancelled")]
+    SearchCancelled,
+
+    #[error("Sibling cycle under parent {parent_id}: {}", .node_ids.join(" -> "))]
+    SiblingCycle {
+        parent_id: String,
+        node_ids: Vec<String>,
+    },
+
+    #[error("Node {node_id} under parent {parent_id} follows missing sibling {before_sibling_id}")]
+    DanglingSiblingLink {
+        parent_id: String,
+        node_id: String,
+        before_sibling_id: String,
+    },
+
+    #[error("Parent {parent_id} has more than one first child: {}", .node_ids.join(", "))]
+    DuplicateSiblingHeads {
+        parent_id: String,
+        node_ids: Vec<String>,
+    },
+
+    #[error(
+        "Nodes {} under parent {parent_id} all follow sibling {before_sibling_id}",
+        .node_ids.join(", ")
+    )]
+    DuplicateSiblingLinks {
+        parent_id: String,
+        before_sibling_id: String,
+        node_ids: Vec<String>,
+    },
+
+    #[error("Parent {parent_id} of node {node_id} not found")]
+    ParentNotFound { node_id: String, parent_id: String },
+
+    #[error("Parent cycle: {}", .node_ids.join(" -> "))]
+    ParentCycle { node_ids: Vec<String> },
 }
 
 impl From<DataStoreError> for NodeSpaceError {
@@ -264,6 +305,40 @@ impl From<DataStoreError> for NodeSpaceError {
                 message: err.to_string(),
                 service: "data-store".to_string(),
             },
+
+            // Search budget errors
+            DataStoreError::SearchTimeout { timeout_ms, .. } => {
+                NodeSpaceError::Database(DatabaseError::QueryTimeout {
+                    seconds: timeout_ms.div_ceil(1000),
+                    query: "hybrid_search".to_string(),
+                    suggested_limit: None,
+                })
+            }
+            DataStoreError::SearchCancelled => NodeSpaceError::InternalError {
+                message: err.to_string(),
+                service: "data-store".to_string(),
+            },
+
+            // Sibling order errors
+            DataStoreError::SiblingCycle { .. }
+            | DataStoreError::DanglingSiblingLink { .. }
+            | DataStoreError::DuplicateSiblingHeads { .. }
+            | DataStoreError::DuplicateSiblingLinks { .. } => {
+                NodeSpaceError::Validation(ValidationError::SchemaValidationFailed {
+                    schema_path: "before_sibling_id".to_string(),
+                    violations: vec![err.to_string()],
+                    schema_version: "1.0".to_string(),
+                })
+            }
+
+            // Parent chain errors
+            DataStoreError::ParentNotFound { .. } | DataStoreError::ParentCycle { .. } => {
+                NodeSpaceError::Validation(ValidationError::SchemaValidationFailed {
+                    schema_path: "parent_id".to_string(),
+                    violations: vec![err.to_string()],
+                    schema_version: "1.0".to_string(),
+                })
+            }
         }
     }
 }
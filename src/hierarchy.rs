@@ -0,0 +1,538 @@
+//! Pure helpers for walking the node hierarchy
+//!
+//! Sibling order comes from `before_sibling_id` back links: a node's
+//! `before_sibling_id` is the sibling directly before it, and the first child
+//! has none. `LanceDataStore` fetches the rows; these functions arrange them.
+
+use crate::data_store::{ContextBudget, ContextOptions};
+use crate::error::DataStoreError;
+use crate::lance_data_store_simple::UniversalNode;
+use std::collections::{HashMap, HashSet};
+
+/// Rows that carry sibling links
+///
+/// Lets the ordering helpers work on full rows and on the link-only rows of
+/// an integrity scan alike.
+pub(crate) trait SiblingLinks {
+    fn node_id(&self) -> &str;
+    fn before_sibling(&self) -> Option<&str>;
+}
+
+impl SiblingLinks for UniversalNode {
+    fn node_id(&self) -> &str {
+        &self.id
+    }
+
+    fn before_sibling(&self) -> Option<&str> {
+        self.before_sibling_id.as_deref()
+    }
+}
+
+impl<T: SiblingLinks> SiblingLinks for &T {
+    fn node_id(&self) -> &str {
+        (*self).node_id()
+    }
+
+    fn before_sibling(&self) -> Option<&str> {
+        (*self).before_sibling()
+    }
+}
+
+/// Children of `parent_id` in `before_sibling_id` order
+///
+/// The links must form one chain: exactly one first child, no link to a node
+/// outside `siblings`, no two nodes after the same sibling, and no cycles.
+pub(crate) fn order_siblings<T: SiblingLinks>(
+    parent_id: &str,
+    siblings: Vec<T>,
+) -> Result<Vec<T>, DataStoreError> {
+    let positions: HashMap<&str, usize> = siblings
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.node_id(), index))
+        .collect();
+    let mut followers: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut heads = Vec::new();
+    for (index, node) in siblings.iter().enumerate() {
+        match node.before_sibling() {
+            None => heads.push(index),
+            Some(before) if positions.contains_key(before) => {
+                followers.entry(before).or_default().push(index)
+            }
+            Some(before) => {
+                return Err(DataStoreError::DanglingSiblingLink {
+                    parent_id: parent_id.to_string(),
+                    node_id: node.node_id().to_string(),
+                    before_sibling_id: before.to_string(),
+                })
+            }
+        }
+    }
+
+    let ids_of = |indices: &[usize]| -> Vec<String> {
+        indices
+            .iter()
+            .map(|index| siblings[*index].node_id().to_string())
+            .collect()
+    };
+    if let Some((before, indices)) = siblings
+        .iter()
+        .filter_map(|node| followers.get_key_value(node.node_id()))
+        .find(|(_, indices)| indices.len() > 1)
+    {
+        return Err(DataStoreError::DuplicateSiblingLinks {
+            parent_id: parent_id.to_string(),
+            before_sibling_id: before.to_string(),
+            node_ids: ids_of(indices),
+        });
+    }
+    if heads.len() > 1 {
+        return Err(DataStoreError::DuplicateSiblingHeads {
+            parent_id: parent_id.to_string(),
+            node_ids: ids_of(&heads),
+        });
+    }
+
+    // With one follower per node, the chain from the head is the whole list
+    // unless some nodes loop among themselves
+    let mut order = Vec::with_capacity(siblings.len());
+    let mut current = heads.first().copied();
+    while let Some(index) = current {
+        order.push(index);
+        current = followers
+            .get(siblings[index].node_id())
+            .map(|next| next[0]);
+    }
+    if order.len() < siblings.len() {
+        let mut on_chain = vec![false; siblings.len()];
+        for index in &order {
+            on_chain[*index] = true;
+        }
+        // Walk back from any node off the chain until a node repeats
+        let mut path: Vec<usize> = Vec::new();
+        let mut index = (0..siblings.len())
+            .find(|index| !on_chain[*index])
+            .unwrap_or_default();
+        while !path.contains(&index) {
+            path.push(index);
+            let before = siblings[index].before_sibling().unwrap_or_default();
+            index = positions[before];
+        }
+        let start = path
+            .iter()
+            .position(|seen| *seen == index)
+            .unwrap_or_default();
+        let mut cycle = path.split_off(start);
+        cycle.reverse();
+        return Err(DataStoreError::SiblingCycle {
+            parent_id: parent_id.to_string(),
+            node_ids: ids_of(&cycle),
+        });
+    }
+
+    let mut siblings: Vec<Option<T>> = siblings.into_iter().map(Some).collect();
+    Ok(order
+        .into_iter()
+        .filter_map(|index| siblings[index].take())
+        .collect())
+}
+
+/// Point each node in `order` at its place among the children of `parent_id`
+///
+/// Sets `parent_id` and `before_sibling_id` on the rows in `rows` and returns
+/// the ids whose links actually changed, so only those need writing.
+pub(crate) fn relink_siblings(
+    parent_id: &str,
+    order: &[String],
+    rows: &mut HashMap<String, UniversalNode>,
+) -> Vec<String> {
+    let mut changed = Vec::new();
+    for (position, id) in order.iter().enumerate() {
+        let Some(row) = rows.get_mut(id) else {
+            continue;
+        };
+        let before = position.checked_sub(1).map(|index| order[index].clone());
+        if row.parent_id.as_deref() != Some(parent_id) || row.before_sibling_id != before {
+            row.parent_id = Some(parent_id.to_string());
+            row.before_sibling_id = before;
+            changed.push(id.clone());
+        }
+    }
+    changed
+}
+
+/// Siblings in `before_sibling_id` order, best effort
+///
+/// Chains start at nodes whose `before_sibling_id` is unset or points outside
+/// `siblings`. Nodes no chain reaches (cycles) are appended in storage order,
+/// so every sibling is returned exactly once.
+pub(crate) fn order_siblings_lenient<T: SiblingLinks>(siblings: Vec<T>) -> Vec<T> {
+    let ids: HashSet<&str> = siblings.iter().map(|node| node.node_id()).collect();
+    let mut followers: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut heads = Vec::new();
+    for (index, node) in siblings.iter().enumerate() {
+        match node.before_sibling() {
+            Some(before) if ids.contains(before) => {
+                followers.entry(before).or_default().push(index)
+            }
+            _ => heads.push(index),
+        }
+    }
+
+    let mut order = Vec::with_capacity(siblings.len());
+    let mut visited = vec![false; siblings.len()];
+    for head in heads {
+        let mut stack = vec![head];
+        while let Some(index) = stack.pop() {
+            if std::mem::replace(&mut visited[index], true) {
+                continue;
+            }
+            order.push(index);
+            if let Some(next) = followers.get(siblings[index].node_id()) {
+                // Reversed so the first follower in storage order is walked first
+                stack.extend(next.iter().rev());
+            }
+        }
+    }
+    order.extend((0..siblings.len()).filter(|index| !visited[*index]));
+
+    let mut siblings: Vec<Option<T>> = siblings.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|index| siblings[index].take())
+        .collect()
+}
+
+/// Node ids chosen for one hit of a context window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ContextSelection {
+    pub hit: String,
+    pub score: f32,
+    pub ancestors: Vec<String>,
+    pub siblings_before: Vec<String>,
+    pub siblings_after: Vec<String>,
+    pub children: Vec<String>,
+}
+
+/// Budget units of one node's content
+fn content_cost(budget: ContextBudget, content: &str) -> usize {
+    match budget {
+        ContextBudget::Unlimited => 0,
+        ContextBudget::Characters(_) => content.chars().count(),
+        ContextBudget::Tokens(_) => content.chars().count().div_ceil(4),
+    }
+}
+
+/// Running total of a context window against its budget
+struct BudgetMeter {
+    budget: ContextBudget,
+    used: usize,
+    exhausted: bool,
+}
+
+impl BudgetMeter {
+    /// Count the node if it fits; once one doesn't, nothing else is admitted
+    fn admit(&mut self, content: &str) -> bool {
+        let limit = match self.budget {
+            ContextBudget::Unlimited => return true,
+            ContextBudget::Characters(limit) | ContextBudget::Tokens(limit) => limit,
+        };
+        if self.exhausted {
+            return false;
+        }
+        let cost = content_cost(self.budget, content);
+        if self.used + cost > limit {
+            self.exhausted = true;
+            return false;
+        }
+        self.used += cost;
+        true
+    }
+}
+
+/// Pick the hits and context nodes of a window
+///
+/// `nodes` holds every fetched row by id and `children` each fetched parent's
+/// child ids in sibling order. Returns the selections, budget units used and
+/// whether anything was cut for the budget.
+pub(crate) fn assemble_context(
+    hits: &[(String, f32)],
+    nodes: &HashMap<String, UniversalNode>,
+    children: &HashMap<String, Vec<String>>,
+    options: &ContextOptions,
+) -> (Vec<ContextSelection>, usize, bool) {
+    let mut meter = BudgetMeter {
+        budget: options.budget,
+        used: 0,
+        exhausted: false,
+    };
+    let content = |id: &str| nodes.get(id).map_or("", |node| node.content.as_str());
+
+    // Hits first, so context for the best hit can't crowd out the next hit
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut selections = Vec::new();
+    for (id, score) in hits {
+        if !nodes.contains_key(id) || !seen.insert(id.as_str()) {
+            continue;
+        }
+        if !meter.admit(content(id)) {
+            break;
+        }
+        selections.push(ContextSelection {
+            hit: id.clone(),
+            score: *score,
+            ..ContextSelection::default()
+        });
+    }
+    // Hits that didn't fit are still hits, not context for earlier ones
+    seen.extend(hits.iter().map(|(id, _)| id.as_str()));
+
+    for selection in selections.iter_mut() {
+        let Some(hit) = nodes.get(&selection.hit) else {
+            continue;
+        };
+
+        if options.include_ancestors {
+            let mut parent = hit.parent_id.as_deref();
+            let mut walked = HashSet::new();
+            while let Some(parent_id) = parent.filter(|id| walked.insert(*id)) {
+                let Some(parent_node) = nodes.get(parent_id) else {
+                    break;
+                };
+                if seen.insert(parent_id) && meter.admit(&parent_node.content) {
+                    selection.ancestors.push(parent_id.to_string());
+                }
+                parent = parent_node.parent_id.as_deref();
+            }
+            selection.ancestors.reverse();
+        }
+
+        if options.sibling_window > 0 {
+            let siblings = hit
+                .parent_id
+                .as_ref()
+                .and_then(|parent_id| children.get(parent_id))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            if let Some(position) = siblings.iter().position(|id| *id == selection.hit) {
+                for distance in 1..=options.sibling_window {
+                    let before = position.checked_sub(distance).map(|index| &siblings[index]);
+                    let after = siblings.get(position + distance);
+                    if let Some(id) = before {
+                        if seen.insert(id) && meter.admit(content(id)) {
+                            selection.siblings_before.push(id.clone());
+                        }
+                    }
+                    if let Some(id) = after {
+                        if seen.insert(id) && meter.admit(content(id)) {
+                            selection.siblings_after.push(id.clone());
+                        }
+                    }
+                }
+                selection.siblings_before.reverse();
+            }
+        }
+
+        if options.include_children {
+            for id in children.get(&selection.hit).into_iter().flatten() {
+                if seen.insert(id) && meter.admit(content(id)) {
+                    selection.children.push(id.clone());
+                }
+            }
+        }
+    }
+
+    (selections, meter.used, meter.exhausted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lance_data_store_simple::test_universal_node;
+
+    fn node(id: &str, parent: Option<&str>, before: Option<&str>, content: &str) -> UniversalNode {
+        UniversalNode {
+            content: content.to_string(),
+            parent_id: parent.map(str::to_string),
+            before_sibling_id: before.map(str::to_string),
+            ..test_universal_node(id)
+        }
+    }
+
+    fn ids(nodes: &[UniversalNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_order_siblings_reports_broken_links() {
+        let ordered = order_siblings(
+            "p",
+            vec![
+                node("c", Some("p"), Some("b"), ""),
+                node("a", Some("p"), None, ""),
+                node("b", Some("p"), Some("a"), ""),
+            ],
+        )
+        .unwrap();
+        assert_eq!(ids(&ordered), vec!["a", "b", "c"]);
+        assert!(order_siblings("p", vec![]).unwrap().is_empty());
+
+        let dangling = order_siblings(
+            "p",
+            vec![
+                node("a", Some("p"), None, ""),
+                node("b", Some("p"), Some("gone"), ""),
+            ],
+        );
+        assert!(matches!(
+            dangling,
+            Err(DataStoreError::DanglingSiblingLink { node_id, before_sibling_id, .. })
+                if node_id == "b" && before_sibling_id == "gone"
+        ));
+
+        let heads = order_siblings(
+            "p",
+            vec![
+                node("a", Some("p"), None, ""),
+                node("b", Some("p"), None, ""),
+            ],
+        );
+        assert!(matches!(
+            heads,
+            Err(DataStoreError::DuplicateSiblingHeads { node_ids, .. }) if node_ids == ["a", "b"]
+        ));
+
+        let fork = order_siblings(
+            "p",
+            vec![
+                node("a", Some("p"), None, ""),
+                node("b", Some("p"), Some("a"), ""),
+                node("c", Some("p"), Some("a"), ""),
+            ],
+        );
+        assert!(matches!(
+            fork,
+            Err(DataStoreError::DuplicateSiblingLinks { before_sibling_id, node_ids, .. })
+                if before_sibling_id == "a" && node_ids == ["b", "c"]
+        ));
+
+        // a is a valid chain on its own; x and y point at each other
+        let cycle = order_siblings(
+            "p",
+            vec![
+                node("a", Some("p"), None, ""),
+                node("x", Some("p"), Some("y"), ""),
+                node("y", Some("p"), Some("x"), ""),
+            ],
+        );
+        assert!(matches!(
+            cycle,
+            Err(DataStoreError::SiblingCycle { node_ids, .. }) if node_ids == ["y", "x"]
+        ));
+    }
+
+    #[test]
+    fn test_relink_siblings() {
+        let mut rows: HashMap<String, UniversalNode> = [
+            node("a", Some("p"), None, ""),
+            node("b", Some("p"), Some("a"), ""),
+            node("c", Some("q"), None, ""),
+        ]
+        .into_iter()
+        .map(|node| (node.id.clone(), node))
+        .collect();
+
+        // Move c from q to between a and b
+        let order = vec!["a".to_string(), "c".to_string(), "b".to_string()];
+        let changed = relink_siblings("p", &order, &mut rows);
+        assert_eq!(changed, vec!["c", "b"]);
+        assert_eq!(rows["c"].parent_id.as_deref(), Some("p"));
+        assert_eq!(rows["c"].before_sibling_id.as_deref(), Some("a"));
+        assert_eq!(rows["b"].before_sibling_id.as_deref(), Some("c"));
+        assert!(relink_siblings("p", &order, &mut rows).is_empty());
+    }
+
+    #[test]
+    fn test_order_siblings_lenient() {
+        // Stored out of order: c follows b follows a
+        let ordered = order_siblings_lenient(vec![
+            node("c", Some("p"), Some("b"), ""),
+            node("a", Some("p"), None, ""),
+            node("b", Some("p"), Some("a"), ""),
+        ]);
+        assert_eq!(ids(&ordered), vec!["a", "b", "c"]);
+
+        // A dangling link starts its own chain; a cycle is appended at the end
+        let ordered = order_siblings_lenient(vec![
+            node("x", Some("p"), Some("y"), ""),
+            node("y", Some("p"), Some("x"), ""),
+            node("a", Some("p"), Some("gone"), ""),
+            node("b", Some("p"), Some("a"), ""),
+        ]);
+        assert_eq!(ids(&ordered), vec!["a", "b", "x", "y"]);
+    }
+
+    #[test]
+    fn test_assemble_context_dedups_and_trims() {
+        let nodes: HashMap<String, UniversalNode> = [
+            node("root", None, None, "Root"),
+            node("s1", Some("root"), None, "First"),
+            node("hit1", Some("root"), Some("s1"), "Hit one"),
+            node("hit2", Some("root"), Some("hit1"), "Hit two"),
+            node("s4", Some("root"), Some("hit2"), "Fourth"),
+            node("kid", Some("hit1"), None, "Child"),
+        ]
+        .into_iter()
+        .map(|node| (node.id.clone(), node))
+        .collect();
+        let children: HashMap<String, Vec<String>> = [
+            ("root", vec!["s1", "hit1", "hit2", "s4"]),
+            ("hit1", vec!["kid"]),
+        ]
+        .into_iter()
+        .map(|(parent, ids)| {
+            (
+                parent.to_string(),
+                ids.into_iter().map(str::to_string).collect(),
+            )
+        })
+        .collect();
+        let hits = vec![("hit1".to_string(), 0.9), ("hit2".to_string(), 0.8)];
+
+        let (selections, used, truncated) =
+            assemble_context(&hits, &nodes, &children, &ContextOptions::default());
+        assert_eq!(used, 0);
+        assert!(!truncated);
+        assert_eq!(selections[0].ancestors, vec!["root"]);
+        assert_eq!(selections[0].siblings_before, vec!["s1"]);
+        // hit2 is a hit itself, so it isn't repeated as hit1's sibling
+        assert!(selections[0].siblings_after.is_empty());
+        assert_eq!(selections[0].children, vec!["kid"]);
+        // The shared root was already shown with hit1
+        assert!(selections[1].ancestors.is_empty());
+        assert!(selections[1].siblings_before.is_empty());
+        assert_eq!(selections[1].siblings_after, vec!["s4"]);
+
+        // Both hits (14 chars) plus the root (4) fit; "First" doesn't
+        let options = ContextOptions {
+            budget: ContextBudget::Characters(20),
+            ..ContextOptions::default()
+        };
+        let (selections, used, truncated) = assemble_context(&hits, &nodes, &children, &options);
+        assert_eq!(selections.len(), 2);
+        assert_eq!(used, 18);
+        assert!(truncated);
+        assert_eq!(selections[0].ancestors, vec!["root"]);
+        assert!(selections[0].siblings_before.is_empty());
+        assert!(selections[1].siblings_after.is_empty());
+
+        let options = ContextOptions {
+            budget: ContextBudget::Tokens(2),
+            ..ContextOptions::default()
+        };
+        let (selections, used, truncated) = assemble_context(&hits, &nodes, &children, &options);
+        assert_eq!(selections.len(), 1);
+        assert_eq!(used, 2);
+        assert!(truncated);
+    }
+}
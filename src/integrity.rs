@@ -0,0 +1,547 @@
+//! Hierarchy consistency checks and repairs
+//!
+//! `LanceDataStore::check_integrity` and `repair` stream the table once and
+//! keep only each row's `HierarchyLinks`. `parent_id` is the source of truth:
+//! `children_ids`, `before_sibling_id` chains and `root_id` are checked
+//! against it and rebuilt from it.
+
+use crate::hierarchy::{self, SiblingLinks};
+use crate::lance_data_store_simple::UniversalNode;
+use nodespace_core_types::NodeId;
+use std::collections::{HashMap, HashSet};
+
+/// Every inconsistency found by `LanceDataStore::check_integrity`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub nodes_scanned: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// One hierarchy inconsistency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// `parent_id` names a node that doesn't exist
+    OrphanedNode { node_id: String, parent_id: String },
+    /// Following `parent_id` from the first node leads back to it
+    ParentCycle { node_ids: Vec<String> },
+    /// A parent's `children_ids` disagrees with its children's `parent_id`
+    ChildrenMismatch {
+        parent_id: String,
+        /// Children whose `parent_id` points here but aren't listed
+        missing: Vec<String>,
+        /// Listed ids whose `parent_id` points elsewhere, or that don't exist
+        unexpected: Vec<String>,
+    },
+    /// The children's `before_sibling_id` links don't form one chain
+    BrokenSiblingChain { parent_id: String, detail: String },
+    /// `root_id` differs from the root of the node's parent chain
+    StaleRootId {
+        node_id: String,
+        root_id: Option<String>,
+        expected: Option<String>,
+    },
+}
+
+/// What `LanceDataStore::repair` does with nodes whose parent is missing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrphanRepair {
+    /// Clear `parent_id`, making each orphan the root of its own tree
+    #[default]
+    Detach,
+    /// Delete orphans along with their descendants
+    Delete,
+    /// Leave orphans pointing at the missing parent
+    Keep,
+}
+
+/// How `LanceDataStore::repair` resolves what it can't rebuild from `parent_id`
+///
+/// Parent cycles are always broken by detaching the first node of the cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairPolicy {
+    pub orphans: OrphanRepair,
+}
+
+/// Outcome of `LanceDataStore::repair`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Issues found before repairing
+    pub issues: Vec<IntegrityIssue>,
+    pub updated: Vec<NodeId>,
+    pub deleted: Vec<NodeId>,
+}
+
+/// The hierarchy fields of one row, all an integrity scan keeps in memory
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HierarchyLinks {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub before_sibling_id: Option<String>,
+    pub children_ids: Vec<String>,
+    pub root_id: Option<String>,
+}
+
+impl HierarchyLinks {
+    /// Copy these links onto the full row
+    pub fn apply(self, row: &mut UniversalNode) {
+        row.parent_id = self.parent_id;
+        row.before_sibling_id = self.before_sibling_id;
+        row.children_ids = self.children_ids;
+        row.root_id = self.root_id;
+    }
+}
+
+impl From<&UniversalNode> for HierarchyLinks {
+    fn from(node: &UniversalNode) -> Self {
+        Self {
+            id: node.id.clone(),
+            parent_id: node.parent_id.clone(),
+            before_sibling_id: node.before_sibling_id.clone(),
+            children_ids: node.children_ids.clone(),
+            root_id: node.root_id.clone(),
+        }
+    }
+}
+
+impl SiblingLinks for HierarchyLinks {
+    fn node_id(&self) -> &str {
+        &self.id
+    }
+
+    fn before_sibling(&self) -> Option<&str> {
+        self.before_sibling_id.as_deref()
+    }
+}
+
+/// Rows changed and deleted by a repair
+#[derive(Debug, Default)]
+pub(crate) struct RepairPlan {
+    pub updated: Vec<HierarchyLinks>,
+    pub deleted: Vec<String>,
+}
+
+/// Children grouped by `parent_id`, parents in order of first appearance
+fn children_by_parent(nodes: &[HierarchyLinks]) -> Vec<(&str, Vec<&HierarchyLinks>)> {
+    let mut groups: Vec<(&str, Vec<&HierarchyLinks>)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for node in nodes {
+        let Some(parent_id) = node.parent_id.as_deref() else {
+            continue;
+        };
+        let position = *positions.entry(parent_id).or_insert_with(|| {
+            groups.push((parent_id, Vec::new()));
+            groups.len() - 1
+        });
+        groups[position].1.push(node);
+    }
+    groups
+}
+
+/// Every `parent_id` cycle, each starting at the first of its nodes scanned
+fn parent_cycles(
+    nodes: &[HierarchyLinks],
+    by_id: &HashMap<&str, &HierarchyLinks>,
+) -> Vec<Vec<String>> {
+    let mut done: HashSet<&str> = HashSet::new();
+    let mut cycles = Vec::new();
+    for node in nodes {
+        let mut path: Vec<&str> = Vec::new();
+        let mut current = Some(node.id.as_str());
+        while let Some(id) = current.filter(|id| !done.contains(id)) {
+            if let Some(start) = path.iter().position(|seen| *seen == id) {
+                cycles.push(path[start..].iter().map(|id| id.to_string()).collect());
+                break;
+            }
+            path.push(id);
+            current = by_id.get(id).and_then(|node| node.parent_id.as_deref());
+        }
+        done.extend(path);
+    }
+    cycles
+}
+
+/// The `root_id` each node should have, by the store's derivation rule
+///
+/// A child belongs to its parent's root, or to the parent itself when the
+/// parent is a root. Nodes at the top of a chain (no parent, or a missing
+/// one) keep their own `root_id`. Nodes on or under a cycle are left out.
+fn expected_roots<'a>(
+    nodes: &'a [HierarchyLinks],
+    by_id: &HashMap<&str, &'a HierarchyLinks>,
+) -> HashMap<&'a str, Option<String>> {
+    let mut expected: HashMap<&str, Option<String>> = HashMap::new();
+    for node in nodes {
+        let mut path: Vec<&HierarchyLinks> = Vec::new();
+        let mut on_path: HashSet<&str> = HashSet::new();
+        let mut current = node;
+        let anchor = loop {
+            if expected.contains_key(current.id.as_str()) {
+                break Some(current);
+            }
+            if !on_path.insert(current.id.as_str()) {
+                break None;
+            }
+            match current
+                .parent_id
+                .as_deref()
+                .and_then(|id| by_id.get(id).copied())
+            {
+                Some(parent) => {
+                    path.push(current);
+                    current = parent;
+                }
+                None => {
+                    expected.insert(current.id.as_str(), current.root_id.clone());
+                    break Some(current);
+                }
+            }
+        };
+        let Some(mut above) = anchor else {
+            continue;
+        };
+        for node in path.into_iter().rev() {
+            let root = expected[above.id.as_str()]
+                .clone()
+                .unwrap_or_else(|| above.id.clone());
+            expected.insert(node.id.as_str(), Some(root));
+            above = node;
+        }
+    }
+    expected
+}
+
+/// Find every inconsistency among the scanned links
+pub(crate) fn check_links(nodes: &[HierarchyLinks]) -> Vec<IntegrityIssue> {
+    let by_id: HashMap<&str, &HierarchyLinks> =
+        nodes.iter().map(|node| (node.id.as_str(), node)).collect();
+    let groups = children_by_parent(nodes);
+    let mut issues = Vec::new();
+
+    for node in nodes {
+        if let Some(parent_id) = node.parent_id.as_deref() {
+            if !by_id.contains_key(parent_id) {
+                issues.push(IntegrityIssue::OrphanedNode {
+                    node_id: node.id.clone(),
+                    parent_id: parent_id.to_string(),
+                });
+            }
+        }
+    }
+    issues.extend(
+        parent_cycles(nodes, &by_id)
+            .into_iter()
+            .map(|node_ids| IntegrityIssue::ParentCycle { node_ids }),
+    );
+
+    let children: HashMap<&str, &Vec<&HierarchyLinks>> = groups
+        .iter()
+        .map(|(parent_id, children)| (*parent_id, children))
+        .collect();
+    for node in nodes {
+        let actual = children.get(node.id.as_str()).copied();
+        let actual_ids: HashSet<&str> = actual
+            .into_iter()
+            .flatten()
+            .map(|child| child.id.as_str())
+            .collect();
+        let listed: HashSet<&str> = node.children_ids.iter().map(String::as_str).collect();
+        let missing: Vec<String> = actual
+            .into_iter()
+            .flatten()
+            .filter(|child| !listed.contains(child.id.as_str()))
+            .map(|child| child.id.clone())
+            .collect();
+        let unexpected: Vec<String> = node
+            .children_ids
+            .iter()
+            .filter(|id| !actual_ids.contains(id.as_str()))
+            .cloned()
+            .collect();
+        if !missing.is_empty() || !unexpected.is_empty() {
+            issues.push(IntegrityIssue::ChildrenMismatch {
+                parent_id: node.id.clone(),
+                missing,
+                unexpected,
+            });
+        }
+    }
+
+    for (parent_id, children) in &groups {
+        if let Err(err) = hierarchy::order_siblings(parent_id, children.clone()) {
+            issues.push(IntegrityIssue::BrokenSiblingChain {
+                parent_id: parent_id.to_string(),
+                detail: err.to_string(),
+            });
+        }
+    }
+
+    let expected = expected_roots(nodes, &by_id);
+    for node in nodes {
+        let Some(root) = expected.get(node.id.as_str()) else {
+            continue;
+        };
+        if node.parent_id.is_some() && node.root_id != *root {
+            issues.push(IntegrityIssue::StaleRootId {
+                node_id: node.id.clone(),
+                root_id: node.root_id.clone(),
+                expected: root.clone(),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Rebuild the links of `nodes` from their `parent_id`s
+///
+/// Cycles are broken and orphans handled first, then sibling chains,
+/// `children_ids` and `root_id`s are rebuilt. Returns only the rows that
+/// changed, in scan order, and the ids to delete.
+pub(crate) fn plan_repair(mut nodes: Vec<HierarchyLinks>, policy: &RepairPolicy) -> RepairPlan {
+    let original: HashMap<String, HierarchyLinks> = nodes
+        .iter()
+        .map(|node| (node.id.clone(), node.clone()))
+        .collect();
+    let detach = |node: &mut HierarchyLinks| {
+        node.parent_id = None;
+        node.before_sibling_id = None;
+        node.root_id = None;
+    };
+
+    let by_id: HashMap<&str, &HierarchyLinks> =
+        nodes.iter().map(|node| (node.id.as_str(), node)).collect();
+    let cycle_heads: HashSet<String> = parent_cycles(&nodes, &by_id)
+        .into_iter()
+        .filter_map(|cycle| cycle.into_iter().next())
+        .collect();
+    for node in nodes.iter_mut() {
+        if cycle_heads.contains(&node.id) {
+            detach(node);
+        }
+    }
+
+    let ids: HashSet<String> = nodes.iter().map(|node| node.id.clone()).collect();
+    let orphans: HashSet<String> = nodes
+        .iter()
+        .filter(|node| matches!(&node.parent_id, Some(parent_id) if !ids.contains(parent_id)))
+        .map(|node| node.id.clone())
+        .collect();
+    let mut deleted = Vec::new();
+    match policy.orphans {
+        OrphanRepair::Detach => {
+            for node in nodes.iter_mut() {
+                if orphans.contains(&node.id) {
+                    detach(node);
+                }
+            }
+        }
+        OrphanRepair::Delete => {
+            let doomed: HashSet<String> = {
+                let children: HashMap<&str, Vec<&str>> = children_by_parent(&nodes)
+                    .into_iter()
+                    .map(|(parent_id, children)| {
+                        (
+                            parent_id,
+                            children.iter().map(|child| child.id.as_str()).collect(),
+                        )
+                    })
+                    .collect();
+                let mut doomed = HashSet::new();
+                for node in nodes.iter().filter(|node| orphans.contains(&node.id)) {
+                    let mut stack = vec![node.id.as_str()];
+                    while let Some(id) = stack.pop() {
+                        if doomed.insert(id.to_string()) {
+                            deleted.push(id.to_string());
+                            stack.extend(children.get(id).into_iter().flatten().rev().copied());
+                        }
+                    }
+                }
+                doomed
+            };
+            nodes.retain(|node| !doomed.contains(&node.id));
+        }
+        OrphanRepair::Keep => {}
+    }
+
+    // Sibling chains, then children_ids in that order
+    let mut before: HashMap<String, Option<String>> = HashMap::new();
+    let mut ordered_children: HashMap<String, Vec<String>> = HashMap::new();
+    for (parent_id, children) in children_by_parent(&nodes) {
+        let order: Vec<String> = match hierarchy::order_siblings(parent_id, children.clone()) {
+            Ok(ordered) => ordered.iter().map(|child| child.id.clone()).collect(),
+            Err(_) => {
+                let ordered = hierarchy::order_siblings_lenient(children);
+                let order: Vec<String> = ordered.iter().map(|child| child.id.clone()).collect();
+                for (position, id) in order.iter().enumerate() {
+                    let link = position.checked_sub(1).map(|index| order[index].clone());
+                    before.insert(id.clone(), link);
+                }
+                order
+            }
+        };
+        ordered_children.insert(parent_id.to_string(), order);
+    }
+    for node in nodes.iter_mut() {
+        if let Some(link) = before.remove(&node.id) {
+            node.before_sibling_id = link;
+        }
+        let actual = ordered_children.remove(&node.id).unwrap_or_default();
+        let listed: HashSet<&str> = node.children_ids.iter().map(String::as_str).collect();
+        let matches =
+            listed.len() == actual.len() && actual.iter().all(|id| listed.contains(id.as_str()));
+        if !matches {
+            node.children_ids = actual;
+        }
+    }
+
+    let by_id: HashMap<&str, &HierarchyLinks> =
+        nodes.iter().map(|node| (node.id.as_str(), node)).collect();
+    let roots: HashMap<String, Option<String>> = expected_roots(&nodes, &by_id)
+        .into_iter()
+        .map(|(id, root)| (id.to_string(), root))
+        .collect();
+    for node in nodes.iter_mut() {
+        if node.parent_id.is_some() {
+            if let Some(root) = roots.get(&node.id) {
+                node.root_id = root.clone();
+            }
+        }
+    }
+
+    RepairPlan {
+        updated: nodes
+            .into_iter()
+            .filter(|node| original.get(&node.id) != Some(node))
+            .collect(),
+        deleted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(
+        id: &str,
+        parent: Option<&str>,
+        before: Option<&str>,
+        root: Option<&str>,
+    ) -> HierarchyLinks {
+        HierarchyLinks {
+            id: id.to_string(),
+            parent_id: parent.map(str::to_string),
+            before_sibling_id: before.map(str::to_string),
+            children_ids: vec![],
+            root_id: root.map(str::to_string),
+        }
+    }
+
+    fn with_children(mut node: HierarchyLinks, children: &[&str]) -> HierarchyLinks {
+        node.children_ids = children.iter().map(|id| id.to_string()).collect();
+        node
+    }
+
+    #[test]
+    fn test_check_links_finds_each_issue() {
+        let clean = vec![
+            with_children(links("doc", None, None, None), &["a", "b"]),
+            links("a", Some("doc"), None, Some("doc")),
+            links("b", Some("doc"), Some("a"), Some("doc")),
+        ];
+        assert!(check_links(&clean).is_empty());
+
+        let nodes = vec![
+            // b isn't listed, ghost doesn't exist
+            with_children(links("doc", None, None, None), &["a", "ghost"]),
+            links("a", Some("doc"), None, Some("doc")),
+            // Second first child, and a root left over from another document
+            links("b", Some("doc"), None, Some("old")),
+            links("orphan", Some("gone"), None, None),
+            links("x", Some("y"), None, None),
+            links("y", Some("x"), None, None),
+        ];
+        let issues = check_links(&nodes);
+        assert_eq!(
+            issues[0],
+            IntegrityIssue::OrphanedNode {
+                node_id: "orphan".to_string(),
+                parent_id: "gone".to_string(),
+            }
+        );
+        assert_eq!(
+            issues[1],
+            IntegrityIssue::ParentCycle {
+                node_ids: vec!["x".to_string(), "y".to_string()],
+            }
+        );
+        assert_eq!(
+            issues[2],
+            IntegrityIssue::ChildrenMismatch {
+                parent_id: "doc".to_string(),
+                missing: vec!["b".to_string()],
+                unexpected: vec!["ghost".to_string()],
+            }
+        );
+        // x and y don't list each other as children either
+        assert!(issues[3..5]
+            .iter()
+            .all(|issue| matches!(issue, IntegrityIssue::ChildrenMismatch { .. })));
+        assert!(matches!(
+            &issues[5],
+            IntegrityIssue::BrokenSiblingChain { parent_id, .. } if parent_id == "doc"
+        ));
+        assert_eq!(
+            issues[6],
+            IntegrityIssue::StaleRootId {
+                node_id: "b".to_string(),
+                root_id: Some("old".to_string()),
+                expected: Some("doc".to_string()),
+            }
+        );
+        assert_eq!(issues.len(), 7);
+    }
+
+    #[test]
+    fn test_plan_repair_rebuilds_from_parent_id() {
+        let nodes = vec![
+            with_children(links("doc", None, None, None), &["ghost"]),
+            links("a", Some("doc"), None, None),
+            links("b", Some("doc"), None, None),
+            links("orphan", Some("gone"), None, Some("doc")),
+            links("leaf", Some("orphan"), None, Some("doc")),
+        ];
+
+        let plan = plan_repair(nodes.clone(), &RepairPolicy::default());
+        assert!(plan.deleted.is_empty());
+        let mut repaired = nodes.clone();
+        for node in repaired.iter_mut() {
+            if let Some(update) = plan.updated.iter().find(|update| update.id == node.id) {
+                *node = update.clone();
+            }
+        }
+        assert!(check_links(&repaired).is_empty());
+        let by_id: HashMap<&str, &HierarchyLinks> = repaired
+            .iter()
+            .map(|node| (node.id.as_str(), node))
+            .collect();
+        assert_eq!(by_id["doc"].children_ids, vec!["a", "b"]);
+        assert_eq!(by_id["b"].before_sibling_id.as_deref(), Some("a"));
+        assert_eq!(by_id["orphan"].parent_id, None);
+        assert_eq!(by_id["leaf"].root_id.as_deref(), Some("orphan"));
+
+        let plan = plan_repair(
+            nodes,
+            &RepairPolicy {
+                orphans: OrphanRepair::Delete,
+            },
+        );
+        assert_eq!(plan.deleted, vec!["orphan", "leaf"]);
+        assert!(plan.updated.iter().all(|node| node.id != "leaf"));
+    }
+}
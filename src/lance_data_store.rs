@@ -7,7 +7,10 @@
 use crate::data_store::DataStore;
 use crate::error::DataStoreError;
 use crate::performance::{OperationType, PerformanceConfig, PerformanceMonitor};
+use crate::query::{FilterMode, NodePage, NodeQuery, NodeStream, PageCursor};
+use crate::relationships;
 use crate::schema::lance_schema::{ContentType, ImageMetadata, NodeType};
+use crate::search_budget::SearchBudget;
 use arrow_array::builder::{ListBuilder, StringBuilder};
 use arrow_array::{
     Array, FixedSizeListArray, Float32Array, ListArray, RecordBatch, RecordBatchIterator,
@@ -838,10 +841,11 @@ impl DataStore for LanceDataStore {
 
         let target_id = id.to_string();
 
-        // Use LanceDB query with reasonable limit and filter in application
+        // Push the id predicate down to LanceDB instead of scanning a bounded prefix
         let results_stream = table
             .query()
-            .limit(1000) // Reasonable limit to avoid loading entire table
+            .only_if(format!("id = '{}'", target_id.replace('\'', "''")))
+            .limit(1)
             .execute()
             .await
             .map_err(|e| DataStoreError::LanceDB(format!("Query by ID failed: {}", e)))?;
@@ -911,6 +915,14 @@ impl DataStore for LanceDataStore {
         }
     }
 
+    async fn upsert_node(&self, _node: Node) -> NodeSpaceResult<NodeId> {
+        // TODO: Implement merge_insert based upsert for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "upsert_node not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
     async fn update_node_with_embedding(
         &self,
         node: Node,
@@ -1036,6 +1048,66 @@ impl DataStore for LanceDataStore {
         Ok(nodes)
     }
 
+    async fn find_nodes(&self, _query: NodeQuery) -> NodeSpaceResult<Vec<Node>> {
+        // TODO: Compile NodeQuery against the UniversalDocument columns for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "find_nodes not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn find_nodes_page(
+        &self,
+        _query: NodeQuery,
+        _page_size: usize,
+        _cursor: Option<&PageCursor>,
+    ) -> NodeSpaceResult<NodePage> {
+        // TODO: Implement cursor pagination for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "find_nodes_page not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn stream_nodes(&self, _query: NodeQuery) -> NodeSpaceResult<NodeStream<'_>> {
+        // TODO: Implement streaming scans for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "stream_nodes not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn store_nodes(&self, nodes: Vec<Node>) -> NodeSpaceResult<Vec<NodeId>> {
+        // TODO: Write a single RecordBatch per call like the simple implementation
+        let mut ids = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            ids.push(self.store_node(node).await?);
+        }
+        Ok(ids)
+    }
+
+    async fn get_nodes(&self, ids: &[NodeId]) -> NodeSpaceResult<Vec<Option<Node>>> {
+        let mut nodes = Vec::with_capacity(ids.len());
+        for id in ids {
+            nodes.push(self.get_node(id).await?);
+        }
+        Ok(nodes)
+    }
+
+    async fn update_nodes(&self, nodes: Vec<Node>) -> NodeSpaceResult<()> {
+        for node in nodes {
+            self.update_node(node).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_nodes(&self, ids: &[NodeId]) -> NodeSpaceResult<()> {
+        for id in ids {
+            self.delete_node(id).await?;
+        }
+        Ok(())
+    }
+
     async fn create_relationship(
         &self,
         from: &NodeId,
@@ -1049,11 +1121,81 @@ impl DataStore for LanceDataStore {
             .with_metadata("to".to_string(), to.to_string())
             .with_metadata("rel_type".to_string(), rel_type.to_string());
 
+        // TODO: Implement a relationships table for full LanceDB
+        if !relationships::is_hierarchy_type(rel_type) {
+            let error = DataStoreError::NotImplemented(format!(
+                "Relationship type '{}' not yet implemented for full LanceDB",
+                rel_type
+            ));
+            timer.complete_error(error.to_string());
+            return Err(error.into());
+        }
+
         // TODO: Implement relationship creation via document updates
         timer.complete_success();
         Ok(())
     }
 
+    async fn create_relationship_with_properties(
+        &self,
+        _from: &NodeId,
+        _to: &NodeId,
+        _rel_type: &str,
+        _properties: serde_json::Value,
+    ) -> NodeSpaceResult<()> {
+        // TODO: Implement a relationships table for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "create_relationship_with_properties not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn get_relationships(
+        &self,
+        _node_id: &NodeId,
+        _direction: crate::data_store::RelationshipDirection,
+        _rel_type: Option<&str>,
+    ) -> NodeSpaceResult<Vec<crate::data_store::Relationship>> {
+        // TODO: Implement a relationships table for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "get_relationships not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn delete_relationship(
+        &self,
+        _from: &NodeId,
+        _to: &NodeId,
+        _rel_type: &str,
+    ) -> NodeSpaceResult<()> {
+        // TODO: Implement a relationships table for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "delete_relationship not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn get_backlinks(&self, _node_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        // TODO: Implement mention lookups for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "get_backlinks not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn traverse(
+        &self,
+        _start: &NodeId,
+        _options: crate::traversal::TraversalOptions,
+    ) -> NodeSpaceResult<crate::traversal::TraversalStream<'_>> {
+        // TODO: Implement graph traversal for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "traverse not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
     async fn store_node_with_embedding(
         &self,
         node: Node,
@@ -1128,6 +1270,58 @@ impl DataStore for LanceDataStore {
         self.search_multimodal(embedding, vec![], limit).await
     }
 
+    async fn search_similar_nodes_diverse(
+        &self,
+        _embedding: Vec<f32>,
+        _limit: usize,
+        _diversity: crate::data_store::DiversityConfig,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        // TODO: Implement MMR re-ranking for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "search_similar_nodes_diverse not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn filtered_vector_search(
+        &self,
+        _embedding: Vec<f32>,
+        _filter: NodeQuery,
+        _limit: usize,
+        _mode: FilterMode,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        // TODO: Implement filtered nearest_to search for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "filtered_vector_search not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn full_text_search(
+        &self,
+        _query: &str,
+        _limit: usize,
+    ) -> NodeSpaceResult<Vec<crate::data_store::SearchResult>> {
+        // TODO: Implement inverted index search for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "full_text_search not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn hybrid_text_vector_search(
+        &self,
+        _text: &str,
+        _embedding: Vec<f32>,
+        _config: crate::data_store::HybridSearchConfig,
+    ) -> NodeSpaceResult<Vec<crate::data_store::SearchResult>> {
+        // TODO: Implement keyword + vector fusion for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "hybrid_text_vector_search not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
     async fn update_node_embedding(
         &self,
         _id: &NodeId,
@@ -1187,9 +1381,10 @@ impl DataStore for LanceDataStore {
     async fn hybrid_multimodal_search(
         &self,
         _query_embedding: Vec<f32>,
-        _config: &crate::data_store::HybridSearchConfig,
+        config: &crate::data_store::HybridSearchConfig,
     ) -> NodeSpaceResult<Vec<crate::data_store::SearchResult>> {
         // TODO: Implement hybrid multimodal search for full LanceDB
+        SearchBudget::start(config).check()?;
         Ok(vec![])
     }
 
@@ -1256,9 +1451,10 @@ impl DataStore for LanceDataStore {
     async fn hybrid_semantic_search(
         &self,
         _embeddings: crate::data_store::QueryEmbeddings,
-        _config: crate::data_store::HybridSearchConfig,
+        config: crate::data_store::HybridSearchConfig,
     ) -> NodeSpaceResult<Vec<crate::data_store::SearchResult>> {
         // TODO: Implement hybrid_semantic_search for full LanceDB
+        SearchBudget::start(&config).check()?;
         Ok(vec![])
     }
 
@@ -1278,6 +1474,59 @@ impl DataStore for LanceDataStore {
         // For now, delegate to existing query_nodes as fallback
         self.query_nodes("").await
     }
+
+    async fn get_ordered_children(&self, _parent_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        // TODO: Implement sibling ordering for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "get_ordered_children not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn insert_child_at(
+        &self,
+        _parent_id: &NodeId,
+        _node: Node,
+        _position: usize,
+    ) -> NodeSpaceResult<NodeId> {
+        // TODO: Implement sibling ordering for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "insert_child_at not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn move_node_before(
+        &self,
+        _node_id: &NodeId,
+        _anchor_id: &NodeId,
+    ) -> NodeSpaceResult<()> {
+        // TODO: Implement sibling ordering for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "move_node_before not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn move_node_after(&self, _node_id: &NodeId, _anchor_id: &NodeId) -> NodeSpaceResult<()> {
+        // TODO: Implement sibling ordering for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "move_node_after not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
+
+    async fn retrieve_context(
+        &self,
+        _embedding: Vec<f32>,
+        _options: crate::data_store::ContextOptions,
+    ) -> NodeSpaceResult<crate::data_store::ContextWindow> {
+        // TODO: Implement context assembly for full LanceDB
+        Err(DataStoreError::NotImplemented(
+            "retrieve_context not yet implemented for full LanceDB".to_string(),
+        )
+        .into())
+    }
 }
 
 #[cfg(test)]
@@ -1,29 +1,338 @@
 use crate::data_store::{
-    DataStore, HybridSearchConfig, ImageMetadata, ImageNode, NodeType, RelevanceFactors,
-    SearchResult,
+    ContextHit, ContextOptions, ContextWindow, DataStore, DiversityConfig, HybridSearchConfig,
+    ImageMetadata, ImageNode, NodeType, Relationship, RelationshipDirection, RelevanceFactors,
+    ScoreComponent, ScoreSignal, SearchExplanation, SearchResult, SourceCap,
 };
 use crate::error::DataStoreError;
-use arrow_array::builder::{ListBuilder, StringBuilder};
-use arrow_array::{Array, ListArray, RecordBatch, RecordBatchIterator, StringArray};
+use crate::hierarchy;
+use crate::integrity::{self, HierarchyLinks, IntegrityReport, RepairPolicy, RepairReport};
+use crate::mentions;
+use crate::query::{
+    normalize_timestamp, sql_string_literal, FilterMode, NodePage, NodeQuery, NodeStream,
+    PageCursor, PagePosition, TextQuery,
+};
+use crate::relationships;
+use crate::scoring::{self, NodeLinks};
+use crate::search_budget::SearchBudget;
+use crate::traversal::{
+    self, Frontier, TraversalEdge, TraversalOptions, TraversalStep, TraversalStream,
+};
+use arrow_array::builder::{FixedSizeListBuilder, Float32Builder, ListBuilder, StringBuilder};
+use arrow_array::{
+    Array, FixedSizeListArray, Float32Array, ListArray, RecordBatch, RecordBatchIterator,
+    StringArray,
+};
 use arrow_schema::{DataType, Field, Schema};
 use async_trait::async_trait;
 use base64::prelude::*;
-use lancedb::query::{ExecutableQuery, QueryBase};
-use lancedb::{connect, Connection, Table};
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use lancedb::arrow::SendableRecordBatchStream;
+use lancedb::index::scalar::{FtsIndexBuilder, FullTextSearchQuery};
+use lancedb::index::vector::{IvfHnswSqIndexBuilder, IvfPqIndexBuilder};
+use lancedb::index::Index;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::{NewColumnTransform, OptimizeAction, OptimizeOptions};
+use lancedb::{connect, Connection, DistanceType, Table};
 use nodespace_core_types::{Node, NodeId, NodeSpaceResult};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+
+/// Columns that get a scalar BTree index for predicate pushdown
+const SCALAR_INDEX_COLUMNS: &[&str] = &["id"];
+
+/// Columns that get an inverted (full-text) index for BM25 keyword search
+const FULL_TEXT_INDEX_COLUMNS: &[&str] = &["content"];
+
+/// List columns that get a LabelList index for `array_has_any` filters
+const LABEL_LIST_INDEX_COLUMNS: &[&str] = &["mentions"];
+
+/// Maximum number of ids per `id IN (...)` predicate for batch lookups and deletes
+const ID_PREDICATE_CHUNK_SIZE: usize = 512;
+
+/// Rows per keyset page when `stream_nodes` walks an ordered query
+const ORDERED_STREAM_PAGE_SIZE: usize = 1024;
+
+/// `LIKE` pattern of the fixed-width form `query::format_timestamp` writes
+const NORMALIZED_TIMESTAMP_PATTERN: &str = "____-__-__T__:__:__._________Z";
+
+/// Optional embedding levels, each stored in its own nullable FixedSizeList column
+const OPTIONAL_VECTOR_COLUMNS: &[&str] = &["contextual_vector", "hierarchical_vector"];
+
+/// Candidate multiplier for filtered vector searches whose predicates are partly checked in Rust
+const RESIDUAL_FILTER_OVERFETCH: usize = 4;
+
+/// Per-level ANN candidates fetched for each requested result before fusion
+const FUSION_CANDIDATE_OVERFETCH: usize = 2;
+
+/// Every vector column, primary first
+const VECTOR_COLUMNS: &[&str] = &["vector", "contextual_vector", "hierarchical_vector"];
+
+/// Explanation signal for each column in `VECTOR_COLUMNS`
+const LEVEL_SIGNALS: [ScoreSignal; 3] = [
+    ScoreSignal::Individual,
+    ScoreSignal::Contextual,
+    ScoreSignal::Hierarchical,
+];
+
+/// Where a moved node lands among its new siblings
+enum SiblingPlacement {
+    /// Index in the new sibling order; past the end appends
+    At(usize),
+    Before(String),
+    After(String),
+}
 
 /// LanceDB DataStore implementation with native Arrow columnar storage
 pub struct LanceDataStore {
     connection: Connection,
     table: Arc<RwLock<Option<Table>>>,
     table_name: String,
+    // Typed edges between nodes, see the relationships module
+    relationship_table: Arc<RwLock<Option<Table>>>,
+    relationships_table_name: String,
     _db_path: String,
     vector_dimension: usize,
+    distance_metric: DistanceMetric,
     // Optional NLP engine for automatic embedding generation
     embedding_generator: Option<Box<dyn EmbeddingGenerator + Send + Sync>>,
+    // Set once the scalar indexes in SCALAR_INDEX_COLUMNS exist
+    scalar_indexes_ready: AtomicBool,
+    // Set once the from_id/to_id indexes of the relationships table exist
+    relationship_indexes_ready: AtomicBool,
+    vector_index_policy: Option<VectorIndexPolicy>,
+    hierarchy_validation: HierarchyValidation,
+    // Serializes automatic index maintenance; shared with the background
+    // task that applies the policy after writes
+    vector_index_state: Arc<Mutex<VectorIndexState>>,
+    // Errors from index upkeep after committed writes
+    index_status: Arc<Mutex<IndexMaintenanceStatus>>,
+    // Held from read to write by every sibling-chain / subtree mutation, so
+    // two of them can't relink against the same stale chain
+    hierarchy_lock: Mutex<()>,
+}
+
+/// Configuration for the Arrow-based LanceDB store
+#[derive(Debug, Clone)]
+pub struct LanceDataStoreConfig {
+    pub table_name: String,
+    /// Table of typed edges written by `create_relationship`
+    pub relationships_table_name: String,
+    pub vector_dimension: usize,
+    /// Metric for every vector index and nearest-neighbour query of this store
+    pub distance_metric: DistanceMetric,
+    /// Automatic vector index building; `None` leaves indexes to the caller
+    pub vector_index_policy: Option<VectorIndexPolicy>,
+    /// How writes treat a `parent_id` that points at a missing node
+    pub hierarchy_validation: HierarchyValidation,
+}
+
+impl Default for LanceDataStoreConfig {
+    fn default() -> Self {
+        Self {
+            table_name: "universal_nodes".to_string(),
+            relationships_table_name: "relationships".to_string(),
+            vector_dimension: 384, // Default for bge-small-en-v1.5
+            distance_metric: DistanceMetric::Cosine,
+            vector_index_policy: Some(VectorIndexPolicy::default()),
+            hierarchy_validation: HierarchyValidation::default(),
+        }
+    }
+}
+
+/// Parent checks made when a node is stored or updated
+///
+/// Either way a child's `root_id` is derived from its parent and a
+/// `parent_id` that would make a node its own ancestor is rejected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HierarchyValidation {
+    /// A missing parent fails the write with `DataStoreError::ParentNotFound`
+    Strict,
+    /// A missing parent is allowed and the node keeps the `root_id` it was
+    /// given, so children can be imported before their parents
+    #[default]
+    Lenient,
+}
+
+/// When the store builds, retrains and optimizes vector indexes on its own
+///
+/// The policy is checked in a background task after writes. Below `min_rows`
+/// LanceDB's flat search is exact and fast enough, and IVF training needs that
+/// much data anyway.
+#[derive(Debug, Clone)]
+pub struct VectorIndexPolicy {
+    /// Build an index once a vector column has this many non-null rows
+    pub min_rows: usize,
+    /// Retrain once a column has grown by this factor since its index was built
+    pub retrain_growth_factor: f64,
+    /// Fold newly written rows into existing indexes every this many rows
+    pub optimize_after_rows: usize,
+    /// Parameters for automatically built indexes
+    pub index_params: IndexParams,
+}
+
+impl Default for VectorIndexPolicy {
+    fn default() -> Self {
+        Self {
+            min_rows: 10_000,
+            retrain_growth_factor: 2.0,
+            optimize_after_rows: 5_000,
+            index_params: IndexParams::default(),
+        }
+    }
+}
+
+/// ANN index algorithms available for vector columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VectorIndexAlgorithm {
+    /// IVF partitions with product quantization
+    #[default]
+    IvfPq,
+    /// IVF partitions with an HNSW graph over scalar-quantized vectors
+    IvfHnswSq,
+}
+
+/// Parameters for `LanceDataStore::create_vector_index`
+#[derive(Debug, Clone)]
+pub struct IndexParams {
+    pub algorithm: VectorIndexAlgorithm,
+    /// IVF partition count; LanceDB derives one from the row count when `None`
+    pub num_partitions: Option<u32>,
+    /// PQ sub-vector count (IVF-PQ only); LanceDB derives one from the dimension when `None`
+    pub num_sub_vectors: Option<u32>,
+    /// Replace an existing index on the column instead of failing
+    pub replace: bool,
+}
+
+impl Default for IndexParams {
+    fn default() -> Self {
+        Self {
+            algorithm: VectorIndexAlgorithm::IvfPq,
+            num_partitions: None,
+            num_sub_vectors: None,
+            replace: true,
+        }
+    }
+}
+
+impl IndexParams {
+    fn to_index(&self, distance_metric: DistanceMetric) -> Index {
+        match self.algorithm {
+            VectorIndexAlgorithm::IvfPq => {
+                let mut builder =
+                    IvfPqIndexBuilder::default().distance_type(distance_metric.distance_type());
+                if let Some(num_partitions) = self.num_partitions {
+                    builder = builder.num_partitions(num_partitions);
+                }
+                if let Some(num_sub_vectors) = self.num_sub_vectors {
+                    builder = builder.num_sub_vectors(num_sub_vectors);
+                }
+                Index::IvfPq(builder)
+            }
+            VectorIndexAlgorithm::IvfHnswSq => {
+                let mut builder =
+                    IvfHnswSqIndexBuilder::default().distance_type(distance_metric.distance_type());
+                if let Some(num_partitions) = self.num_partitions {
+                    builder = builder.num_partitions(num_partitions);
+                }
+                Index::IvfHnswSq(builder)
+            }
+        }
+    }
+}
+
+/// An index on the node table, as reported by `LanceDataStore::list_indexes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    pub name: String,
+    pub index_type: String,
+    pub columns: Vec<String>,
+}
+
+/// Coverage of an index, as reported by `LanceDataStore::index_stats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    pub index_type: String,
+    pub num_indexed_rows: usize,
+    /// Rows written since the index was built or last optimized; searched by brute force
+    pub num_unindexed_rows: usize,
+    pub distance_type: Option<String>,
+}
+
+/// Failures of the index upkeep that runs after writes, as reported by
+/// `LanceDataStore::index_maintenance_status`
+///
+/// Writes are committed before their indexes are maintained, so these errors
+/// are recorded here instead of failing the write; each is cleared by the next
+/// successful attempt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexMaintenanceStatus {
+    /// Why the scalar, full-text or label-list indexes could not be built;
+    /// retried after every write
+    pub scalar_error: Option<String>,
+    /// Why the last run of the vector index policy failed; retried once the
+    /// table grows by `optimize_after_rows`
+    pub vector_error: Option<String>,
+    /// Why the `from_id`/`to_id` indexes of the relationships table could not
+    /// be built; retried after every edge write
+    pub relationship_error: Option<String>,
+}
+
+impl IndexMaintenanceStatus {
+    /// Keep the outcome of a vector index policy run that evaluated the table
+    fn record_vector_run(&mut self, result: &Result<bool, DataStoreError>) {
+        match result {
+            Ok(false) => {}
+            Ok(true) => self.vector_error = None,
+            Err(e) => self.vector_error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Bookkeeping for the automatic vector index policy
+#[derive(Debug, Default)]
+struct VectorIndexState {
+    // Table row count at the last policy evaluation
+    evaluated_at_rows: Option<usize>,
+    // Column -> non-null rows when its index was last built or retrained
+    built_at_rows: HashMap<String, usize>,
+}
+
+/// Distance metric for vector indexes and searches
+///
+/// Search methods return `(Node, f32)` pairs where the score is derived from
+/// LanceDB's `_distance` so that higher always means more similar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    /// Cosine distance; score is the cosine similarity `1 - d`, in `[-1, 1]`
+    #[default]
+    Cosine,
+    /// Squared Euclidean distance; score is `1 / (1 + d)`, in `(0, 1]`
+    L2,
+    /// Dot product, for vectors the model already normalizes; LanceDB reports
+    /// `1 - a·b`, so the score is the raw dot product `a·b`
+    Dot,
+}
+
+impl DistanceMetric {
+    fn distance_type(self) -> DistanceType {
+        match self {
+            DistanceMetric::Cosine => DistanceType::Cosine,
+            DistanceMetric::L2 => DistanceType::L2,
+            DistanceMetric::Dot => DistanceType::Dot,
+        }
+    }
+
+    /// Convert a LanceDB `_distance` into a higher-is-better similarity score
+    pub fn score_from_distance(self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::Cosine | DistanceMetric::Dot => 1.0 - distance,
+            DistanceMetric::L2 => 1.0 / (1.0 + distance.max(0.0)),
+        }
+    }
 }
 
 /// Trait for generating embeddings from text content
@@ -66,6 +375,30 @@ pub struct UniversalNode {
     pub metadata: Option<serde_json::Value>,
 }
 
+/// Bare "text" row for unit tests; override fields with struct update syntax
+#[cfg(test)]
+pub(crate) fn test_universal_node(id: &str) -> UniversalNode {
+    UniversalNode {
+        id: id.to_string(),
+        r#type: "text".to_string(),
+        content: String::new(),
+        individual_vector: vec![],
+        contextual_vector: None,
+        hierarchical_vector: None,
+        embedding_model: None,
+        embeddings_generated_at: None,
+        vector: vec![],
+        parent_id: None,
+        before_sibling_id: None,
+        children_ids: vec![],
+        mentions: vec![],
+        root_id: None,
+        created_at: String::new(),
+        updated_at: String::new(),
+        metadata: None,
+    }
+}
+
 impl LanceDataStore {
     /// Initialize new LanceDB connection with Arrow-based storage
     pub async fn new(db_path: &str) -> Result<Self, DataStoreError> {
@@ -84,6 +417,18 @@ impl LanceDataStore {
     pub async fn with_vector_dimension(
         db_path: &str,
         vector_dimension: usize,
+    ) -> Result<Self, DataStoreError> {
+        let config = LanceDataStoreConfig {
+            vector_dimension,
+            ..LanceDataStoreConfig::default()
+        };
+        Self::with_config(db_path, config).await
+    }
+
+    /// Initialize new LanceDB connection with explicit store configuration
+    pub async fn with_config(
+        db_path: &str,
+        config: LanceDataStoreConfig,
     ) -> Result<Self, DataStoreError> {
         let connection = connect(db_path).execute().await.map_err(|e| {
             DataStoreError::LanceDBConnection(format!("LanceDB connection failed: {}", e))
@@ -92,10 +437,20 @@ impl LanceDataStore {
         let instance = Self {
             connection,
             table: Arc::new(RwLock::new(None)),
-            table_name: "universal_nodes".to_string(),
+            table_name: config.table_name,
+            relationship_table: Arc::new(RwLock::new(None)),
+            relationships_table_name: config.relationships_table_name,
             _db_path: db_path.to_string(),
-            vector_dimension,
+            vector_dimension: config.vector_dimension,
+            distance_metric: config.distance_metric,
+            vector_index_policy: config.vector_index_policy,
+            hierarchy_validation: config.hierarchy_validation,
+            vector_index_state: Arc::new(Mutex::new(VectorIndexState::default())),
+            index_status: Arc::new(Mutex::new(IndexMaintenanceStatus::default())),
+            hierarchy_lock: Mutex::new(()),
             embedding_generator: None, // Can be set later via set_embedding_generator
+            scalar_indexes_ready: AtomicBool::new(false),
+            relationship_indexes_ready: AtomicBool::new(false),
         };
 
         // Initialize Arrow-based table
@@ -116,11 +471,16 @@ impl LanceDataStore {
 
         let table = if table_names.contains(&self.table_name) {
             // Open existing table
-            self.connection
+            let table = self
+                .connection
                 .open_table(&self.table_name)
                 .execute()
                 .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Failed to open table: {}", e)))?
+                .map_err(|e| DataStoreError::LanceDB(format!("Failed to open table: {}", e)))?;
+
+            self.add_missing_vector_columns(&table).await?;
+            self.normalize_stored_timestamps(&table).await?;
+            table
         } else {
             // Create new table with empty data
             let empty_batch = self.create_empty_record_batch(schema.clone())?;
@@ -137,9 +497,46 @@ impl LanceDataStore {
         // Store table reference
         *self.table.write().await = Some(table);
 
-        // Create vector index for similarity search
-        self.create_vector_index().await?;
+        // Create scalar indexes for point lookups and filters
+        self.maintain_scalar_indexes().await;
+
+        // Build or catch up vector indexes for tables that already hold data
+        self.schedule_vector_index_maintenance().await;
+
+        self.initialize_relationship_table(&table_names).await?;
+
+        Ok(())
+    }
+
+    /// Open or create the table of typed edges
+    async fn initialize_relationship_table(
+        &self,
+        table_names: &[String],
+    ) -> Result<(), DataStoreError> {
+        let table = if table_names.contains(&self.relationships_table_name) {
+            self.connection
+                .open_table(&self.relationships_table_name)
+                .execute()
+                .await
+                .map_err(|e| {
+                    DataStoreError::LanceDB(format!("Failed to open relationships table: {}", e))
+                })?
+        } else {
+            let schema = relationships::relationship_schema();
+            let empty_batch = RecordBatch::new_empty(schema.clone());
+            let batches = RecordBatchIterator::new(vec![empty_batch].into_iter().map(Ok), schema);
+
+            self.connection
+                .create_table(&self.relationships_table_name, Box::new(batches))
+                .execute()
+                .await
+                .map_err(|e| {
+                    DataStoreError::LanceDB(format!("Failed to create relationships table: {}", e))
+                })?
+        };
 
+        *self.relationship_table.write().await = Some(table);
+        self.maintain_relationship_indexes().await;
         Ok(())
     }
 
@@ -178,16 +575,140 @@ impl LanceDataStore {
             Field::new("created_at", DataType::Utf8, false),
             Field::new("updated_at", DataType::Utf8, false),
             Field::new("metadata", DataType::Utf8, true), // Nullable JSON string
+            // Multi-level embeddings - nullable, appended so older tables can be migrated in place
+            self.vector_field("contextual_vector", true),
+            self.vector_field("hierarchical_vector", true),
         ]))
     }
 
+    /// FixedSizeList of Float32 field sized to the configured vector dimension
+    fn vector_field(&self, name: &str, nullable: bool) -> Field {
+        Field::new(
+            name,
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, false)),
+                self.vector_dimension as i32,
+            ),
+            nullable,
+        )
+    }
+
+    /// Add embedding columns missing from tables created before they existed
+    async fn add_missing_vector_columns(&self, table: &Table) -> Result<(), DataStoreError> {
+        let existing_schema = table.schema().await.map_err(|e| {
+            DataStoreError::LanceDBSchema(format!("Failed to read table schema: {}", e))
+        })?;
+
+        let missing: Vec<Field> = OPTIONAL_VECTOR_COLUMNS
+            .iter()
+            .filter(|column| existing_schema.field_with_name(column).is_err())
+            .map(|column| self.vector_field(column, true))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        table
+            .add_columns(
+                NewColumnTransform::AllNulls(Arc::new(Schema::new(missing))),
+                None,
+            )
+            .await
+            .map_err(|e| {
+                DataStoreError::Migration(format!("Failed to add embedding columns: {}", e))
+            })?;
+
+        Ok(())
+    }
+
+    /// Rewrite `created_at` / `updated_at` of rows stored before timestamps were normalized
+    ///
+    /// Only rows not already in the fixed-width form are read, so on a
+    /// migrated table this is one filtered scan that finds nothing. Text that
+    /// doesn't parse as a timestamp is left alone.
+    async fn normalize_stored_timestamps(&self, table: &Table) -> Result<(), DataStoreError> {
+        let pattern = sql_string_literal(NORMALIZED_TIMESTAMP_PATTERN);
+        let mut batches = table
+            .query()
+            .only_if(format!(
+                "created_at NOT LIKE {} OR updated_at NOT LIKE {}",
+                pattern, pattern
+            ))
+            .execute()
+            .await
+            .map_err(|e| DataStoreError::Migration(format!("Timestamp scan failed: {}", e)))?;
+
+        let schema = self.create_universal_schema();
+        while let Some(batch) = batches
+            .try_next()
+            .await
+            .map_err(|e| DataStoreError::Migration(format!("Failed to read result batch: {}", e)))?
+        {
+            let stale: Vec<UniversalNode> = self
+                .extract_nodes_from_batch(&batch)?
+                .into_iter()
+                .filter(|node| {
+                    normalize_timestamp(&node.created_at) != node.created_at
+                        || normalize_timestamp(&node.updated_at) != node.updated_at
+                })
+                .collect();
+            if stale.is_empty() {
+                continue;
+            }
+
+            // create_record_batch_from_nodes writes the normalized form
+            let rows = self.create_record_batch_from_nodes(stale, schema.clone())?;
+            let rows = RecordBatchIterator::new(vec![rows].into_iter().map(Ok), schema.clone());
+            let mut merge_insert = table.merge_insert(&["id"]);
+            merge_insert.when_matched_update_all(None);
+            merge_insert.execute(Box::new(rows)).await.map_err(|e| {
+                DataStoreError::Migration(format!("Failed to normalize timestamps: {}", e))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Nullable FixedSizeList column for an optional embedding level
+    fn optional_vector_array<'a>(
+        &self,
+        vectors: impl Iterator<Item = Option<&'a Vec<f32>>>,
+    ) -> Result<FixedSizeListArray, DataStoreError> {
+        let item_field = Arc::new(Field::new("item", DataType::Float32, false));
+        let mut builder =
+            FixedSizeListBuilder::new(Float32Builder::new(), self.vector_dimension as i32)
+                .with_field(item_field);
+        // Null slots still occupy `vector_dimension` child values
+        let null_slot = vec![0.0; self.vector_dimension];
+
+        for vector in vectors {
+            match vector.filter(|vector| !vector.is_empty()) {
+                Some(vector) => {
+                    if vector.len() != self.vector_dimension {
+                        return Err(DataStoreError::Arrow(format!(
+                            "Vector dimension mismatch: expected {}, got {}",
+                            self.vector_dimension,
+                            vector.len()
+                        )));
+                    }
+                    builder.values().append_slice(vector);
+                    builder.append(true);
+                }
+                None => {
+                    builder.values().append_slice(&null_slot);
+                    builder.append(false);
+                }
+            }
+        }
+
+        Ok(builder.finish())
+    }
+
     /// Create an empty RecordBatch for table initialization
     fn create_empty_record_batch(
         &self,
         schema: Arc<Schema>,
     ) -> Result<RecordBatch, DataStoreError> {
-        use arrow_array::{FixedSizeListArray, Float32Array};
-
         // Create empty FixedSizeListArray for vectors with configurable dimension
         let empty_values = Float32Array::from(Vec::<f32>::new());
         let field = Arc::new(Field::new("item", DataType::Float32, false));
@@ -217,6 +738,8 @@ impl LanceDataStore {
                 Arc::new(StringArray::from(Vec::<String>::new())), // created_at
                 Arc::new(StringArray::from(Vec::<String>::new())), // updated_at
                 Arc::new(StringArray::from(Vec::<Option<String>>::new())), // metadata
+                Arc::new(self.optional_vector_array(std::iter::empty())?), // contextual_vector
+                Arc::new(self.optional_vector_array(std::iter::empty())?), // hierarchical_vector
             ],
         )
         .map_err(|e| DataStoreError::Arrow(format!("Failed to create empty batch: {}", e)))?;
@@ -224,136 +747,389 @@ impl LanceDataStore {
         Ok(batch)
     }
 
-    /// Create vector index for efficient similarity search
-    async fn create_vector_index(&self) -> Result<(), DataStoreError> {
+    /// Build an ANN index on a vector column using the store's distance metric
+    pub async fn create_vector_index(
+        &self,
+        column: &str,
+        params: IndexParams,
+    ) -> Result<(), DataStoreError> {
         let table_guard = self.table.read().await;
-        if let Some(table) = table_guard.as_ref() {
-            // Only create index if table has data
-            let stats = table
-                .count_rows(None)
-                .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Failed to get row count: {}", e)))?;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
 
-            if stats > 0 {
-                // Create IVF (Inverted File) index for vector similarity search
-                match table
-                    .create_index(
-                        &["vector"],
-                        lancedb::index::Index::IvfPq(Default::default()),
-                    )
-                    .replace(true) // Replace existing index if present
-                    .execute()
-                    .await
-                {
-                    Ok(_) => {}
-                    Err(_) => {
-                        // This is not a fatal error - index can be created later when data exists
-                    }
-                }
-            }
-        }
-        Ok(())
+        build_vector_index(table, column, params, self.distance_metric).await
     }
 
-    /// Convert NodeSpace Node to UniversalNode with multi-level embeddings support
-    /// For TextNode and DateNode: Empty metadata to eliminate redundant hierarchical data
-    /// For other node types: Preserve metadata for type-specific properties
-    fn node_to_universal(&self, node: Node, embedding: Option<Vec<f32>>) -> UniversalNode {
-        let now = chrono::Utc::now().to_rfc3339();
-
-        // Use the actual node type from the Node struct
-        let node_type = node.r#type.clone();
-
-        // Extract relationships from Node fields and metadata
-        // Prefer Node.parent_id field over metadata for direct field access
-        let parent_id = node
-            .parent_id
+    /// All indexes on the node table, scalar and vector
+    pub async fn list_indexes(&self) -> Result<Vec<IndexInfo>, DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
             .as_ref()
-            .map(|id| id.to_string())
-            .or_else(|| {
-                node.metadata
-                    .as_ref()
-                    .and_then(|m| m.get("parent_id"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            });
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
 
-        let children_ids = node
-            .metadata
-            .as_ref()
-            .and_then(|m| m.get("children_ids"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
+        table_indexes(table).await
+    }
 
-        let mentions = node
-            .metadata
+    /// Coverage statistics for an index, `None` if no index has that name
+    pub async fn index_stats(&self, name: &str) -> Result<Option<IndexStats>, DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
             .as_ref()
-            .and_then(|m| m.get("mentions"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        // Extract root hierarchy optimization fields
-        // Extract root hierarchy fields from Node fields and metadata
-        // Prefer Node fields over metadata for direct field access
-        let root_id = node.root_id.as_ref().map(|id| id.to_string()).or_else(|| {
-            node.metadata
-                .as_ref()
-                .and_then(|m| m.get("root_id"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        });
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
 
-        // root_type removed - use node.r#type instead
+        table_index_stats(table, name).await
+    }
 
-        // Extract multi-level embeddings from metadata if available
-        let default_vector = vec![0.0; self.vector_dimension];
-        let individual_vector = embedding.clone().unwrap_or_else(|| default_vector.clone());
+    /// Errors left by the index upkeep that follows writes
+    pub async fn index_maintenance_status(&self) -> IndexMaintenanceStatus {
+        self.index_status.lock().await.clone()
+    }
 
-        let contextual_vector = node
-            .metadata
+    /// Add rows written since the last build to every index without retraining
+    pub async fn optimize_indexes(&self) -> Result<(), DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
             .as_ref()
-            .and_then(|m| m.get("contextual_vector"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_f64().map(|f| f as f32))
-                    .collect()
-            });
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
 
-        let hierarchical_vector = node
-            .metadata
-            .as_ref()
-            .and_then(|m| m.get("hierarchical_vector"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_f64().map(|f| f as f32))
-                    .collect()
-            });
+        optimize_table_indexes(table).await
+    }
 
-        let embedding_model = node
-            .metadata
+    /// Drop an index by name
+    pub async fn drop_index(&self, name: &str) -> Result<(), DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
             .as_ref()
-            .and_then(|m| m.get("embedding_model"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
 
-        let embeddings_generated_at = node
-            .metadata
-            .as_ref()
-            .and_then(|m| m.get("embeddings_generated_at"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        table.drop_index(name).await.map_err(|e| {
+            DataStoreError::LanceDBIndex(format!("Failed to drop index '{}': {}", name, e))
+        })?;
+        drop(table_guard);
+
+        // Let the policy rebuild it if the store still qualifies
+        self.vector_index_state.lock().await.built_at_rows.clear();
+
+        Ok(())
+    }
+
+    /// Apply the automatic vector index policy now
+    ///
+    /// Writes run the policy in a background task; this waits for a run in
+    /// progress, then evaluates the policy once more. It only does work once the
+    /// table has grown by `optimize_after_rows` since the last evaluation.
+    /// Failures are returned as `DataStoreError::VectorIndexCreation` and also
+    /// kept in `index_maintenance_status`.
+    pub async fn maintain_vector_indexes(&self) -> Result<(), DataStoreError> {
+        let policy = match &self.vector_index_policy {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+        let mut state = self.vector_index_state.lock().await;
+        let table = self
+            .table
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let result =
+            apply_vector_index_policy(&table, policy, self.distance_metric, &mut state).await;
+        self.index_status.lock().await.record_vector_run(&result);
+        result.map(|_| ())
+    }
+
+    /// Run the vector index policy in a background task after a write
+    ///
+    /// Index training can take minutes, so writers neither wait for it nor see
+    /// its errors; those land in `index_maintenance_status`. While a run is in
+    /// progress further writes start none: rows they add are picked up by the
+    /// next run.
+    async fn schedule_vector_index_maintenance(&self) {
+        let Some(policy) = self.vector_index_policy.clone() else {
+            return;
+        };
+        let Ok(mut state) = self.vector_index_state.clone().try_lock_owned() else {
+            return;
+        };
+        let Some(table) = self.table.read().await.clone() else {
+            return;
+        };
+        let index_status = self.index_status.clone();
+        let distance_metric = self.distance_metric;
+
+        tokio::spawn(async move {
+            let result =
+                apply_vector_index_policy(&table, &policy, distance_metric, &mut state).await;
+            index_status.lock().await.record_vector_run(&result);
+        });
+    }
+
+    /// Create the scalar BTree indexes that back `id = '...'` style lookups and
+    /// the inverted index behind `full_text_search`
+    ///
+    /// Index training needs data, so on an empty table this is a no-op and is
+    /// retried after the next write. Rows written after the index was built are
+    /// still found by filtered queries: LanceDB scans unindexed fragments. A
+    /// failing index doesn't stop the others from being built.
+    async fn ensure_scalar_indexes(&self) -> Result<(), DataStoreError> {
+        if self.scalar_indexes_ready.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let row_count = table
+            .count_rows(None)
+            .await
+            .map_err(|e| DataStoreError::LanceDB(format!("Failed to get row count: {}", e)))?;
+        if row_count == 0 {
+            return Ok(());
+        }
+
+        let existing_indexes = table
+            .list_indices()
+            .await
+            .map_err(|e| DataStoreError::LanceDBIndex(format!("Failed to list indexes: {}", e)))?;
+
+        let mut failures = Vec::new();
+        for column in SCALAR_INDEX_COLUMNS {
+            let already_indexed = existing_indexes
+                .iter()
+                .any(|index| index.columns.len() == 1 && index.columns[0] == *column);
+            if already_indexed {
+                continue;
+            }
+
+            if let Err(e) = table
+                .create_index(&[*column], lancedb::index::Index::BTree(Default::default()))
+                .execute()
+                .await
+            {
+                failures.push(format!("Failed to create BTree index on '{}': {}", column, e));
+            }
+        }
+
+        for column in FULL_TEXT_INDEX_COLUMNS {
+            let already_indexed = existing_indexes
+                .iter()
+                .any(|index| index.columns.len() == 1 && index.columns[0] == *column);
+            if already_indexed {
+                continue;
+            }
+
+            if let Err(e) = table
+                .create_index(&[*column], Index::FTS(FtsIndexBuilder::default()))
+                .execute()
+                .await
+            {
+                failures.push(format!(
+                    "Failed to create full-text index on '{}': {}",
+                    column, e
+                ));
+            }
+        }
+
+        for column in LABEL_LIST_INDEX_COLUMNS {
+            let already_indexed = existing_indexes
+                .iter()
+                .any(|index| index.columns.len() == 1 && index.columns[0] == *column);
+            if already_indexed {
+                continue;
+            }
+
+            if let Err(e) = table
+                .create_index(&[*column], Index::LabelList(Default::default()))
+                .execute()
+                .await
+            {
+                failures.push(format!(
+                    "Failed to create LabelList index on '{}': {}",
+                    column, e
+                ));
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(DataStoreError::LanceDBIndex(failures.join("; ")));
+        }
+        self.scalar_indexes_ready.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// `ensure_scalar_indexes` for paths that must not fail on index errors
+    ///
+    /// The failure is kept in `index_maintenance_status` and the build is
+    /// retried on the next call.
+    async fn maintain_scalar_indexes(&self) {
+        let error = self.ensure_scalar_indexes().await.err();
+        self.index_status.lock().await.scalar_error = error.map(|e| e.to_string());
+    }
+
+    /// `ensure_relationship_indexes` for paths that must not fail on index errors
+    ///
+    /// Like `maintain_scalar_indexes`, the failure is kept in
+    /// `index_maintenance_status` and retried on the next call.
+    async fn maintain_relationship_indexes(&self) {
+        let error = self.ensure_relationship_indexes().await.err();
+        self.index_status.lock().await.relationship_error = error.map(|e| e.to_string());
+    }
+
+    /// Create BTree indexes on both endpoints of the relationships table once it has rows
+    async fn ensure_relationship_indexes(&self) -> Result<(), DataStoreError> {
+        if self.relationship_indexes_ready.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let table_guard = self.relationship_table.read().await;
+        let table = table_guard.as_ref().ok_or_else(|| {
+            DataStoreError::LanceDB("Relationships table not initialized".to_string())
+        })?;
+
+        let row_count = table
+            .count_rows(None)
+            .await
+            .map_err(|e| DataStoreError::LanceDB(format!("Failed to get row count: {}", e)))?;
+        if row_count == 0 {
+            return Ok(());
+        }
+
+        let existing_indexes = table
+            .list_indices()
+            .await
+            .map_err(|e| DataStoreError::LanceDBIndex(format!("Failed to list indexes: {}", e)))?;
+
+        for column in relationships::RELATIONSHIP_INDEX_COLUMNS {
+            let already_indexed = existing_indexes
+                .iter()
+                .any(|index| index.columns.len() == 1 && index.columns[0] == *column);
+            if already_indexed {
+                continue;
+            }
+
+            table
+                .create_index(&[*column], lancedb::index::Index::BTree(Default::default()))
+                .execute()
+                .await
+                .map_err(|e| {
+                    DataStoreError::LanceDBIndex(format!(
+                        "Failed to create BTree index on '{}': {}",
+                        column, e
+                    ))
+                })?;
+        }
+
+        self.relationship_indexes_ready
+            .store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Convert NodeSpace Node to UniversalNode with multi-level embeddings support
+    /// For TextNode and DateNode: Empty metadata to eliminate redundant hierarchical data
+    /// For other node types: Preserve metadata for type-specific properties
+    fn node_to_universal(&self, node: Node, embedding: Option<Vec<f32>>) -> UniversalNode {
+        let now = chrono::Utc::now().to_rfc3339();
+
+        // Use the actual node type from the Node struct
+        let node_type = node.r#type.clone();
+
+        // Extract relationships from Node fields and metadata
+        // Prefer Node.parent_id field over metadata for direct field access
+        let parent_id = node
+            .parent_id
+            .as_ref()
+            .map(|id| id.to_string())
+            .or_else(|| {
+                node.metadata
+                    .as_ref()
+                    .and_then(|m| m.get("parent_id"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            });
+
+        let children_ids = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("children_ids"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let explicit_mentions = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("mentions"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        // [[id]] and @id references in the text count as mentions too
+        let mentions =
+            mentions::merge_mentions(explicit_mentions, &content_to_string(&node.content));
+
+        // Extract root hierarchy optimization fields
+        // Extract root hierarchy fields from Node fields and metadata
+        // Prefer Node fields over metadata for direct field access
+        let root_id = node.root_id.as_ref().map(|id| id.to_string()).or_else(|| {
+            node.metadata
+                .as_ref()
+                .and_then(|m| m.get("root_id"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        });
+
+        // root_type removed - use node.r#type instead
+
+        // Extract multi-level embeddings from metadata if available
+        let default_vector = vec![0.0; self.vector_dimension];
+        let individual_vector = embedding.clone().unwrap_or_else(|| default_vector.clone());
+
+        let contextual_vector = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("contextual_vector"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_f64().map(|f| f as f32))
+                    .collect()
+            });
+
+        let hierarchical_vector = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("hierarchical_vector"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_f64().map(|f| f as f32))
+                    .collect()
+            });
+
+        let embedding_model = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("embedding_model"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let embeddings_generated_at = node
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("embeddings_generated_at"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
 
         // Simplify metadata for TextNode and DateNode to eliminate redundant hierarchical data
         // For these node types, hierarchical data should come from parent_id/children_ids fields only
@@ -371,10 +1147,7 @@ impl LanceDataStore {
         UniversalNode {
             id: node.id.to_string(),
             r#type: node_type,
-            content: match &node.content {
-                serde_json::Value::String(s) => s.clone(),
-                other => other.to_string(),
-            },
+            content: content_to_string(&node.content),
             individual_vector: individual_vector.clone(),
             contextual_vector,
             hierarchical_vector,
@@ -438,7 +1211,7 @@ impl LanceDataStore {
             })
             .unwrap_or_default();
 
-        let mentions = node
+        let explicit_mentions = node
             .metadata
             .as_ref()
             .and_then(|m| m.get("mentions"))
@@ -449,6 +1222,9 @@ impl LanceDataStore {
                     .collect()
             })
             .unwrap_or_default();
+        // [[id]] and @id references in the text count as mentions too
+        let mentions =
+            mentions::merge_mentions(explicit_mentions, &content_to_string(&node.content));
 
         // Extract root hierarchy optimization fields
         // Extract root hierarchy fields from Node fields and metadata
@@ -479,10 +1255,7 @@ impl LanceDataStore {
         UniversalNode {
             id: node.id.to_string(),
             r#type: node_type,
-            content: match &node.content {
-                serde_json::Value::String(s) => s.clone(),
-                other => other.to_string(),
-            },
+            content: content_to_string(&node.content),
             individual_vector: embeddings.individual.clone(),
             contextual_vector: embeddings.contextual.clone(),
             hierarchical_vector: embeddings.hierarchical.clone(),
@@ -527,8 +1300,15 @@ impl LanceDataStore {
         let before_sibling_ids: Vec<Option<String>> = nodes.iter().map(|n| n.before_sibling_id.clone()).collect();
         let root_ids: Vec<Option<String>> = nodes.iter().map(|n| n.root_id.clone()).collect();
         // root_type field removed
-        let created_ats: Vec<String> = nodes.iter().map(|n| n.created_at.clone()).collect();
-        let updated_ats: Vec<String> = nodes.iter().map(|n| n.updated_at.clone()).collect();
+        // One UTC format, so timestamp predicates can compare the text
+        let created_ats: Vec<String> = nodes
+            .iter()
+            .map(|n| normalize_timestamp(&n.created_at))
+            .collect();
+        let updated_ats: Vec<String> = nodes
+            .iter()
+            .map(|n| normalize_timestamp(&n.updated_at))
+            .collect();
         let metadatas: Vec<Option<String>> = nodes
             .iter()
             .map(|n| n.metadata.as_ref().map(|v| v.to_string()))
@@ -536,8 +1316,6 @@ impl LanceDataStore {
 
         // Vector field: Vec<f32> -> FixedSizeListArray for LanceDB vector indexing
         let vectors = {
-            use arrow_array::{FixedSizeListArray, Float32Array};
-
             // Collect all vector values into a flat array
             let mut flat_values = Vec::new();
             for node in &nodes {
@@ -559,6 +1337,12 @@ impl LanceDataStore {
                 })?
         };
 
+        // Optional multi-level embeddings: null where a level was never generated
+        let contextual_vectors =
+            self.optional_vector_array(nodes.iter().map(|n| n.contextual_vector.as_ref()))?;
+        let hierarchical_vectors =
+            self.optional_vector_array(nodes.iter().map(|n| n.hierarchical_vector.as_ref()))?;
+
         // Children IDs: Vec<String> -> ListArray for string lists
         let mut children_builder = ListBuilder::new(StringBuilder::new());
         for node in &nodes {
@@ -596,6 +1380,8 @@ impl LanceDataStore {
                 Arc::new(StringArray::from(created_ats)),
                 Arc::new(StringArray::from(updated_ats)),
                 Arc::new(StringArray::from(metadatas)),
+                Arc::new(contextual_vectors),
+                Arc::new(hierarchical_vectors),
             ],
         )
         .map_err(|e| DataStoreError::Arrow(format!("Failed to create RecordBatch: {}", e)))?;
@@ -626,1408 +1412,5795 @@ impl LanceDataStore {
         } else {
             return Err(DataStoreError::LanceDB("Table not initialized".to_string()));
         }
+        drop(table_guard);
+
+        // The first write into a fresh table is when the scalar indexes can be
+        // trained; the row is committed either way
+        self.maintain_scalar_indexes().await;
+        self.schedule_vector_index_maintenance().await;
 
         Ok(())
     }
 
-    /// Delete a node by exact ID match (more specific predicate)
-    async fn delete_node_by_exact_id(&self, node_id: &NodeId) -> Result<(), DataStoreError> {
+    /// Store many nodes with one RecordBatch and one table write
+    async fn store_nodes_arrow(
+        &self,
+        universal_nodes: Vec<UniversalNode>,
+    ) -> Result<(), DataStoreError> {
+        if universal_nodes.is_empty() {
+            return Ok(());
+        }
+
+        let schema = self.create_universal_schema();
+        let batch = self.create_record_batch_from_nodes(universal_nodes, schema.clone())?;
+
         let table_guard = self.table.read().await;
         if let Some(table) = table_guard.as_ref() {
-            let id_str = node_id.to_string();
-
-            // First, check how many rows exist before deletion
-            let _count_before = table.count_rows(None).await.unwrap_or(0);
-            // Use more specific LanceDB delete predicate
-            let predicate = format!("id == '{}'", id_str);
+            let batches = RecordBatchIterator::new(vec![batch].into_iter().map(Ok), schema);
 
-            match table.delete(&predicate).await {
-                Ok(_stats) => {
-                    // Deletion successful
-                }
-                Err(e) => {
-                    return Err(DataStoreError::LanceDB(format!(
-                        "Failed to delete node: {}",
-                        e
-                    )));
-                }
-            }
+            table.add(Box::new(batches)).execute().await.map_err(|e| {
+                DataStoreError::LanceDB(format!("Failed to add batch to table: {}", e))
+            })?;
+        } else {
+            return Err(DataStoreError::LanceDB("Table not initialized".to_string()));
         }
+        drop(table_guard);
+
+        self.maintain_scalar_indexes().await;
+        self.schedule_vector_index_maintenance().await;
+
         Ok(())
     }
 
-    /// Query nodes from Arrow storage with native LanceDB filtering
-    async fn query_nodes_arrow(&self, query: &str) -> Result<Vec<UniversalNode>, DataStoreError> {
+    /// Fetch many nodes by id with chunked `id IN (...)` predicates, keyed by id
+    async fn get_universal_nodes_arrow(
+        &self,
+        ids: &[String],
+    ) -> Result<HashMap<String, UniversalNode>, DataStoreError> {
+        let mut found = HashMap::with_capacity(ids.len());
+        if ids.is_empty() {
+            return Ok(found);
+        }
+
         let table_guard = self.table.read().await;
-        if let Some(table) = table_guard.as_ref() {
-            // Use LanceDB query with limit to avoid loading all data
-            let results = table
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        for chunk in ids.chunks(ID_PREDICATE_CHUNK_SIZE) {
+            let results_stream = table
                 .query()
-                .limit(1000) // Reasonable limit to avoid memory issues
+                .only_if(id_in_predicate(chunk))
                 .execute()
                 .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Query failed: {}", e)))?;
+                .map_err(|e| DataStoreError::LanceDB(format!("Batch query by ID failed: {}", e)))?;
 
-            let batches: Vec<RecordBatch> = futures::TryStreamExt::try_collect(results)
+            let batches: Vec<RecordBatch> = futures::TryStreamExt::try_collect(results_stream)
                 .await
                 .map_err(|e| {
-                    DataStoreError::LanceDB(format!("Failed to collect results: {}", e))
+                    DataStoreError::LanceDB(format!("Failed to collect query results: {}", e))
                 })?;
 
-            let mut nodes = Vec::new();
-            for batch in batches {
-                let batch_nodes = self.extract_nodes_from_batch(&batch)?;
-
-                if query.is_empty() {
-                    nodes.extend(batch_nodes);
-                } else {
-                    // Apply content filter efficiently
-                    for node in batch_nodes {
-                        if node.content.to_lowercase().contains(&query.to_lowercase()) {
-                            nodes.push(node);
-                        }
-                    }
+            for batch in batches.iter() {
+                for universal_node in self.extract_nodes_from_batch(batch)? {
+                    found.insert(universal_node.id.clone(), universal_node);
                 }
             }
-
-            Ok(nodes)
-        } else {
-            Err(DataStoreError::LanceDB("Table not initialized".to_string()))
         }
+
+        Ok(found)
     }
 
-    /// Extract UniversalNode objects from Arrow RecordBatch with proper ListArray handling
-    fn extract_nodes_from_batch(
+    /// Rows whose `column` equals any of `values`, in chunked `IN` predicates
+    async fn nodes_where_in(
         &self,
-        batch: &RecordBatch,
+        column: &str,
+        values: &[String],
     ) -> Result<Vec<UniversalNode>, DataStoreError> {
-        let mut nodes = Vec::new();
-        let num_rows = batch.num_rows();
-
-        if num_rows == 0 {
-            return Ok(nodes);
+        let mut found = Vec::new();
+        if values.is_empty() {
+            return Ok(found);
         }
 
-        // Extract column arrays with proper error handling
-        let ids = batch
-            .column_by_name("id")
-            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-            .ok_or_else(|| DataStoreError::Arrow("Missing or invalid id column".to_string()))?;
-
-        let node_types = batch
-            .column_by_name("type")
-            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-            .ok_or_else(|| {
-                DataStoreError::Arrow("Missing or invalid type column".to_string())
-            })?;
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
 
-        let contents = batch
-            .column_by_name("content")
-            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-            .ok_or_else(|| {
-                DataStoreError::Arrow("Missing or invalid content column".to_string())
-            })?;
-
-        let created_ats = batch
-            .column_by_name("created_at")
-            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-            .ok_or_else(|| {
-                DataStoreError::Arrow("Missing or invalid created_at column".to_string())
-            })?;
+        for chunk in values.chunks(ID_PREDICATE_CHUNK_SIZE) {
+            let results_stream = table
+                .query()
+                .only_if(column_in_predicate(column, chunk))
+                .execute()
+                .await
+                .map_err(|e| {
+                    DataStoreError::LanceDB(format!("Batch query by {} failed: {}", column, e))
+                })?;
 
-        let updated_ats = batch
-            .column_by_name("updated_at")
-            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-            .ok_or_else(|| {
-                DataStoreError::Arrow("Missing or invalid updated_at column".to_string())
-            })?;
+            let batches: Vec<RecordBatch> = futures::TryStreamExt::try_collect(results_stream)
+                .await
+                .map_err(|e| {
+                    DataStoreError::LanceDB(format!("Failed to collect query results: {}", e))
+                })?;
 
-        // Extract vector FixedSizeListArray
-        let vector_list_array = batch
-            .column_by_name("vector")
-            .and_then(|col| {
-                col.as_any()
-                    .downcast_ref::<arrow_array::FixedSizeListArray>()
-            })
-            .ok_or_else(|| DataStoreError::Arrow("Missing or invalid vector column".to_string()))?;
+            for batch in batches.iter() {
+                found.extend(self.extract_nodes_from_batch(batch)?);
+            }
+        }
 
-        // Extract children_ids ListArray
-        let children_list_array = batch
-            .column_by_name("children_ids")
-            .and_then(|col| col.as_any().downcast_ref::<ListArray>());
+        Ok(found)
+    }
 
-        // Extract mentions ListArray
-        let mentions_list_array = batch
-            .column_by_name("mentions")
-            .and_then(|col| col.as_any().downcast_ref::<ListArray>());
+    /// Direct children of each parent, in storage order
+    ///
+    /// Parents without children are absent from the map.
+    async fn get_children_arrow(
+        &self,
+        parent_ids: &[String],
+    ) -> Result<HashMap<String, Vec<UniversalNode>>, DataStoreError> {
+        let mut by_parent: HashMap<String, Vec<UniversalNode>> = HashMap::new();
+        for universal_node in self.nodes_where_in("parent_id", parent_ids).await? {
+            if let Some(parent_id) = universal_node.parent_id.clone() {
+                by_parent.entry(parent_id).or_default().push(universal_node);
+            }
+        }
+        Ok(by_parent)
+    }
 
-        for i in 0..num_rows {
-            let id = ids.value(i).to_string();
-            let node_type = node_types.value(i).to_string();
-            let content = contents.value(i).to_string();
-            let created_at = created_ats.value(i).to_string();
-            let updated_at = updated_ats.value(i).to_string();
+    /// Every descendant of `node_key` through `parent_id`, breadth first
+    async fn get_descendants_arrow(
+        &self,
+        node_key: &str,
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let mut descendants = Vec::new();
+        let mut visited: HashSet<String> = HashSet::from([node_key.to_string()]);
+        let mut frontier = vec![node_key.to_string()];
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for child in self.nodes_where_in("parent_id", &frontier).await? {
+                if visited.insert(child.id.clone()) {
+                    next.push(child.id.clone());
+                    descendants.push(child);
+                }
+            }
+            frontier = next;
+        }
+        Ok(descendants)
+    }
 
-            // Extract optional fields
-            let parent_id = batch
-                .column_by_name("parent_id")
-                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                .and_then(|arr| {
-                    if arr.is_null(i) {
-                        None
-                    } else {
-                        Some(arr.value(i).to_string())
-                    }
-                });
+    /// Children of `parent_id` in `before_sibling_id` order, failing on broken links
+    async fn get_ordered_children_strict(
+        &self,
+        parent_id: &str,
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let children = self
+            .get_children_arrow(&[parent_id.to_string()])
+            .await?
+            .remove(parent_id)
+            .unwrap_or_default();
+        hierarchy::order_siblings(parent_id, children)
+    }
 
-            let before_sibling_id = batch
-                .column_by_name("before_sibling_id")
-                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                .and_then(|arr| {
-                    if arr.is_null(i) {
-                        None
-                    } else {
-                        Some(arr.value(i).to_string())
-                    }
-                });
+    /// Derive a node's `root_id` from its parent before it is written
+    ///
+    /// A child belongs to its parent's root, or to the parent itself when the
+    /// parent is a root. New nodes without a parent keep the `root_id` they
+    /// were given (update paths clear it first, see `clear_root_if_detached`).
+    /// Walks the parent chain so a node can't become its own ancestor.
+    async fn resolve_root_id(&self, universal: &mut UniversalNode) -> Result<(), DataStoreError> {
+        self.resolve_root_ids(std::slice::from_mut(universal)).await
+    }
 
-            let metadata_str = batch
-                .column_by_name("metadata")
-                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                .and_then(|arr| {
-                    if arr.is_null(i) {
-                        None
-                    } else {
-                        Some(arr.value(i).to_string())
-                    }
-                });
+    /// `resolve_root_id` for rows written together
+    ///
+    /// Parents are looked up in the batch before the table, so a batch may
+    /// hold parents and children in any order. Stored ancestors are fetched
+    /// one hierarchy level at a time for the whole batch. Nothing is changed
+    /// if any row fails validation.
+    async fn resolve_root_ids(
+        &self,
+        universals: &mut [UniversalNode],
+    ) -> Result<(), DataStoreError> {
+        let in_batch: HashMap<String, usize> = universals
+            .iter()
+            .enumerate()
+            .map(|(index, universal)| (universal.id.clone(), index))
+            .collect();
 
-            // Convert metadata string back to JSON Value
-            let metadata = metadata_str.and_then(|s| serde_json::from_str(&s).ok());
+        let mut stored: HashMap<String, UniversalNode> = HashMap::new();
+        let mut wanted: Vec<String> = universals
+            .iter()
+            .filter_map(|universal| universal.parent_id.clone())
+            .collect();
+        loop {
+            wanted.retain(|id| !in_batch.contains_key(id) && !stored.contains_key(id));
+            wanted.sort();
+            wanted.dedup();
+            if wanted.is_empty() {
+                break;
+            }
+            let found = self.get_universal_nodes_arrow(&wanted).await?;
+            wanted = found
+                .values()
+                .filter_map(|row| row.parent_id.clone())
+                .collect();
+            stored.extend(found);
+        }
 
-            // Extract vector embedding from FixedSizeListArray
-            let vector = if !vector_list_array.is_null(i) {
-                let vector_list = vector_list_array.value(i);
-                if let Some(float_array) = vector_list
-                    .as_any()
-                    .downcast_ref::<arrow_array::Float32Array>()
-                {
-                    (0..float_array.len())
-                        .map(|j| float_array.value(j))
-                        .collect()
-                } else {
-                    vec![0.0; self.vector_dimension] // Fallback if vector extraction fails
+        let parent_of = |id: &str| -> Option<Option<&String>> {
+            match in_batch.get(id) {
+                Some(&index) => Some(universals[index].parent_id.as_ref()),
+                None => stored.get(id).map(|row| row.parent_id.as_ref()),
+            }
+        };
+        for universal in universals.iter() {
+            let mut chain = vec![universal.id.clone()];
+            let mut ancestor = universal.parent_id.clone();
+            while let Some(id) = ancestor {
+                if id == universal.id {
+                    chain.push(id);
+                    return Err(DataStoreError::ParentCycle { node_ids: chain });
                 }
-            } else {
-                vec![0.0; self.vector_dimension] // Fallback for null vector
-            };
-
-            // Extract children_ids from ListArray
-            let children_ids = if let Some(children_list_array) = children_list_array {
-                if !children_list_array.is_null(i) {
-                    let children_list = children_list_array.value(i);
-                    if let Some(string_array) = children_list.as_any().downcast_ref::<StringArray>()
-                    {
-                        (0..string_array.len())
-                            .map(|j| string_array.value(j).to_string())
-                            .collect()
-                    } else {
-                        vec![]
-                    }
-                } else {
-                    vec![]
+                // A loop further up doesn't involve this node; stop walking it
+                if chain.contains(&id) {
+                    break;
                 }
-            } else {
-                vec![]
-            };
+                let Some(parent_id) = parent_of(&id) else {
+                    break;
+                };
+                ancestor = parent_id.cloned();
+                chain.push(id);
+            }
+        }
 
-            // Extract mentions from ListArray
-            let mentions = if let Some(mentions_list_array) = mentions_list_array {
-                if !mentions_list_array.is_null(i) {
-                    let mentions_list = mentions_list_array.value(i);
-                    if let Some(string_array) = mentions_list.as_any().downcast_ref::<StringArray>()
-                    {
-                        (0..string_array.len())
-                            .map(|j| string_array.value(j).to_string())
-                            .collect()
-                    } else {
-                        vec![]
-                    }
-                } else {
-                    vec![]
+        // Each row is resolved after its parent in the batch, so children see
+        // the parent's new root
+        let mut roots: Vec<Option<Option<String>>> = vec![None; universals.len()];
+        for start in 0..universals.len() {
+            let mut path = Vec::new();
+            let mut current = start;
+            while roots[current].is_none() {
+                path.push(current);
+                match universals[current]
+                    .parent_id
+                    .as_ref()
+                    .and_then(|parent_id| in_batch.get(parent_id))
+                {
+                    Some(&parent) => current = parent,
+                    None => break,
                 }
-            } else {
-                vec![]
-            };
-
-            // Extract root hierarchy optimization fields
-            let root_id = batch
-                .column_by_name("root_id")
-                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
-                .and_then(|arr| {
-                    if arr.is_null(i) {
-                        None
-                    } else {
-                        Some(arr.value(i).to_string())
-                    }
-                });
+            }
 
-            // root_type field removed
+            for &index in path.iter().rev() {
+                let universal = &universals[index];
+                let root = match &universal.parent_id {
+                    None => universal.root_id.clone(),
+                    Some(parent_id) => match (in_batch.get(parent_id), stored.get(parent_id)) {
+                        (Some(&parent), _) => Some(
+                            roots[parent]
+                                .clone()
+                                .flatten()
+                                .unwrap_or_else(|| parent_id.clone()),
+                        ),
+                        (None, Some(parent)) => {
+                            Some(parent.root_id.clone().unwrap_or_else(|| parent.id.clone()))
+                        }
+                        (None, None)
+                            if self.hierarchy_validation == HierarchyValidation::Strict =>
+                        {
+                            return Err(DataStoreError::ParentNotFound {
+                                node_id: universal.id.clone(),
+                                parent_id: parent_id.clone(),
+                            });
+                        }
+                        (None, None) => universal.root_id.clone(),
+                    },
+                };
+                roots[index] = Some(root);
+            }
+        }
 
-            let node = UniversalNode {
-                id,
-                r#type: node_type,
-                content,
-                individual_vector: vector.clone(),
-                contextual_vector: None,
-                hierarchical_vector: None,
-                embedding_model: None,
-                embeddings_generated_at: None,
-                vector,
-                parent_id,
-                before_sibling_id,
-                children_ids,
-                mentions,
-                root_id,   // Root hierarchy optimization
-                // root_type field removed
-                created_at,
-                updated_at,
-                metadata,
-            };
+        for (universal, root) in universals.iter_mut().zip(roots) {
+            universal.root_id = root.flatten();
+        }
+        Ok(())
+    }
 
-            nodes.push(node);
+    /// Descendants of an updated node, re-rooted if the node's root moved
+    ///
+    /// Children of a root belong to the root itself, so the root its
+    /// descendants share is the node's `root_id` or else the node's id.
+    /// Returns nothing when that hasn't changed. Rows in `batch` (and what
+    /// is stored under them) are left to the batch, which writes them itself.
+    async fn rerooted_descendants(
+        &self,
+        updated: &UniversalNode,
+        existing: &UniversalNode,
+        batch: &HashSet<String>,
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let subtree_root =
+            |node: &UniversalNode| node.root_id.clone().unwrap_or_else(|| node.id.clone());
+        let new_root = subtree_root(updated);
+        if new_root == subtree_root(existing) {
+            return Ok(Vec::new());
         }
 
-        Ok(nodes)
+        // Breadth-first, so a parent is always seen before its children
+        let mut skipped: HashSet<String> = HashSet::new();
+        let mut descendants = self.get_descendants_arrow(&updated.id).await?;
+        descendants.retain(|descendant| {
+            let under_batch_row =
+                matches!(&descendant.parent_id, Some(parent_id) if skipped.contains(parent_id));
+            if batch.contains(&descendant.id) || under_batch_row {
+                skipped.insert(descendant.id.clone());
+                return false;
+            }
+            true
+        });
+        for descendant in &mut descendants {
+            descendant.root_id = Some(new_root.clone());
+            descendant.updated_at = updated.updated_at.clone();
+        }
+        Ok(descendants)
     }
 
-    /// Extract distance scores from LanceDB query results
-    fn extract_distances_from_batch(&self, batch: &RecordBatch) -> Result<Vec<f32>, DataStoreError> {
-        // LanceDB typically returns distances in a column named "_distance"
-        let distances = batch
-            .column_by_name("_distance")
-            .and_then(|col| col.as_any().downcast_ref::<arrow_array::Float32Array>())
+    /// Place `node_id` directly before or after `anchor_id`, under the anchor's parent
+    async fn move_node_beside(
+        &self,
+        node_id: &NodeId,
+        anchor_id: &NodeId,
+        after: bool,
+    ) -> Result<(), DataStoreError> {
+        let (node_key, anchor_key) = (node_id.to_string(), anchor_id.to_string());
+        if node_key == anchor_key {
+            return Err(DataStoreError::InvalidNode(format!(
+                "Cannot move node {} relative to itself",
+                node_key
+            )));
+        }
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        let anchor = self
+            .get_universal_node_arrow(anchor_id)
+            .await?
             .ok_or_else(|| {
-                DataStoreError::Arrow("Missing or invalid _distance column in search results".to_string())
+                DataStoreError::NodeNotFound(format!("Node {} not found for move", anchor_key))
             })?;
+        let Some(new_parent) = anchor.parent_id else {
+            return Err(DataStoreError::InvalidNode(format!(
+                "Anchor node {} has no parent to order siblings under",
+                anchor_key
+            )));
+        };
 
-        let mut distance_values = Vec::new();
-        for i in 0..distances.len() {
-            let distance = if distances.is_null(i) {
-                f32::INFINITY // Treat null distances as infinite (no similarity)
-            } else {
-                distances.value(i)
-            };
-            distance_values.push(distance);
-        }
-
-        Ok(distance_values)
+        let placement = if after {
+            SiblingPlacement::After(anchor_key)
+        } else {
+            SiblingPlacement::Before(anchor_key)
+        };
+        self.relocate_subtree(&node_key, &new_parent, placement)
+            .await
     }
 
-    /// Vector similarity search using Arrow storage
-    async fn vector_search_arrow(
+    /// Move a node and its descendants under `new_parent`
+    ///
+    /// Unlinks the node from its old siblings and parent, links it in at
+    /// `placement`, and gives the whole subtree the new parent's `root_id`.
+    /// Every changed row is written with one merge_insert, so readers never
+    /// see a half-moved tree. Callers hold `hierarchy_lock`.
+    async fn relocate_subtree(
         &self,
-        embedding: Vec<f32>,
-        limit: usize,
-    ) -> Result<Vec<(Node, f32)>, DataStoreError> {
-        let table_guard = self.table.read().await;
-        if let Some(table) = table_guard.as_ref() {
-            // Perform vector similarity search
-            let query_builder = table.query().nearest_to(embedding.clone()).map_err(|e| {
-                DataStoreError::LanceDB(format!("Failed to create nearest_to query: {}", e))
-            })?;
-
-            let results = query_builder
-                .limit(limit)
-                .execute()
-                .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Vector search failed: {}", e)))?;
+        node_key: &str,
+        new_parent: &str,
+        placement: SiblingPlacement,
+    ) -> Result<(), DataStoreError> {
+        let mut rows = self
+            .get_universal_nodes_arrow(&[node_key.to_string(), new_parent.to_string()])
+            .await?;
+        for id in [node_key, new_parent] {
+            if !rows.contains_key(id) {
+                return Err(DataStoreError::NodeNotFound(format!(
+                    "Node {} not found for move",
+                    id
+                )));
+            }
+        }
 
-            let batches: Vec<RecordBatch> = futures::TryStreamExt::try_collect(results)
+        // A node can't move under itself or one of its descendants
+        let mut ancestor = Some(new_parent.to_string());
+        let mut walked = HashSet::new();
+        while let Some(id) = ancestor.filter(|id| walked.insert(id.clone())) {
+            if id == node_key {
+                return Err(DataStoreError::InvalidNode(format!(
+                    "Cannot move node {} under itself or its descendant {}",
+                    node_key, new_parent
+                )));
+            }
+            ancestor = self
+                .get_universal_nodes_arrow(std::slice::from_ref(&id))
+                .await?
+                .remove(&id)
+                .and_then(|node| node.parent_id);
+        }
+
+        let mut changed = Vec::new();
+        if rows[node_key].parent_id.as_deref() != Some(new_parent) {
+            changed.extend(self.unlink_node(node_key, &mut rows).await?);
+        }
+
+        let mut order = Vec::new();
+        for sibling in self.get_ordered_children_strict(new_parent).await? {
+            if sibling.id != node_key {
+                order.push(sibling.id.clone());
+                rows.entry(sibling.id.clone()).or_insert(sibling);
+            }
+        }
+        let position = match &placement {
+            SiblingPlacement::At(position) => (*position).min(order.len()),
+            SiblingPlacement::Before(anchor) | SiblingPlacement::After(anchor) => {
+                let index = order.iter().position(|id| id == anchor).ok_or_else(|| {
+                    DataStoreError::NodeNotFound(format!("Node {} not found for move", anchor))
+                })?;
+                index + usize::from(matches!(placement, SiblingPlacement::After(_)))
+            }
+        };
+        order.insert(position, node_key.to_string());
+        changed.extend(hierarchy::relink_siblings(new_parent, &order, &mut rows));
+
+        if let Some(parent) = rows.get_mut(new_parent) {
+            if !parent.children_ids.iter().any(|id| id == node_key) {
+                let index = position.min(parent.children_ids.len());
+                parent.children_ids.insert(index, node_key.to_string());
+                changed.push(new_parent.to_string());
+            }
+        }
+
+        // Descendants follow the node into the new parent's hierarchy
+        let new_root = {
+            let parent = &rows[new_parent];
+            parent.root_id.clone().unwrap_or_else(|| parent.id.clone())
+        };
+        if rows[node_key].root_id.as_deref() != Some(new_root.as_str()) {
+            let mut subtree = vec![node_key.to_string()];
+            for descendant in self.get_descendants_arrow(node_key).await? {
+                subtree.push(descendant.id.clone());
+                rows.entry(descendant.id.clone()).or_insert(descendant);
+            }
+            for id in subtree {
+                if let Some(row) = rows.get_mut(&id) {
+                    row.root_id = Some(new_root.clone());
+                }
+                changed.push(id);
+            }
+        }
+
+        self.write_changed_rows(changed, rows, &[]).await
+    }
+
+    /// Take a node out of its sibling chain and its parent's `children_ids`
+    ///
+    /// The node's next sibling inherits its `before_sibling_id`. Rows are
+    /// edited in `rows` (fetching any that are missing) and the changed ids
+    /// returned for the caller's write.
+    async fn unlink_node(
+        &self,
+        node_key: &str,
+        rows: &mut HashMap<String, UniversalNode>,
+    ) -> Result<Vec<String>, DataStoreError> {
+        let Some(node) = rows.get(node_key).cloned() else {
+            return Ok(Vec::new());
+        };
+
+        let mut changed = Vec::new();
+        for follower in self
+            .nodes_where_in("before_sibling_id", &[node_key.to_string()])
+            .await?
+        {
+            if follower.parent_id != node.parent_id {
+                continue;
+            }
+            let follower = rows.entry(follower.id.clone()).or_insert(follower);
+            follower.before_sibling_id = node.before_sibling_id.clone();
+            changed.push(follower.id.clone());
+        }
+
+        if let Some(parent_id) = &node.parent_id {
+            if !rows.contains_key(parent_id) {
+                rows.extend(
+                    self.get_universal_nodes_arrow(std::slice::from_ref(parent_id))
+                        .await?,
+                );
+            }
+            if let Some(parent) = rows.get_mut(parent_id) {
+                let before = parent.children_ids.len();
+                parent.children_ids.retain(|id| id != node_key);
+                if parent.children_ids.len() != before {
+                    changed.push(parent_id.clone());
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Write the changed rows and delete `delete_ids` as one table version
+    async fn write_changed_rows(
+        &self,
+        changed: Vec<String>,
+        mut rows: HashMap<String, UniversalNode>,
+        delete_ids: &[String],
+    ) -> Result<(), DataStoreError> {
+        let deleted: HashSet<&String> = delete_ids.iter().collect();
+        let now = chrono::Utc::now().to_rfc3339();
+        let updates: Vec<UniversalNode> = changed
+            .iter()
+            .filter(|id| !deleted.contains(id))
+            .filter_map(|id| rows.remove(id))
+            .map(|mut row| {
+                row.updated_at = now.clone();
+                row
+            })
+            .collect();
+        self.merge_nodes_arrow(updates, delete_ids).await
+    }
+
+    /// Stored nodes for `ids` in the same order, skipping ids with no row
+    async fn get_universal_nodes_ordered(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let mut found = self.get_universal_nodes_arrow(ids).await?;
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    /// Neighbours of `node` along each of `options.edges`, keeping the node
+    /// types allowed `depth` hops from the start
+    async fn traversal_neighbours(
+        &self,
+        node: &UniversalNode,
+        depth: usize,
+        options: &TraversalOptions,
+    ) -> Result<Vec<(TraversalEdge, UniversalNode)>, DataStoreError> {
+        let mut neighbours = Vec::new();
+        for edge in &options.edges {
+            let nodes = match edge {
+                TraversalEdge::Parent => {
+                    let parent_ids: Vec<String> = node.parent_id.iter().cloned().collect();
+                    self.get_universal_nodes_ordered(&parent_ids).await?
+                }
+                TraversalEdge::Children => {
+                    let children = self
+                        .get_children_arrow(&[node.id.clone()])
+                        .await?
+                        .remove(&node.id)
+                        .unwrap_or_default();
+                    hierarchy::order_siblings_lenient(children)
+                }
+                // Links to deleted nodes are skipped
+                TraversalEdge::Mentions => self.get_universal_nodes_ordered(&node.mentions).await?,
+                TraversalEdge::MentionedBy => {
+                    let node_id = NodeId::from_string(node.id.clone());
+                    self.find_nodes_arrow(&NodeQuery::new().mentions(&node_id))
+                        .await?
+                }
+                TraversalEdge::Relationship {
+                    rel_type,
+                    direction,
+                } => {
+                    let mut edges = self
+                        .query_relationships(relationships::direction_predicate(
+                            &node.id,
+                            *direction,
+                            rel_type.as_deref(),
+                        ))
+                        .await?;
+                    edges.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+                    let other_ends: Vec<String> = edges
+                        .iter()
+                        .map(|edge| {
+                            if edge.from.as_str() == node.id {
+                                edge.to.to_string()
+                            } else {
+                                edge.from.to_string()
+                            }
+                        })
+                        .collect();
+                    self.get_universal_nodes_ordered(&other_ends).await?
+                }
+            };
+            neighbours.extend(
+                nodes
+                    .into_iter()
+                    .filter(|neighbour| traversal::type_allowed(options, depth, &neighbour.r#type))
+                    .map(|neighbour| (edge.clone(), neighbour)),
+            );
+        }
+        Ok(neighbours)
+    }
+
+    /// Hop distance from the nearest anchor for every node within `max_hops`
+    ///
+    /// Breadth-first over `parent_id`, `children_ids`, `mentions` and `root_id`,
+    /// one round of chunked predicates per hop.
+    async fn hop_distances(
+        &self,
+        anchors: &[NodeId],
+        max_hops: usize,
+    ) -> Result<HashMap<String, usize>, DataStoreError> {
+        let mut distances: HashMap<String, usize> = anchors
+            .iter()
+            .map(|anchor| (anchor.to_string(), 0))
+            .collect();
+        let mut frontier: HashSet<String> = distances.keys().cloned().collect();
+
+        for hop in 1..=max_hops {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut next = HashSet::new();
+            for links in self.node_links_touching(&frontier).await? {
+                for id in scoring::adjacent_ids(&links, &frontier) {
+                    if !distances.contains_key(&id) {
+                        distances.insert(id.clone(), hop);
+                        next.insert(id);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        Ok(distances)
+    }
+
+    /// Relationship columns of the given nodes and of every node linking to them
+    async fn node_links_touching(
+        &self,
+        ids: &HashSet<String>,
+    ) -> Result<Vec<NodeLinks>, DataStoreError> {
+        let ids: Vec<String> = ids.iter().cloned().collect();
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let mut links = Vec::new();
+        for chunk in ids.chunks(ID_PREDICATE_CHUNK_SIZE) {
+            let literals = chunk
+                .iter()
+                .map(|id| sql_string_literal(id))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let predicate = format!(
+                "id IN ({literals}) OR parent_id IN ({literals}) OR root_id IN ({literals}) \
+                 OR array_has_any(children_ids, [{literals}]) \
+                 OR array_has_any(mentions, [{literals}])"
+            );
+
+            let results_stream = table
+                .query()
+                .select(Select::columns(&[
+                    "id",
+                    "parent_id",
+                    "children_ids",
+                    "mentions",
+                    "root_id",
+                ]))
+                .only_if(predicate)
+                .execute()
                 .await
                 .map_err(|e| {
-                    DataStoreError::LanceDB(format!("Failed to collect search results: {}", e))
+                    DataStoreError::LanceDB(format!("Graph neighbour query failed: {}", e))
                 })?;
 
-            let mut results = Vec::new();
-            for batch in batches {
-                let universal_nodes = self.extract_nodes_from_batch(&batch)?;
-                let distances = self.extract_distances_from_batch(&batch)?;
+            let batches: Vec<RecordBatch> = results_stream.try_collect().await.map_err(|e| {
+                DataStoreError::LanceDB(format!("Failed to collect query results: {}", e))
+            })?;
+            for batch in batches.iter() {
+                links.extend(node_links_from_batch(batch)?);
+            }
+        }
 
-                for (i, universal_node) in universal_nodes.into_iter().enumerate() {
-                    let node = self.universal_to_node(universal_node);
-                    
-                    // Convert LanceDB distance to similarity score
-                    // LanceDB returns squared L2 distances, convert to cosine similarity (0-1 range)
-                    let distance = distances.get(i).copied().unwrap_or(f32::INFINITY);
-                    let similarity = if distance.is_finite() && distance >= 0.0 {
-                        // Convert distance to similarity: closer distances = higher similarity
-                        // For normalized vectors, squared L2 distance relates to cosine similarity as:
-                        // cosine_similarity = 1 - (squared_l2_distance / 2)
-                        let cosine_sim = 1.0 - (distance / 2.0);
-                        cosine_sim.clamp(0.0, 1.0) // Clamp to [0, 1]
-                    } else {
-                        0.0 // Invalid distance = zero similarity
-                    };
-                    
-                    results.push((node, similarity));
+        Ok(links)
+    }
+
+    /// Hierarchy columns of every row, read batch by batch
+    async fn scan_hierarchy_links(&self) -> Result<Vec<HierarchyLinks>, DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let mut batches = table
+            .query()
+            .select(Select::columns(&[
+                "id",
+                "parent_id",
+                "before_sibling_id",
+                "children_ids",
+                "root_id",
+            ]))
+            .execute()
+            .await
+            .map_err(|e| DataStoreError::LanceDB(format!("Hierarchy scan failed: {}", e)))?;
+
+        let mut links = Vec::new();
+        while let Some(batch) = batches.try_next().await.map_err(|e| {
+            DataStoreError::LanceDBQuery(format!("Failed to read result batch: {}", e))
+        })? {
+            links.extend(hierarchy_links_from_batch(&batch)?);
+        }
+        Ok(links)
+    }
+
+    /// Delete many nodes with chunked `id IN (...)` predicates
+    async fn delete_nodes_arrow(&self, ids: &[String]) -> Result<(), DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        for chunk in ids.chunks(ID_PREDICATE_CHUNK_SIZE) {
+            table.delete(&id_in_predicate(chunk)).await.map_err(|e| {
+                DataStoreError::LanceDB(format!("Batch delete operation failed: {}", e))
+            })?;
+        }
+        drop(table_guard);
+
+        self.delete_relationships_touching(ids).await
+    }
+
+    /// Write a typed edge, keeping `created_at` if the edge already exists
+    async fn store_relationship(
+        &self,
+        from: &NodeId,
+        to: &NodeId,
+        rel_type: &str,
+        properties: Option<serde_json::Value>,
+    ) -> Result<(), DataStoreError> {
+        let (from_key, to_key) = (from.to_string(), to.to_string());
+        let nodes = self
+            .get_universal_nodes_arrow(&[from_key.clone(), to_key.clone()])
+            .await?;
+        for id in [&from_key, &to_key] {
+            if !nodes.contains_key(id) {
+                return Err(DataStoreError::NodeNotFound(format!(
+                    "Node {} not found for relationship",
+                    id
+                )));
+            }
+        }
+
+        let created_at = self
+            .query_relationships(relationships::edge_predicate(&from_key, &to_key, rel_type))
+            .await?
+            .into_iter()
+            .next()
+            .map(|existing| existing.created_at)
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+        let batch = relationships::relationships_to_batch(&[Relationship {
+            from: from.clone(),
+            to: to.clone(),
+            rel_type: rel_type.to_string(),
+            properties,
+            created_at,
+        }])?;
+
+        let table_guard = self.relationship_table.read().await;
+        let table = table_guard.as_ref().ok_or_else(|| {
+            DataStoreError::LanceDB("Relationships table not initialized".to_string())
+        })?;
+        let batches = RecordBatchIterator::new(
+            vec![batch].into_iter().map(Ok),
+            relationships::relationship_schema(),
+        );
+        let mut merge_insert = table.merge_insert(relationships::RELATIONSHIP_KEY_COLUMNS);
+        merge_insert
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all();
+        merge_insert
+            .execute(Box::new(batches))
+            .await
+            .map_err(|e| DataStoreError::LanceDB(format!("Relationship upsert failed: {}", e)))?;
+        drop(table_guard);
+
+        // The edge is committed; an index failure is recorded, not returned
+        self.maintain_relationship_indexes().await;
+        Ok(())
+    }
+
+    /// Edges matching a predicate on the relationships table
+    async fn query_relationships(
+        &self,
+        predicate: String,
+    ) -> Result<Vec<Relationship>, DataStoreError> {
+        let table_guard = self.relationship_table.read().await;
+        let table = table_guard.as_ref().ok_or_else(|| {
+            DataStoreError::LanceDB("Relationships table not initialized".to_string())
+        })?;
+
+        let results_stream = table
+            .query()
+            .only_if(predicate)
+            .execute()
+            .await
+            .map_err(|e| DataStoreError::LanceDB(format!("Relationship query failed: {}", e)))?;
+        let batches: Vec<RecordBatch> = results_stream.try_collect().await.map_err(|e| {
+            DataStoreError::LanceDB(format!("Failed to collect query results: {}", e))
+        })?;
+
+        let mut found = Vec::new();
+        for batch in batches.iter() {
+            found.extend(relationships::relationships_from_batch(batch)?);
+        }
+        Ok(found)
+    }
+
+    /// Delete edges matching a predicate on the relationships table
+    async fn delete_relationships_where(&self, predicate: &str) -> Result<(), DataStoreError> {
+        let table_guard = self.relationship_table.read().await;
+        let table = table_guard.as_ref().ok_or_else(|| {
+            DataStoreError::LanceDB("Relationships table not initialized".to_string())
+        })?;
+        table
+            .delete(predicate)
+            .await
+            .map_err(|e| DataStoreError::LanceDB(format!("Relationship delete failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Delete every edge starting or ending at one of `ids`
+    async fn delete_relationships_touching(&self, ids: &[String]) -> Result<(), DataStoreError> {
+        for chunk in ids.chunks(ID_PREDICATE_CHUNK_SIZE) {
+            self.delete_relationships_where(&relationships::touching_predicate(chunk))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Embedding for updated content: generated when a generator is configured, zeros otherwise
+    async fn embedding_for_content(&self, content: &serde_json::Value) -> Vec<f32> {
+        if let Some(ref generator) = self.embedding_generator {
+            if let Ok(embedding) = generator.generate_embedding(&content.to_string()).await {
+                return embedding;
+            }
+        }
+        vec![0.0; self.vector_dimension]
+    }
+
+    /// Build the row for an updated node, regenerating the embedding only if content changed
+    ///
+    /// Contextual and hierarchical embeddings are kept alongside an unchanged
+    /// individual embedding unless the node supplies new ones. `children_ids`
+    /// and `before_sibling_id` stay as stored unless the node supplies them,
+    /// since text and date nodes come back from `get_node` without metadata.
+    async fn prepare_update(&self, node: Node, existing: &UniversalNode) -> UniversalNode {
+        let supplies_children = matches!(
+            node.metadata.as_ref().and_then(|m| m.get("children_ids")),
+            Some(serde_json::Value::Array(_))
+        );
+
+        let mut universal = if existing.content != content_to_string(&node.content) {
+            let embedding = self.embedding_for_content(&node.content).await;
+            self.node_to_universal(node, Some(embedding))
+        } else {
+            let mut universal = self.node_to_universal(node, Some(existing.vector.clone()));
+            keep_stored_embeddings(&mut universal, existing);
+            universal
+        };
+
+        if !supplies_children {
+            universal.children_ids = existing.children_ids.clone();
+        }
+        if universal.before_sibling_id.is_none() {
+            universal.before_sibling_id = existing.before_sibling_id.clone();
+        }
+        universal
+    }
+
+    /// Insert-or-replace rows keyed on `id` with LanceDB merge_insert
+    ///
+    /// All rows are committed as a single table version, so a crash can never
+    /// leave a node deleted but not yet re-inserted.
+    async fn upsert_nodes_arrow(
+        &self,
+        universal_nodes: Vec<UniversalNode>,
+    ) -> Result<(), DataStoreError> {
+        self.merge_nodes_arrow(universal_nodes, &[]).await
+    }
+
+    /// `upsert_nodes_arrow` that also deletes the rows in `delete_ids`
+    ///
+    /// Deletes ride on the same merge_insert (rows not in the source batch
+    /// that match `id IN (...)`), so writes and deletes land as one version.
+    async fn merge_nodes_arrow(
+        &self,
+        universal_nodes: Vec<UniversalNode>,
+        delete_ids: &[String],
+    ) -> Result<(), DataStoreError> {
+        if universal_nodes.is_empty() && delete_ids.is_empty() {
+            return Ok(());
+        }
+
+        let schema = self.create_universal_schema();
+        let batch = self.create_record_batch_from_nodes(universal_nodes, schema.clone())?;
+
+        let table_guard = self.table.read().await;
+        if let Some(table) = table_guard.as_ref() {
+            let batches = RecordBatchIterator::new(vec![batch].into_iter().map(Ok), schema);
+
+            let mut merge_insert = table.merge_insert(&["id"]);
+            merge_insert
+                .when_matched_update_all(None)
+                .when_not_matched_insert_all();
+            if !delete_ids.is_empty() {
+                merge_insert.when_not_matched_by_source_delete(Some(id_in_predicate(delete_ids)));
+            }
+            merge_insert
+                .execute(Box::new(batches))
+                .await
+                .map_err(|e| DataStoreError::LanceDB(format!("Upsert failed: {}", e)))?;
+        } else {
+            return Err(DataStoreError::LanceDB("Table not initialized".to_string()));
+        }
+        drop(table_guard);
+
+        // Edges of deleted nodes go with them
+        self.delete_relationships_touching(delete_ids).await?;
+
+        self.maintain_scalar_indexes().await;
+        self.schedule_vector_index_maintenance().await;
+
+        Ok(())
+    }
+
+    /// Query nodes from Arrow storage with native LanceDB filtering
+    async fn query_nodes_arrow(&self, query: &str) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let node_query = if query.is_empty() {
+            NodeQuery::new()
+        } else {
+            NodeQuery::new().content_contains(query)
+        };
+        self.find_nodes_arrow(&node_query).await
+    }
+
+    /// Start a structured query with its SQL filter pushed down into LanceDB
+    ///
+    /// Returns the raw batch stream and whether limit/offset were applied by
+    /// LanceDB. Paging can only run inside LanceDB when it sees every predicate
+    /// and no ordering has to be applied afterwards.
+    async fn execute_node_query(
+        &self,
+        query: &NodeQuery,
+    ) -> Result<(SendableRecordBatchStream, bool), DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let mut lance_query = table.query();
+        if let Some(filter) = query.to_sql_filter() {
+            lance_query = lance_query.only_if(filter);
+        }
+
+        let push_down_paging = !query.has_residual_predicates() && query.order_by.is_none();
+        if push_down_paging {
+            if let Some(limit) = query.limit {
+                lance_query = lance_query.limit(limit);
+            }
+            if query.offset > 0 {
+                lance_query = lance_query.offset(query.offset);
+            }
+        }
+
+        let results = lance_query
+            .execute()
+            .await
+            .map_err(|e| DataStoreError::LanceDBQuery(format!("Query failed: {}", e)))?;
+
+        Ok((results, push_down_paging))
+    }
+
+    /// Stream matching nodes batch by batch without collecting the table
+    ///
+    /// Ordering is ignored here; callers that need it go through `find_nodes_arrow`.
+    async fn scan_nodes_arrow(
+        &self,
+        query: &NodeQuery,
+    ) -> Result<BoxStream<'_, Result<UniversalNode, DataStoreError>>, DataStoreError> {
+        let (batches, push_down_paging) = self.execute_node_query(query).await?;
+
+        let residual = query.clone();
+        let nodes = batches
+            .map(move |batch| {
+                let batch = batch.map_err(|e| {
+                    DataStoreError::LanceDBQuery(format!("Failed to read result batch: {}", e))
+                })?;
+                self.extract_nodes_from_batch(&batch)
+            })
+            .map_ok(|nodes| stream::iter(nodes.into_iter().map(Ok::<_, DataStoreError>)))
+            .try_flatten()
+            .try_filter(move |node| future::ready(residual.matches_residual(node)));
+
+        if push_down_paging {
+            Ok(nodes.boxed())
+        } else {
+            Ok(nodes
+                .skip(query.offset)
+                .take(query.limit.unwrap_or(usize::MAX))
+                .boxed())
+        }
+    }
+
+    /// Run a structured query and collect the matching nodes in the requested order
+    async fn find_nodes_arrow(
+        &self,
+        query: &NodeQuery,
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        if query.order_by.is_none() {
+            return self.scan_nodes_arrow(query).await?.try_collect().await;
+        }
+
+        let count = query.limit.map(|limit| query.offset.saturating_add(limit));
+        let nodes = self.first_in_order_arrow(query, count).await?;
+        Ok(nodes
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// The first `count` matches in `query`'s order, or every match for `None`
+    ///
+    /// LanceDB has no ORDER BY, so matches are streamed and the buffer is
+    /// sorted and cut back to `count` whenever it doubles: memory stays
+    /// proportional to `count` rather than to the number of matches.
+    async fn first_in_order_arrow(
+        &self,
+        query: &NodeQuery,
+        count: Option<usize>,
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let unpaged = NodeQuery {
+            limit: None,
+            offset: 0,
+            ..query.clone()
+        };
+        let mut matches = self.scan_nodes_arrow(&unpaged).await?;
+        let mut nodes = Vec::new();
+        while let Some(node) = matches.try_next().await? {
+            nodes.push(node);
+            if let Some(count) = count {
+                if nodes.len() >= count.max(1).saturating_mul(2) {
+                    query.sort(&mut nodes);
+                    nodes.truncate(count);
                 }
             }
+        }
+
+        query.sort(&mut nodes);
+        if let Some(count) = count {
+            nodes.truncate(count);
+        }
+        Ok(nodes)
+    }
+
+    /// The `count` smallest matching ids, in ascending order
+    ///
+    /// Reads only the `id` column, buffering like `first_in_order_arrow`.
+    async fn first_ids_arrow(
+        &self,
+        query: &NodeQuery,
+        count: usize,
+    ) -> Result<Vec<String>, DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let mut lance_query = table.query().select(Select::columns(&["id"]));
+        if let Some(filter) = query.to_sql_filter() {
+            lance_query = lance_query.only_if(filter);
+        }
+        let mut batches = lance_query
+            .execute()
+            .await
+            .map_err(|e| DataStoreError::LanceDBQuery(format!("Query failed: {}", e)))?;
+
+        let mut ids = Vec::new();
+        while let Some(batch) = batches.try_next().await.map_err(|e| {
+            DataStoreError::LanceDBQuery(format!("Failed to read result batch: {}", e))
+        })? {
+            ids.extend(
+                (0..batch.num_rows()).filter_map(|row| optional_string_at(&batch, "id", row)),
+            );
+            if ids.len() >= count.max(1).saturating_mul(2) {
+                ids.sort_unstable();
+                ids.truncate(count);
+            }
+        }
+
+        ids.sort_unstable();
+        ids.truncate(count);
+        Ok(ids)
+    }
+
+    /// One page of `query` in page order, resuming after `position`
+    ///
+    /// The keyset predicate is pushed down, so each page only reads rows
+    /// after the previous one. Returns the page and where the next one
+    /// starts, `None` once the results are exhausted.
+    async fn find_page_arrow(
+        &self,
+        query: &NodeQuery,
+        page_size: usize,
+        position: &PagePosition,
+    ) -> Result<(Vec<UniversalNode>, Option<PagePosition>), DataStoreError> {
+        let remaining = query
+            .limit
+            .map_or(usize::MAX, |limit| limit.saturating_sub(position.returned));
+        let page_len = page_size.min(remaining);
+        // The query's own offset only applies to the first page
+        let skip = if position.after.is_none() {
+            query.offset
+        } else {
+            0
+        };
+
+        let page_query = NodeQuery {
+            order_by: Some(query.page_order()),
+            after: position.after.clone(),
+            ..query.clone()
+        };
+        // Fetch one extra row to learn whether another page exists
+        let count = skip.saturating_add(page_len).saturating_add(1);
+        let mut nodes = if query.order_by.is_none() && !query.has_residual_predicates() {
+            // Unordered pages go by id: only the id column of the remaining
+            // matches is scanned, and just the page's rows are read in full
+            let ids = self.first_ids_arrow(&page_query, count).await?;
+            self.get_universal_nodes_ordered(&ids).await?
+        } else {
+            self.first_in_order_arrow(&page_query, Some(count)).await?
+        };
+        nodes.drain(..skip.min(nodes.len()));
+
+        let has_more = nodes.len() > page_len && page_len < remaining;
+        nodes.truncate(page_len);
+        let next = match nodes.last() {
+            Some(last) if has_more => Some(PagePosition {
+                after: Some(page_query.page_key(last)),
+                returned: position.returned + page_len,
+            }),
+            _ => None,
+        };
+        Ok((nodes, next))
+    }
+
+    /// Extract UniversalNode objects from Arrow RecordBatch with proper ListArray handling
+    fn extract_nodes_from_batch(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<UniversalNode>, DataStoreError> {
+        let mut nodes = Vec::new();
+        let num_rows = batch.num_rows();
+
+        if num_rows == 0 {
+            return Ok(nodes);
+        }
+
+        // Extract column arrays with proper error handling
+        let ids = batch
+            .column_by_name("id")
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| DataStoreError::Arrow("Missing or invalid id column".to_string()))?;
+
+        let node_types = batch
+            .column_by_name("type")
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| {
+                DataStoreError::Arrow("Missing or invalid type column".to_string())
+            })?;
+
+        let contents = batch
+            .column_by_name("content")
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| {
+                DataStoreError::Arrow("Missing or invalid content column".to_string())
+            })?;
+
+        let created_ats = batch
+            .column_by_name("created_at")
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| {
+                DataStoreError::Arrow("Missing or invalid created_at column".to_string())
+            })?;
+
+        let updated_ats = batch
+            .column_by_name("updated_at")
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| {
+                DataStoreError::Arrow("Missing or invalid updated_at column".to_string())
+            })?;
+
+        // Extract vector FixedSizeListArray
+        let vector_list_array = batch
+            .column_by_name("vector")
+            .and_then(|col| {
+                col.as_any()
+                    .downcast_ref::<FixedSizeListArray>()
+            })
+            .ok_or_else(|| DataStoreError::Arrow("Missing or invalid vector column".to_string()))?;
+
+        // Extract children_ids ListArray
+        let children_list_array = batch
+            .column_by_name("children_ids")
+            .and_then(|col| col.as_any().downcast_ref::<ListArray>());
+
+        // Extract mentions ListArray
+        let mentions_list_array = batch
+            .column_by_name("mentions")
+            .and_then(|col| col.as_any().downcast_ref::<ListArray>());
+
+        for i in 0..num_rows {
+            let id = ids.value(i).to_string();
+            let node_type = node_types.value(i).to_string();
+            let content = contents.value(i).to_string();
+            let created_at = created_ats.value(i).to_string();
+            let updated_at = updated_ats.value(i).to_string();
+
+            // Extract optional fields
+            let parent_id = batch
+                .column_by_name("parent_id")
+                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+                .and_then(|arr| {
+                    if arr.is_null(i) {
+                        None
+                    } else {
+                        Some(arr.value(i).to_string())
+                    }
+                });
+
+            let before_sibling_id = batch
+                .column_by_name("before_sibling_id")
+                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+                .and_then(|arr| {
+                    if arr.is_null(i) {
+                        None
+                    } else {
+                        Some(arr.value(i).to_string())
+                    }
+                });
+
+            let metadata_str = batch
+                .column_by_name("metadata")
+                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+                .and_then(|arr| {
+                    if arr.is_null(i) {
+                        None
+                    } else {
+                        Some(arr.value(i).to_string())
+                    }
+                });
+
+            // Convert metadata string back to JSON Value
+            let metadata = metadata_str.and_then(|s| serde_json::from_str(&s).ok());
+
+            // Extract vector embedding from FixedSizeListArray
+            let vector = if !vector_list_array.is_null(i) {
+                let vector_list = vector_list_array.value(i);
+                if let Some(float_array) = vector_list
+                    .as_any()
+                    .downcast_ref::<Float32Array>()
+                {
+                    (0..float_array.len())
+                        .map(|j| float_array.value(j))
+                        .collect()
+                } else {
+                    vec![0.0; self.vector_dimension] // Fallback if vector extraction fails
+                }
+            } else {
+                vec![0.0; self.vector_dimension] // Fallback for null vector
+            };
+
+            // Extract children_ids from ListArray
+            let children_ids = if let Some(children_list_array) = children_list_array {
+                if !children_list_array.is_null(i) {
+                    let children_list = children_list_array.value(i);
+                    if let Some(string_array) = children_list.as_any().downcast_ref::<StringArray>()
+                    {
+                        (0..string_array.len())
+                            .map(|j| string_array.value(j).to_string())
+                            .collect()
+                    } else {
+                        vec![]
+                    }
+                } else {
+                    vec![]
+                }
+            } else {
+                vec![]
+            };
+
+            // Extract mentions from ListArray
+            let mentions = if let Some(mentions_list_array) = mentions_list_array {
+                if !mentions_list_array.is_null(i) {
+                    let mentions_list = mentions_list_array.value(i);
+                    if let Some(string_array) = mentions_list.as_any().downcast_ref::<StringArray>()
+                    {
+                        (0..string_array.len())
+                            .map(|j| string_array.value(j).to_string())
+                            .collect()
+                    } else {
+                        vec![]
+                    }
+                } else {
+                    vec![]
+                }
+            } else {
+                vec![]
+            };
+
+            // Extract root hierarchy optimization fields
+            let root_id = batch
+                .column_by_name("root_id")
+                .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+                .and_then(|arr| {
+                    if arr.is_null(i) {
+                        None
+                    } else {
+                        Some(arr.value(i).to_string())
+                    }
+                });
+
+            // root_type field removed
+
+            let node = UniversalNode {
+                id,
+                r#type: node_type,
+                content,
+                individual_vector: vector.clone(),
+                contextual_vector: optional_vector_at(batch, "contextual_vector", i),
+                hierarchical_vector: optional_vector_at(batch, "hierarchical_vector", i),
+                embedding_model: None,
+                embeddings_generated_at: None,
+                vector,
+                parent_id,
+                before_sibling_id,
+                children_ids,
+                mentions,
+                root_id,   // Root hierarchy optimization
+                // root_type field removed
+                created_at,
+                updated_at,
+                metadata,
+            };
+
+            nodes.push(node);
+        }
+
+        Ok(nodes)
+    }
+
+    /// Extract distance scores from LanceDB query results
+    fn extract_distances_from_batch(&self, batch: &RecordBatch) -> Result<Vec<f32>, DataStoreError> {
+        // LanceDB typically returns distances in a column named "_distance"
+        let distances = batch
+            .column_by_name("_distance")
+            .and_then(|col| col.as_any().downcast_ref::<arrow_array::Float32Array>())
+            .ok_or_else(|| {
+                DataStoreError::Arrow("Missing or invalid _distance column in search results".to_string())
+            })?;
+
+        let mut distance_values = Vec::new();
+        for i in 0..distances.len() {
+            let distance = if distances.is_null(i) {
+                f32::INFINITY // Treat null distances as infinite (no similarity)
+            } else {
+                distances.value(i)
+            };
+            distance_values.push(distance);
+        }
+
+        Ok(distance_values)
+    }
+
+    /// BM25 scores from the `_score` column of full-text search results
+    fn extract_fts_scores_from_batch(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<f32>, DataStoreError> {
+        let scores = batch
+            .column_by_name("_score")
+            .and_then(|col| col.as_any().downcast_ref::<Float32Array>())
+            .ok_or_else(|| {
+                DataStoreError::Arrow(
+                    "Missing or invalid _score column in search results".to_string(),
+                )
+            })?;
+
+        Ok((0..scores.len())
+            .map(|i| {
+                if scores.is_null(i) {
+                    0.0
+                } else {
+                    scores.value(i)
+                }
+            })
+            .collect())
+    }
+
+    /// Full-text search on `content` as `(node, score)`, best first
+    ///
+    /// Terms and phrase words are ranked by BM25 through the inverted index.
+    /// Queries with only prefixes have nothing for BM25 to rank, so they run as
+    /// a filtered scan ranked by the number of words matching a prefix.
+    async fn full_text_search_arrow(
+        &self,
+        text_query: &TextQuery,
+        limit: usize,
+    ) -> Result<Vec<(UniversalNode, f32)>, DataStoreError> {
+        // Builds the inverted index on first use; a missing index fails the
+        // search below with LanceDB's own error
+        self.maintain_scalar_indexes().await;
+
+        let Some(text) = text_query.ranked_text() else {
+            return self.prefix_search_arrow(text_query, limit).await;
+        };
+
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        // Phrases and prefixes are re-checked below, so ask for extra
+        // candidates to still fill `limit` after the exact check
+        let residual = text_query.has_residual_predicates();
+        let mut fetch_limit = if residual {
+            limit.saturating_mul(RESIDUAL_FILTER_OVERFETCH)
+        } else {
+            limit
+        };
+        let filter = text_query.to_sql_filter();
+
+        loop {
+            let mut query = table
+                .query()
+                .full_text_search(FullTextSearchQuery::new(text.clone()))
+                .limit(fetch_limit);
+            if let Some(filter) = &filter {
+                query = query.only_if(filter.clone());
+            }
+            let results = query.execute().await.map_err(|e| {
+                DataStoreError::LanceDBQuery(format!("Full-text search failed: {}", e))
+            })?;
+
+            let batches: Vec<RecordBatch> = results.try_collect().await.map_err(|e| {
+                DataStoreError::LanceDBQuery(format!("Failed to collect search results: {}", e))
+            })?;
+
+            let mut fetched = 0;
+            let mut hits = Vec::new();
+            for batch in batches {
+                fetched += batch.num_rows();
+                let universal_nodes = self.extract_nodes_from_batch(&batch)?;
+                let scores = self.extract_fts_scores_from_batch(&batch)?;
+                hits.extend(
+                    universal_nodes
+                        .into_iter()
+                        .zip(scores)
+                        .filter(|(universal_node, _)| text_query.matches(&universal_node.content)),
+                );
+            }
+
+            // Widen the search until the exact check leaves a full page or
+            // the index has no more matches
+            let exhausted = fetched < fetch_limit || fetch_limit == usize::MAX;
+            if hits.len() >= limit || !residual || exhausted {
+                hits.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+                hits.truncate(limit);
+                return Ok(hits);
+            }
+            fetch_limit = fetch_limit.saturating_mul(2);
+        }
+    }
+
+    /// Prefix-only full-text query as a filtered scan, best `limit` rows first
+    ///
+    /// Rows are scored as batches stream in and the running top `limit` is
+    /// trimmed whenever it doubles, so memory stays bounded by `limit` rather
+    /// than by the number of matches.
+    async fn prefix_search_arrow(
+        &self,
+        text_query: &TextQuery,
+        limit: usize,
+    ) -> Result<Vec<(UniversalNode, f32)>, DataStoreError> {
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        let mut query = table.query();
+        if let Some(filter) = text_query.to_sql_filter() {
+            query = query.only_if(filter);
+        }
+        let mut results = query
+            .execute()
+            .await
+            .map_err(|e| DataStoreError::LanceDBQuery(format!("Full-text search failed: {}", e)))?;
+
+        // Stable sorts keep earlier rows ahead on equal scores, so trimming
+        // early gives the same result as sorting everything at the end
+        let by_score = |a: &(UniversalNode, f32), b: &(UniversalNode, f32)| {
+            b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
+        };
+        let mut hits: Vec<(UniversalNode, f32)> = Vec::new();
+        while let Some(batch) = results.try_next().await.map_err(|e| {
+            DataStoreError::LanceDBQuery(format!("Failed to read search results: {}", e))
+        })? {
+            hits.extend(
+                self.extract_nodes_from_batch(&batch)?
+                    .into_iter()
+                    .filter(|universal_node| text_query.matches(&universal_node.content))
+                    .map(|universal_node| {
+                        let score = text_query.prefix_matches(&universal_node.content) as f32;
+                        (universal_node, score)
+                    }),
+            );
+            if hits.len() >= limit.max(1).saturating_mul(2) {
+                hits.sort_by(by_score);
+                hits.truncate(limit);
+            }
+        }
+
+        hits.sort_by(by_score);
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    /// Vector similarity search on the primary `vector` column
+    ///
+    /// Scores follow the store's `DistanceMetric`: higher is more similar.
+    async fn vector_search_arrow(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+        filter: &NodeQuery,
+        mode: FilterMode,
+    ) -> Result<Vec<(Node, f32)>, DataStoreError> {
+        let hits = self
+            .vector_search_column("vector", embedding, limit, filter, mode)
+            .await?;
+
+        // LanceDB returns hits nearest first, so scores are already descending
+        Ok(hits
+            .into_iter()
+            .map(|(universal_node, distance)| {
+                let score = self.distance_metric.score_from_distance(distance);
+                (self.universal_to_node(universal_node), score)
+            })
+            .collect())
+    }
+
+    /// Native ANN search on one embedding column with the store's distance metric
+    ///
+    /// The `filter` compiles into the search's `only_if` predicate; rows where
+    /// the column is null are always excluded, so nodes without that embedding
+    /// level never show up as matches. Ordering and paging on `filter` are
+    /// ignored: hits come back nearest first, at most `limit` of them.
+    async fn vector_search_column(
+        &self,
+        column: &str,
+        embedding: Vec<f32>,
+        limit: usize,
+        filter: &NodeQuery,
+        mode: FilterMode,
+    ) -> Result<Vec<(UniversalNode, f32)>, DataStoreError> {
+        let mut predicate = format!("{} IS NOT NULL", column);
+        if let Some(filter_sql) = filter.to_sql_filter() {
+            predicate = format!("{} AND {}", predicate, filter_sql);
+        }
+
+        // Predicates LanceDB only approximates are re-checked below, so ask for
+        // extra candidates to still fill `limit` after the exact check
+        let residual = filter.has_residual_predicates();
+        let mut fetch_limit = if residual {
+            limit.saturating_mul(RESIDUAL_FILTER_OVERFETCH)
+        } else {
+            limit
+        };
+
+        let table_guard = self.table.read().await;
+        let table = table_guard
+            .as_ref()
+            .ok_or_else(|| DataStoreError::LanceDB("Table not initialized".to_string()))?;
+
+        loop {
+            let mut vector_query = table
+                .query()
+                .nearest_to(embedding.clone())
+                .map_err(|e| {
+                    DataStoreError::VectorSearchError(format!(
+                        "Failed to create nearest_to query: {}",
+                        e
+                    ))
+                })?
+                .column(column)
+                .distance_type(self.distance_metric.distance_type())
+                .only_if(predicate.clone())
+                .limit(fetch_limit);
+            if mode == FilterMode::PostFilter {
+                vector_query = vector_query.postfilter();
+            }
+
+            let results = vector_query.execute().await.map_err(|e| {
+                DataStoreError::VectorSearchError(format!(
+                    "Vector search on '{}' failed: {}",
+                    column, e
+                ))
+            })?;
+
+            let batches: Vec<RecordBatch> = results.try_collect().await.map_err(|e| {
+                DataStoreError::VectorSearchError(format!(
+                    "Failed to collect search results: {}",
+                    e
+                ))
+            })?;
+
+            let mut fetched = 0;
+            let mut hits = Vec::new();
+            for batch in batches {
+                fetched += batch.num_rows();
+                let universal_nodes = self.extract_nodes_from_batch(&batch)?;
+                let distances = self.extract_distances_from_batch(&batch)?;
+                hits.extend(
+                    universal_nodes
+                        .into_iter()
+                        .zip(distances)
+                        .filter(|(universal_node, _)| filter.matches_residual(universal_node)),
+                );
+            }
+
+            // Pre-filtering promises a full page, so a search the exact check
+            // left short is widened until it fills or candidates run out
+            let exhausted = fetched < fetch_limit || fetch_limit == usize::MAX;
+            if hits.len() >= limit || !residual || mode == FilterMode::PostFilter || exhausted {
+                hits.truncate(limit);
+                return Ok(hits);
+            }
+            fetch_limit = fetch_limit.saturating_mul(2);
+        }
+    }
+
+    /// ANN hits on an embedding column as `(node, score)`
+    ///
+    /// No score floor: what counts as a weak match depends on the metric, and
+    /// L2 scores of distant vectors or negative dot products are still hits.
+    async fn search_embedding_column(
+        &self,
+        column: &str,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> Result<Vec<(Node, f32)>, DataStoreError> {
+        let hits = self
+            .vector_search_column(
+                column,
+                embedding,
+                limit,
+                &NodeQuery::new(),
+                FilterMode::PreFilter,
+            )
+            .await?;
+
+        Ok(hits
+            .into_iter()
+            .map(|(universal_node, distance)| {
+                let score = self.distance_metric.score_from_distance(distance);
+                (self.universal_to_node(universal_node), score)
+            })
+            .collect())
+    }
+
+    /// Get a single node by ID using an `id = '...'` predicate pushed down to LanceDB
+    async fn get_node_arrow(&self, id: &NodeId) -> Result<Option<Node>, DataStoreError> {
+        let universal_node = self.get_universal_node_arrow(id).await?;
+        Ok(universal_node.map(|universal| self.universal_to_node(universal)))
+    }
+
+    /// Point lookup of the stored UniversalNode, served by the BTree index on `id`
+    async fn get_universal_node_arrow(
+        &self,
+        id: &NodeId,
+    ) -> Result<Option<UniversalNode>, DataStoreError> {
+        let table_guard = self.table.read().await;
+        if let Some(table) = table_guard.as_ref() {
+            let results_stream = table
+                .query()
+                .only_if(format!("id = {}", sql_string_literal(id.as_str())))
+                .limit(1)
+                .execute()
+                .await
+                .map_err(|e| DataStoreError::LanceDB(format!("Query by ID failed: {}", e)))?;
+
+            // Collect the results into Vec<RecordBatch>
+            let batches: Vec<RecordBatch> = futures::TryStreamExt::try_collect(results_stream)
+                .await
+                .map_err(|e| {
+                    DataStoreError::LanceDB(format!("Failed to collect query results: {}", e))
+                })?;
+
+            for batch in batches.iter() {
+                let mut universal_nodes = self.extract_nodes_from_batch(batch)?;
+                if !universal_nodes.is_empty() {
+                    return Ok(Some(universal_nodes.swap_remove(0)));
+                }
+            }
+
+            Ok(None) // No matching node found
+        } else {
+            Err(DataStoreError::LanceDB("Table not initialized".to_string()))
+        }
+    }
+
+    /// Delete a node using native LanceDB delete operations
+    async fn delete_node_arrow(&self, id: &NodeId) -> Result<(), DataStoreError> {
+        let table_guard = self.table.read().await;
+        if let Some(table) = table_guard.as_ref() {
+            // Use native LanceDB delete operation with SQL predicate
+            let _delete_result = table
+                .delete(&format!("id = '{}'", id.as_str().replace("'", "''")))
+                .await
+                .map_err(|e| DataStoreError::LanceDB(format!("Delete operation failed: {}", e)))?;
+
+            // DeleteResult contains version info - we just verify it succeeded
+            drop(table_guard);
+            self.delete_relationships_touching(&[id.to_string()]).await
+        } else {
+            Err(DataStoreError::LanceDB("Table not initialized".to_string()))
+        }
+    }
+
+    /// Convert UniversalNode back to NodeSpace Node
+    /// For TextNode and DateNode, keep metadata empty to maintain simplified approach
+    /// For other node types, preserve their type-specific metadata
+    fn universal_to_node(&self, universal: UniversalNode) -> Node {
+        // Mentions found in the text are re-derived on every write, so only the
+        // caller's own go back into metadata; otherwise a removed link would stick
+        let derived_mentions = mentions::extract_mentions(&universal.content);
+        let content = serde_json::Value::String(universal.content);
+
+        // Determine if this is a simplified node type (text/date) that should have empty metadata
+        let final_metadata = match universal.r#type.as_str() {
+            "text" | "date" => {
+                // For text and date nodes: Keep metadata empty/null for simplified approach
+                // Hierarchical data is maintained in parent_id/children_ids fields in UniversalNode
+                // and will be computed by core-logic layer when needed
+                None
+            }
+            _ => {
+                // For other node types (image, task, etc.): Preserve their metadata
+                // These may have type-specific properties that need to be maintained
+                let mut metadata = universal.metadata.unwrap_or_else(|| serde_json::json!({}));
+
+                // Only add node_type for non-simplified nodes
+                metadata["node_type"] = serde_json::Value::String(universal.r#type.clone());
+
+                // For non-simplified nodes, we can still include hierarchical data in metadata
+                // for backwards compatibility, but it should be computed from the canonical source
+                if let Some(parent_id) = &universal.parent_id {
+                    metadata["parent_id"] = serde_json::Value::String(parent_id.clone());
+                }
+                if !universal.children_ids.is_empty() {
+                    metadata["children_ids"] = serde_json::Value::Array(
+                        universal
+                            .children_ids
+                            .iter()
+                            .map(|id| serde_json::Value::String(id.clone()))
+                            .collect(),
+                    );
+                }
+                let explicit_mentions: Vec<serde_json::Value> = universal
+                    .mentions
+                    .iter()
+                    .filter(|id| !derived_mentions.contains(id))
+                    .map(|id| serde_json::Value::String(id.clone()))
+                    .collect();
+                if !explicit_mentions.is_empty() {
+                    metadata["mentions"] = serde_json::Value::Array(explicit_mentions);
+                }
+
+                Some(metadata)
+            }
+        };
+
+        Node {
+            id: NodeId::from_string(universal.id),
+            r#type: universal.r#type,
+            content,
+            metadata: final_metadata,
+            created_at: universal.created_at,
+            updated_at: universal.updated_at,
+            parent_id: universal.parent_id.map(NodeId::from_string),
+            before_sibling: universal.before_sibling_id.map(NodeId::from_string),
+            next_sibling: None, // TODO: Map from before_sibling_id when core-types adds before_sibling field
+            root_id: universal.root_id.map(NodeId::from_string),
+        }
+    }
+}
+
+// Implement the DataStore trait for compatibility with existing NodeSpace architecture
+#[async_trait]
+impl DataStore for LanceDataStore {
+    async fn store_node(&self, node: Node) -> NodeSpaceResult<NodeId> {
+        let mut universal = self.node_to_universal(node.clone(), None);
+        self.resolve_root_id(&mut universal).await?;
+
+        // Store using Arrow persistence
+        self.store_node_arrow(universal.clone()).await?;
+
+        Ok(node.id)
+    }
+
+    async fn get_node(&self, id: &NodeId) -> NodeSpaceResult<Option<Node>> {
+        // Use Arrow-based retrieval
+        let result = self.get_node_arrow(id).await?;
+        Ok(result)
+    }
+
+    async fn update_node(&self, node: Node) -> NodeSpaceResult<()> {
+        // First verify the node exists and get the old version
+        let existing_node = self
+            .get_universal_node_arrow(&node.id)
+            .await?
+            .ok_or_else(|| {
+                DataStoreError::NodeNotFound(format!("Node {} not found for update", node.id))
+            })?;
+
+        // Update the node's updated_at timestamp
+        let mut updated_node = node;
+        updated_node.updated_at = chrono::Utc::now().to_rfc3339();
+
+        // Regenerates the embedding if content changed, otherwise keeps the stored one
+        let mut universal = self.prepare_update(updated_node, &existing_node).await;
+        clear_root_if_detached(&mut universal, &existing_node);
+        self.resolve_root_id(&mut universal).await?;
+
+        // Descendants follow the node if its root changed
+        let mut rows = self
+            .rerooted_descendants(&universal, &existing_node, &HashSet::new())
+            .await?;
+        rows.insert(0, universal);
+        self.upsert_nodes_arrow(rows).await?;
+
+        Ok(())
+    }
+
+    async fn update_node_with_embedding(
+        &self,
+        node: Node,
+        embedding: Vec<f32>,
+    ) -> NodeSpaceResult<()> {
+        // Verify the node exists
+        let existing_node = self
+            .get_universal_node_arrow(&node.id)
+            .await?
+            .ok_or_else(|| {
+                DataStoreError::NodeNotFound(format!("Node {} not found for update", node.id))
+            })?;
+
+        // Update the node's updated_at timestamp
+        let mut updated_node = node;
+        updated_node.updated_at = chrono::Utc::now().to_rfc3339();
+
+        // Use the provided embedding; the other levels stay as stored
+        let mut universal = self.node_to_universal(updated_node, Some(embedding));
+        keep_stored_embeddings(&mut universal, &existing_node);
+        clear_root_if_detached(&mut universal, &existing_node);
+        self.resolve_root_id(&mut universal).await?;
+
+        let mut rows = self
+            .rerooted_descendants(&universal, &existing_node, &HashSet::new())
+            .await?;
+        rows.insert(0, universal);
+        self.upsert_nodes_arrow(rows).await?;
+
+        Ok(())
+    }
+
+    async fn upsert_node(&self, node: Node) -> NodeSpaceResult<NodeId> {
+        let node_id = node.id.clone();
+
+        let existing_node = self.get_universal_node_arrow(&node.id).await?;
+        let mut universal = match &existing_node {
+            Some(existing_node) => {
+                let mut updated_node = node;
+                updated_node.updated_at = chrono::Utc::now().to_rfc3339();
+                let mut universal = self.prepare_update(updated_node, existing_node).await;
+                clear_root_if_detached(&mut universal, existing_node);
+                universal
+            }
+            // New nodes are stored exactly like store_node would
+            None => self.node_to_universal(node, None),
+        };
+        self.resolve_root_id(&mut universal).await?;
+
+        let mut rows = match &existing_node {
+            Some(existing_node) => {
+                self.rerooted_descendants(&universal, existing_node, &HashSet::new())
+                    .await?
+            }
+            None => Vec::new(),
+        };
+        rows.insert(0, universal);
+        self.upsert_nodes_arrow(rows).await?;
+
+        Ok(node_id)
+    }
+
+    async fn delete_node(&self, id: &NodeId) -> NodeSpaceResult<()> {
+        // Use Arrow-based deletion
+        self.delete_node_arrow(id).await?;
+
+        Ok(())
+    }
+
+    async fn query_nodes(&self, query: &str) -> NodeSpaceResult<Vec<Node>> {
+        // Use Arrow-based query
+        let universal_nodes = self.query_nodes_arrow(query).await?;
+        let nodes = universal_nodes
+            .into_iter()
+            .map(|universal| self.universal_to_node(universal))
+            .collect();
+        Ok(nodes)
+    }
+
+    async fn find_nodes(&self, query: NodeQuery) -> NodeSpaceResult<Vec<Node>> {
+        let universal_nodes = self.find_nodes_arrow(&query).await?;
+        let nodes = universal_nodes
+            .into_iter()
+            .map(|universal| self.universal_to_node(universal))
+            .collect();
+        Ok(nodes)
+    }
+
+    async fn find_nodes_page(
+        &self,
+        query: NodeQuery,
+        page_size: usize,
+        cursor: Option<&PageCursor>,
+    ) -> NodeSpaceResult<NodePage> {
+        if page_size == 0 {
+            return Err(DataStoreError::InvalidQuery(
+                "page_size must be greater than 0".to_string(),
+            )
+            .into());
+        }
+
+        let position = match cursor {
+            Some(cursor) => cursor.position(&query)?,
+            None => PagePosition::default(),
+        };
+        let (universal_nodes, next) = self.find_page_arrow(&query, page_size, &position).await?;
+
+        Ok(NodePage {
+            nodes: universal_nodes
+                .into_iter()
+                .map(|universal| self.universal_to_node(universal))
+                .collect(),
+            next_cursor: next.map(|next| PageCursor::new(&query, &next)),
+        })
+    }
+
+    async fn stream_nodes(&self, query: NodeQuery) -> NodeSpaceResult<NodeStream<'_>> {
+        if query.order_by.is_some() {
+            // LanceDB can't return rows in order, so walk bounded keyset pages
+            let pages = stream::try_unfold(Some(PagePosition::default()), move |position| {
+                let query = query.clone();
+                async move {
+                    let Some(position) = position else {
+                        return Ok(None);
+                    };
+                    let (nodes, next) = self
+                        .find_page_arrow(&query, ORDERED_STREAM_PAGE_SIZE, &position)
+                        .await?;
+                    let page = stream::iter(nodes.into_iter().map(Ok::<_, DataStoreError>));
+                    Ok::<_, DataStoreError>(Some((page, next)))
+                }
+            });
+            return Ok(pages
+                .try_flatten()
+                .map(move |result| {
+                    result
+                        .map(|universal| self.universal_to_node(universal))
+                        .map_err(Into::into)
+                })
+                .boxed());
+        }
+
+        let nodes = self.scan_nodes_arrow(&query).await?;
+        Ok(nodes
+            .map(move |result| {
+                result
+                    .map(|universal| self.universal_to_node(universal))
+                    .map_err(Into::into)
+            })
+            .boxed())
+    }
+
+    async fn store_nodes(&self, nodes: Vec<Node>) -> NodeSpaceResult<Vec<NodeId>> {
+        let ids: Vec<NodeId> = nodes.iter().map(|node| node.id.clone()).collect();
+        let id_strings: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        reject_duplicate_ids(&id_strings)?;
+
+        let mut universals = nodes
+            .into_iter()
+            .map(|node| self.node_to_universal(node, None))
+            .collect::<Vec<_>>();
+        self.resolve_root_ids(&mut universals).await?;
+
+        // Single RecordBatch, single table write
+        self.store_nodes_arrow(universals).await?;
+
+        Ok(ids)
+    }
+
+    async fn get_nodes(&self, ids: &[NodeId]) -> NodeSpaceResult<Vec<Option<Node>>> {
+        let id_strings: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let mut found = self.get_universal_nodes_arrow(&id_strings).await?;
+
+        let nodes = id_strings
+            .iter()
+            .map(|id| {
+                found
+                    .remove(id)
+                    .map(|universal| self.universal_to_node(universal))
+            })
+            .collect();
+        Ok(nodes)
+    }
+
+    async fn update_nodes(&self, nodes: Vec<Node>) -> NodeSpaceResult<()> {
+        let id_strings: Vec<String> = nodes.iter().map(|node| node.id.to_string()).collect();
+        reject_duplicate_ids(&id_strings)?;
+        let existing = self.get_universal_nodes_arrow(&id_strings).await?;
+
+        // Validate every node before writing anything
+        if let Some(missing) = id_strings.iter().find(|id| !existing.contains_key(*id)) {
+            return Err(DataStoreError::NodeNotFound(format!(
+                "Node {} not found for update",
+                missing
+            ))
+            .into());
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut universals = Vec::with_capacity(nodes.len());
+        for mut node in nodes {
+            node.updated_at = now.clone();
+
+            // Regenerate embeddings only for nodes whose content changed
+            let existing_node = &existing[node.id.as_str()];
+            let mut universal = self.prepare_update(node, existing_node).await;
+            clear_root_if_detached(&mut universal, existing_node);
+            universals.push(universal);
+        }
+        self.resolve_root_ids(&mut universals).await?;
+
+        // Descendants outside the batch follow nodes whose root changed
+        let batch: HashSet<String> = universals.iter().map(|u| u.id.clone()).collect();
+        let mut rerooted = Vec::new();
+        for universal in &universals {
+            rerooted.extend(
+                self.rerooted_descendants(universal, &existing[&universal.id], &batch)
+                    .await?,
+            );
+        }
+        universals.extend(rerooted);
+
+        self.upsert_nodes_arrow(universals).await?;
+
+        Ok(())
+    }
+
+    async fn delete_nodes(&self, ids: &[NodeId]) -> NodeSpaceResult<()> {
+        let id_strings: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        self.delete_nodes_arrow(&id_strings).await?;
+        Ok(())
+    }
+
+    async fn create_relationship(
+        &self,
+        from: &NodeId,
+        to: &NodeId,
+        rel_type: &str,
+    ) -> NodeSpaceResult<()> {
+        if !relationships::is_hierarchy_type(rel_type) {
+            return Ok(self.store_relationship(from, to, rel_type, None).await?);
+        }
+        // `to` becomes the last child of `from`, leaving any previous parent
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        Ok(self
+            .relocate_subtree(
+                &to.to_string(),
+                &from.to_string(),
+                SiblingPlacement::At(usize::MAX),
+            )
+            .await?)
+    }
+
+    async fn create_relationship_with_properties(
+        &self,
+        from: &NodeId,
+        to: &NodeId,
+        rel_type: &str,
+        properties: serde_json::Value,
+    ) -> NodeSpaceResult<()> {
+        if relationships::is_hierarchy_type(rel_type) {
+            return Err(DataStoreError::InvalidQuery(format!(
+                "\"{}\" relationships are stored in the hierarchy and can't carry properties",
+                rel_type
+            ))
+            .into());
+        }
+        let properties = (!properties.is_null()).then_some(properties);
+        Ok(self
+            .store_relationship(from, to, rel_type, properties)
+            .await?)
+    }
+
+    async fn get_relationships(
+        &self,
+        node_id: &NodeId,
+        direction: RelationshipDirection,
+        rel_type: Option<&str>,
+    ) -> NodeSpaceResult<Vec<Relationship>> {
+        let mut found = self
+            .query_relationships(relationships::direction_predicate(
+                node_id.as_str(),
+                direction,
+                rel_type,
+            ))
+            .await?;
+        found.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(found)
+    }
+
+    async fn delete_relationship(
+        &self,
+        from: &NodeId,
+        to: &NodeId,
+        rel_type: &str,
+    ) -> NodeSpaceResult<()> {
+        self.delete_relationships_where(&relationships::edge_predicate(
+            from.as_str(),
+            to.as_str(),
+            rel_type,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn get_backlinks(&self, node_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        self.find_nodes(NodeQuery::new().mentions(node_id)).await
+    }
+
+    async fn traverse(
+        &self,
+        start: &NodeId,
+        options: TraversalOptions,
+    ) -> NodeSpaceResult<TraversalStream<'_>> {
+        let start_node = self.get_universal_node_arrow(start).await?.ok_or_else(|| {
+            DataStoreError::NodeNotFound(format!("Node {} not found for traversal", start))
+        })?;
+        let frontier = Frontier::new(start_node, &options);
+
+        // Neighbours are looked up as each node is yielded, so dropping the
+        // stream early skips the rest of the walk
+        let steps = stream::try_unfold(
+            (frontier, options),
+            move |(mut frontier, options)| async move {
+                loop {
+                    let Some(visit) = frontier.next_visit() else {
+                        return Ok::<_, DataStoreError>(None);
+                    };
+                    if frontier.expands(&visit) {
+                        let neighbours = self
+                            .traversal_neighbours(&visit.node, visit.depth + 1, &options)
+                            .await?;
+                        frontier.push_neighbours(&visit, neighbours);
+                    }
+                    if !visit.first {
+                        continue;
+                    }
+                    let step = TraversalStep {
+                        node: self.universal_to_node(visit.node),
+                        depth: visit.depth,
+                        path: visit.path,
+                        via: visit.via,
+                    };
+                    return Ok(Some((step, (frontier, options))));
+                }
+            },
+        );
+        Ok(steps.map_err(Into::into).boxed())
+    }
+
+    async fn store_node_with_embedding(
+        &self,
+        node: Node,
+        embedding: Vec<f32>,
+    ) -> NodeSpaceResult<NodeId> {
+        let mut universal = self.node_to_universal(node.clone(), Some(embedding));
+        self.resolve_root_id(&mut universal).await?;
+
+        // Store using Arrow persistence
+        self.store_node_arrow(universal.clone()).await?;
+
+        Ok(node.id)
+    }
+
+    async fn search_similar_nodes(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        // Use Arrow-based vector search
+        let results = self
+            .vector_search_arrow(embedding, limit, &NodeQuery::new(), FilterMode::PreFilter)
+            .await?;
+        Ok(results)
+    }
+
+    async fn search_similar_nodes_diverse(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+        diversity: DiversityConfig,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        let candidates = limit.saturating_mul(diversity.candidate_multiplier.max(1));
+        let hits: Vec<(UniversalNode, f32)> = self
+            .vector_search_column(
+                "vector",
+                embedding,
+                candidates,
+                &NodeQuery::new(),
+                FilterMode::PreFilter,
+            )
+            .await?
+            .into_iter()
+            .map(|(universal_node, distance)| {
+                (
+                    universal_node,
+                    self.distance_metric.score_from_distance(distance),
+                )
+            })
+            .collect();
+
+        let order = scoring::diversify(
+            &hits
+                .iter()
+                .map(|(node, score)| (node, *score))
+                .collect::<Vec<_>>(),
+            limit,
+            &diversity,
+        );
+        let mut hits: Vec<Option<(UniversalNode, f32)>> = hits.into_iter().map(Some).collect();
+        Ok(order
+            .into_iter()
+            .filter_map(|index| hits[index].take())
+            .map(|(universal_node, score)| (self.universal_to_node(universal_node), score))
+            .collect())
+    }
+
+    async fn filtered_vector_search(
+        &self,
+        embedding: Vec<f32>,
+        filter: NodeQuery,
+        limit: usize,
+        mode: FilterMode,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        Ok(self
+            .vector_search_arrow(embedding, limit, &filter, mode)
+            .await?)
+    }
+
+    async fn full_text_search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<SearchResult>> {
+        let text_query = TextQuery::parse(query)?;
+        let hits = self.full_text_search_arrow(&text_query, limit).await?;
+        let filters: Vec<String> = text_query.to_sql_filter().into_iter().collect();
+
+        Ok(hits
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (universal_node, score))| {
+                let explanation = SearchExplanation {
+                    components: vec![ScoreComponent {
+                        signal: ScoreSignal::Keyword,
+                        score,
+                        weight: 1.0,
+                        rank: Some(rank + 1),
+                        contribution: score,
+                    }],
+                    fusion: None,
+                    highlights: text_query.highlights(&universal_node.content),
+                    filters: filters.clone(),
+                    truncated: false,
+                };
+                SearchResult {
+                    node: self.universal_to_node(universal_node),
+                    score,
+                    relevance_factors: RelevanceFactors {
+                        semantic_score: 0.0,
+                        keyword_score: score,
+                        structural_score: 0.0,
+                        temporal_score: 0.0,
+                        cross_modal_score: None,
+                    },
+                    explanation,
+                }
+            })
+            .collect())
+    }
+
+    async fn hybrid_text_vector_search(
+        &self,
+        text: &str,
+        embedding: Vec<f32>,
+        config: HybridSearchConfig,
+    ) -> NodeSpaceResult<Vec<SearchResult>> {
+        let text_query = TextQuery::parse(text)?;
+        let mut budget = SearchBudget::start(&config);
+        budget.check()?;
+        if config.max_results == 0 {
+            return Ok(Vec::new());
+        }
+
+        let candidates = config
+            .max_results
+            .saturating_mul(FUSION_CANDIDATE_OVERFETCH);
+        let mut universal_nodes: HashMap<String, UniversalNode> = HashMap::new();
+
+        let mut semantic = scoring::LevelRanking {
+            weight: config.semantic_weight as f32,
+            hits: Vec::new(),
+        };
+        if config.semantic_weight > 0.0 {
+            let hits = budget
+                .run(self.vector_search_column(
+                    "vector",
+                    embedding,
+                    candidates,
+                    &text_query.filter,
+                    FilterMode::PreFilter,
+                ))
+                .await?
+                .unwrap_or_default();
+            for (universal_node, distance) in hits {
+                let similarity = self.distance_metric.score_from_distance(distance);
+                if similarity < config.min_similarity_threshold as f32 {
+                    continue;
+                }
+                semantic.hits.push((universal_node.id.clone(), similarity));
+                universal_nodes.insert(universal_node.id.clone(), universal_node);
+            }
+        }
+
+        let mut keyword = scoring::LevelRanking {
+            weight: config.keyword_weight as f32,
+            hits: Vec::new(),
+        };
+        let mut bm25_scores = HashMap::new();
+        if config.keyword_weight > 0.0 {
+            let hits = budget
+                .run(self.full_text_search_arrow(&text_query, candidates))
+                .await?
+                .unwrap_or_default();
+            // Hits are best first, so the first score normalizes the rest into (0, 1]
+            let best = hits
+                .first()
+                .map(|(_, score)| *score)
+                .filter(|score| *score > 0.0)
+                .unwrap_or(1.0);
+            for (universal_node, score) in hits {
+                keyword.hits.push((universal_node.id.clone(), score / best));
+                bm25_scores.insert(universal_node.id.clone(), score);
+                universal_nodes
+                    .entry(universal_node.id.clone())
+                    .or_insert(universal_node);
+            }
+        }
+
+        let mut filters: Vec<String> = text_query.to_sql_filter().into_iter().collect();
+        if config.semantic_weight > 0.0 {
+            filters.push(similarity_floor(config.min_similarity_threshold));
+        }
+
+        let rankings = [semantic, keyword];
+        let mut results = Vec::new();
+        for hit in scoring::fuse_rankings(config.fusion_strategy, &rankings)
+            .into_iter()
+            .take(config.max_results)
+        {
+            let Some(universal_node) = universal_nodes.remove(&hit.id) else {
+                continue;
+            };
+
+            let explanation = SearchExplanation {
+                components: hit.components(
+                    &rankings,
+                    &[ScoreSignal::Individual, ScoreSignal::Keyword],
+                ),
+                fusion: Some(config.fusion_strategy),
+                highlights: text_query.highlights(&universal_node.content),
+                filters: filters.clone(),
+                truncated: false,
+            };
+            results.push(SearchResult {
+                score: hit.score,
+                relevance_factors: RelevanceFactors {
+                    semantic_score: hit.levels[0]
+                        .as_ref()
+                        .map_or(0.0, |level_hit| level_hit.similarity),
+                    keyword_score: bm25_scores.get(&hit.id).copied().unwrap_or(0.0),
+                    structural_score: 0.0,
+                    temporal_score: 0.0,
+                    cross_modal_score: None,
+                },
+                explanation,
+                node: self.universal_to_node(universal_node),
+            });
+        }
+
+        Ok(budget.finish(results)?)
+    }
+
+    async fn update_node_embedding(&self, id: &NodeId, embedding: Vec<f32>) -> NodeSpaceResult<()> {
+        // Get the existing node, update its embedding, and store it back
+        if let Some(existing) = self.get_universal_node_arrow(id).await? {
+            let mut node = self.universal_to_node(existing.clone());
+            // Update the embedding in metadata
+            let mut metadata = node.metadata.unwrap_or_else(|| serde_json::json!({}));
+            metadata["vector"] = serde_json::Value::Array(
+                embedding
+                    .iter()
+                    .map(|&f| {
+                        serde_json::Value::Number(serde_json::Number::from_f64(f as f64).unwrap())
+                    })
+                    .collect(),
+            );
+            node.metadata = Some(metadata);
+
+            // Replace the stored row with the updated embedding
+            let mut universal = self.node_to_universal(node, Some(embedding));
+            keep_stored_embeddings(&mut universal, &existing);
+            universal.children_ids = existing.children_ids.clone();
+            clear_root_if_detached(&mut universal, &existing);
+            self.resolve_root_id(&mut universal).await?;
+
+            let mut rows = self
+                .rerooted_descendants(&universal, &existing, &HashSet::new())
+                .await?;
+            rows.insert(0, universal);
+            self.upsert_nodes_arrow(rows).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn semantic_search_with_embedding(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        // Same as search_similar_nodes for this implementation
+        self.search_similar_nodes(embedding, limit).await
+    }
+
+    // Cross-modal search methods
+    async fn create_image_node(&self, image_node: ImageNode) -> NodeSpaceResult<String> {
+        // Convert ImageNode to UniversalNode format
+        let mut universal_node = UniversalNode {
+            id: image_node.id.clone(),
+            r#type: "image".to_string(),
+            content: image_node
+                .metadata
+                .description
+                .unwrap_or_else(|| format!("Image: {}", image_node.metadata.filename)),
+            individual_vector: image_node.embedding.clone(),
+            contextual_vector: None,
+            hierarchical_vector: None,
+            embedding_model: None,
+            embeddings_generated_at: None,
+            vector: image_node.embedding,
+            parent_id: None,
+            before_sibling_id: None,
+            children_ids: vec![],
+            mentions: vec![],
+            root_id: None,   // Root hierarchy optimization
+            // root_type field removed
+            created_at: image_node.created_at.to_rfc3339(),
+            updated_at: image_node.created_at.to_rfc3339(),
+            metadata: Some(serde_json::json!({
+                "image_data": base64::prelude::BASE64_STANDARD.encode(&image_node.image_data),
+                "filename": image_node.metadata.filename,
+                "mime_type": image_node.metadata.mime_type,
+                "width": image_node.metadata.width,
+                "height": image_node.metadata.height,
+                "exif_data": image_node.metadata.exif_data
+            })),
+        };
+
+        // Store in LanceDB table with proper Arrow schema
+        self.resolve_root_id(&mut universal_node).await?;
+        self.store_node_arrow(universal_node).await?;
+
+        Ok(image_node.id)
+    }
+
+    async fn get_image_node(&self, id: &str) -> NodeSpaceResult<Option<ImageNode>> {
+        // Get node from Arrow storage
+        let node_id = NodeId::from_string(id.to_string());
+        if let Some(node) = self.get_node(&node_id).await? {
+            if let Some(metadata) = &node.metadata {
+                if metadata.get("node_type").and_then(|v| v.as_str()) == Some("image") {
+                    // Convert back to ImageNode
+                    let image_data = base64::prelude::BASE64_STANDARD
+                        .decode(
+                            metadata
+                                .get("image_data")
+                                .and_then(|v| v.as_str())
+                                .ok_or_else(|| {
+                                    DataStoreError::InvalidNode("Missing image data".to_string())
+                                })?,
+                        )
+                        .map_err(|e| {
+                            DataStoreError::InvalidNode(format!("Invalid base64 image data: {}", e))
+                        })?;
+
+                    // Extract vector from metadata or use default
+                    let embedding = metadata
+                        .get("vector")
+                        .and_then(|v| v.as_array())
+                        .map(|arr| {
+                            arr.iter()
+                                .filter_map(|v| v.as_f64().map(|f| f as f32))
+                                .collect()
+                        })
+                        .unwrap_or_else(|| vec![0.0; 384]);
+
+                    let image_node = ImageNode {
+                        id: node.id.to_string(),
+                        image_data,
+                        embedding,
+                        metadata: ImageMetadata {
+                            filename: metadata
+                                .get("filename")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("unknown")
+                                .to_string(),
+                            mime_type: metadata
+                                .get("mime_type")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("image/jpeg")
+                                .to_string(),
+                            width: metadata.get("width").and_then(|v| v.as_u64()).unwrap_or(0)
+                                as u32,
+                            height: metadata.get("height").and_then(|v| v.as_u64()).unwrap_or(0)
+                                as u32,
+                            exif_data: metadata.get("exif_data").cloned(),
+                            description: if let serde_json::Value::String(content) = &node.content {
+                                if content.starts_with("Image:") {
+                                    None
+                                } else {
+                                    Some(content.clone())
+                                }
+                            } else {
+                                None
+                            },
+                        },
+                        created_at: chrono::DateTime::parse_from_rfc3339(&node.created_at)
+                            .map_err(|e| {
+                                DataStoreError::InvalidNode(format!("Invalid timestamp: {}", e))
+                            })?
+                            .with_timezone(&chrono::Utc),
+                    };
+
+                    return Ok(Some(image_node));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn search_multimodal(
+        &self,
+        query_embedding: Vec<f32>,
+        types: Vec<NodeType>,
+    ) -> NodeSpaceResult<Vec<Node>> {
+        // Get all nodes from Arrow storage
+        let universal_nodes = self.query_nodes_arrow("").await?;
+        let mut results = Vec::new();
+
+        // Convert NodeType enum to string filters
+        let type_filters: Vec<String> = types
+            .into_iter()
+            .map(|t| match t {
+                NodeType::Text => "text".to_string(),
+                NodeType::Image => "image".to_string(),
+                NodeType::Date => "date".to_string(),
+                NodeType::Task => "task".to_string(),
+            })
+            .collect();
+
+        for universal_node in universal_nodes {
+            // Filter by node types
+            if !type_filters.is_empty() && !type_filters.contains(&universal_node.r#type) {
+                continue;
+            }
+
+            let similarity = cosine_similarity(&query_embedding, &universal_node.vector);
+            if similarity > 0.1 {
+                // Basic similarity threshold
+                let node = self.universal_to_node(universal_node);
+                results.push((node, similarity));
+            }
+        }
+
+        // Sort by similarity and return just the nodes
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(results.into_iter().map(|(node, _)| node).collect())
+    }
+
+    async fn hybrid_multimodal_search(
+        &self,
+        query_embedding: Vec<f32>,
+        config: &HybridSearchConfig,
+    ) -> NodeSpaceResult<Vec<SearchResult>> {
+        let mut budget = SearchBudget::start(config);
+        budget.check()?;
+        if config.max_results == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Hop distances from the anchors; empty when the search has no structural context
+        let hop_distances = budget
+            .run(self.hop_distances(&config.anchor_node_ids, config.max_structural_hops))
+            .await?
+            .unwrap_or_default();
+        let temporal_reference = config.temporal_reference.unwrap_or_else(chrono::Utc::now);
+
+        // Stream the table, keeping only the best candidates in memory
+        let mut results = Vec::new();
+        let Some(mut universal_nodes) =
+            budget.run(self.scan_nodes_arrow(&NodeQuery::new())).await?
+        else {
+            return Ok(budget.finish(results)?);
+        };
+
+        while let Some(universal_node) = budget.run(universal_nodes.try_next()).await?.flatten() {
+            let semantic_score = cosine_similarity(&query_embedding, &universal_node.vector);
+
+            // Skip if below minimum threshold
+            if semantic_score < config.min_similarity_threshold as f32 {
+                continue;
+            }
+
+            // Structural score from graph distance to the nearest anchor
+            let structural_score =
+                scoring::structural_score(hop_distances.get(&universal_node.id).copied());
+
+            // Temporal score from the configured field and decay curve
+            let temporal_score = scoring::temporal_score(
+                &config.temporal_scoring,
+                scoring::node_timestamp(&universal_node, &config.temporal_field),
+                temporal_reference,
+            );
+
+            // Cross-modal bonus for image-text combinations
+            let cross_modal_score =
+                if config.enable_cross_modal && universal_node.r#type == "image" {
+                    Some(0.9) // Boost for cross-modal queries
+                } else {
+                    None
+                };
+
+            // Weighted final score: the sum of each signal's score times its weight
+            let mut components = vec![
+                weighted_component(
+                    ScoreSignal::Individual,
+                    semantic_score,
+                    config.semantic_weight,
+                ),
+                weighted_component(
+                    ScoreSignal::Structural,
+                    structural_score,
+                    config.structural_weight,
+                ),
+                weighted_component(
+                    ScoreSignal::Temporal,
+                    temporal_score,
+                    config.temporal_weight,
+                ),
+            ];
+            if let Some(cross_modal_score) = cross_modal_score {
+                components.push(weighted_component(
+                    ScoreSignal::CrossModal,
+                    cross_modal_score,
+                    config.cross_modal_weight,
+                ));
+            }
+            let final_score: f32 = components
+                .iter()
+                .map(|component| component.contribution)
+                .sum();
+
+            let node = self.universal_to_node(universal_node);
+            let search_result = SearchResult {
+                node,
+                score: final_score,
+                relevance_factors: RelevanceFactors {
+                    semantic_score,
+                    keyword_score: 0.0,
+                    structural_score,
+                    temporal_score,
+                    cross_modal_score,
+                },
+                explanation: SearchExplanation {
+                    components,
+                    fusion: None,
+                    highlights: Vec::new(),
+                    filters: vec![similarity_floor(config.min_similarity_threshold)],
+                    truncated: false,
+                },
+            };
+
+            results.push(search_result);
+            if results.len() >= config.max_results.saturating_mul(2) {
+                results.sort_by(|a, b| {
+                    b.score
+                        .partial_cmp(&a.score)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                results.truncate(config.max_results);
+            }
+        }
+
+        // Sort by final score and apply limits
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(config.max_results);
+
+        Ok(budget.finish(results)?)
+    }
+
+    // Multi-level embedding methods
+    async fn store_node_with_multi_embeddings(
+        &self,
+        node: Node,
+        embeddings: crate::data_store::MultiLevelEmbeddings,
+    ) -> NodeSpaceResult<NodeId> {
+        let mut universal = self.node_to_universal_with_multi_embeddings(node.clone(), embeddings);
+        self.resolve_root_id(&mut universal).await?;
+
+        // Store using Arrow persistence
+        self.store_node_arrow(universal).await?;
+
+        Ok(node.id)
+    }
+
+    async fn update_node_embeddings(
+        &self,
+        node_id: &NodeId,
+        embeddings: crate::data_store::MultiLevelEmbeddings,
+    ) -> NodeSpaceResult<()> {
+        // Get the existing node
+        if let Some(existing) = self.get_universal_node_arrow(node_id).await? {
+            // Convert with new embeddings; text and date nodes carry no
+            // children_ids in metadata, so those come from the stored row
+            let node = self.universal_to_node(existing.clone());
+            let mut universal = self.node_to_universal_with_multi_embeddings(node, embeddings);
+            universal.children_ids = existing.children_ids.clone();
+            clear_root_if_detached(&mut universal, &existing);
+            self.resolve_root_id(&mut universal).await?;
+
+            let mut rows = self
+                .rerooted_descendants(&universal, &existing, &HashSet::new())
+                .await?;
+            rows.insert(0, universal);
+            self.upsert_nodes_arrow(rows).await?;
+
+            Ok(())
+        } else {
+            Err(DataStoreError::NodeNotFound(format!("Node {} not found", node_id)).into())
+        }
+    }
+
+    async fn get_node_embeddings(
+        &self,
+        node_id: &NodeId,
+    ) -> NodeSpaceResult<Option<crate::data_store::MultiLevelEmbeddings>> {
+        // Get the node from Arrow storage
+        if let Some(universal_node) = self.get_universal_node_arrow(node_id).await? {
+            let embeddings = crate::data_store::MultiLevelEmbeddings {
+                individual: universal_node.individual_vector,
+                contextual: universal_node.contextual_vector,
+                hierarchical: universal_node.hierarchical_vector,
+                embedding_model: universal_node.embedding_model,
+                generated_at: if let Some(timestamp_str) = universal_node.embeddings_generated_at {
+                    chrono::DateTime::parse_from_rfc3339(&timestamp_str)
+                        .map(|dt| dt.with_timezone(&chrono::Utc))
+                        .unwrap_or_else(|_| chrono::Utc::now())
+                } else {
+                    chrono::Utc::now()
+                },
+            };
+            return Ok(Some(embeddings));
+        }
+
+        Ok(None)
+    }
+
+    async fn search_by_individual_embedding(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        // The individual embedding is stored in the primary `vector` column
+        Ok(self
+            .search_embedding_column("vector", embedding, limit)
+            .await?)
+    }
+
+    async fn search_by_contextual_embedding(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        Ok(self
+            .search_embedding_column("contextual_vector", embedding, limit)
+            .await?)
+    }
+
+    async fn search_by_hierarchical_embedding(
+        &self,
+        embedding: Vec<f32>,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        Ok(self
+            .search_embedding_column("hierarchical_vector", embedding, limit)
+            .await?)
+    }
+
+    async fn hybrid_semantic_search(
+        &self,
+        embeddings: crate::data_store::QueryEmbeddings,
+        config: crate::data_store::HybridSearchConfig,
+    ) -> NodeSpaceResult<Vec<crate::data_store::SearchResult>> {
+        let mut budget = SearchBudget::start(&config);
+        budget.check()?;
+        if config.max_results == 0 {
+            return Ok(Vec::new());
+        }
+
+        // One ANN query per embedding level; without cross-level fusion only the
+        // individual level is searched and scored on its own
+        let mut levels = vec![(
+            "vector",
+            Some(embeddings.individual),
+            if config.enable_cross_level_fusion {
+                config.individual_weight
+            } else {
+                1.0
+            },
+        )];
+        if config.enable_cross_level_fusion {
+            levels.push((
+                "contextual_vector",
+                embeddings.contextual,
+                config.contextual_weight,
+            ));
+            levels.push((
+                "hierarchical_vector",
+                embeddings.hierarchical,
+                config.hierarchical_weight,
+            ));
+        }
+
+        let diversity_overfetch = config
+            .diversity
+            .as_ref()
+            .map_or(1, |diversity| diversity.candidate_multiplier.max(1));
+        let candidates = config
+            .max_results
+            .saturating_mul(FUSION_CANDIDATE_OVERFETCH)
+            .saturating_mul(diversity_overfetch);
+        let mut universal_nodes: HashMap<String, UniversalNode> = HashMap::new();
+        let mut rankings = Vec::with_capacity(levels.len());
+        for (column, embedding, weight) in levels {
+            let mut ranking = scoring::LevelRanking {
+                weight: weight as f32,
+                hits: Vec::new(),
+            };
+            if let Some(embedding) = embedding.filter(|_| weight > 0.0) {
+                let hits = budget
+                    .run(self.vector_search_column(
+                        column,
+                        embedding,
+                        candidates,
+                        &NodeQuery::new(),
+                        FilterMode::PreFilter,
+                    ))
+                    .await?
+                    .unwrap_or_default();
+                for (universal_node, distance) in hits {
+                    let similarity = self.distance_metric.score_from_distance(distance);
+                    if similarity < config.min_similarity_threshold as f32 {
+                        continue;
+                    }
+                    ranking.hits.push((universal_node.id.clone(), similarity));
+                    universal_nodes.insert(universal_node.id.clone(), universal_node);
+                }
+            }
+            rankings.push(ranking);
+        }
+
+        let ranked: Vec<(scoring::FusedHit, UniversalNode)> =
+            scoring::fuse_rankings(config.fusion_strategy, &rankings)
+                .into_iter()
+                .filter_map(|hit| {
+                    let universal_node = universal_nodes.remove(&hit.id)?;
+                    Some((hit, universal_node))
+                })
+                .collect();
+        let order = match &config.diversity {
+            Some(diversity) => scoring::diversify(
+                &ranked
+                    .iter()
+                    .map(|(hit, node)| (node, hit.score))
+                    .collect::<Vec<_>>(),
+                config.max_results,
+                diversity,
+            ),
+            None => (0..ranked.len().min(config.max_results)).collect(),
+        };
+        let mut filters = vec![similarity_floor(config.min_similarity_threshold)];
+        if let Some(cap) = config
+            .diversity
+            .as_ref()
+            .and_then(|diversity| diversity.source_cap)
+        {
+            filters.push(source_cap_filter(cap));
+        }
+
+        let mut ranked: Vec<Option<_>> = ranked.into_iter().map(Some).collect();
+        let mut results = Vec::new();
+        for (hit, universal_node) in order.into_iter().filter_map(|index| ranked[index].take()) {
+            results.push(crate::data_store::SearchResult {
+                node: self.universal_to_node(universal_node),
+                score: hit.score,
+                relevance_factors: crate::data_store::RelevanceFactors {
+                    semantic_score: hit.best_similarity(),
+                    keyword_score: 0.0,
+                    structural_score: 0.0,
+                    temporal_score: 0.0,
+                    cross_modal_score: None,
+                },
+                explanation: SearchExplanation {
+                    components: hit.components(&rankings, &LEVEL_SIGNALS),
+                    fusion: Some(config.fusion_strategy),
+                    highlights: Vec::new(),
+                    filters: filters.clone(),
+                    truncated: false,
+                },
+            });
+        }
+
+        Ok(budget.finish(results)?)
+    }
+
+    // Implement DataStore trait methods for root-based hierarchy queries
+    async fn get_nodes_by_root(&self, root_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        // Direct delegation to the implementation method
+        self.get_nodes_by_root_internal(root_id).await
+    }
+
+    async fn get_nodes_by_root_and_type(
+        &self,
+        root_id: &NodeId,
+        r#type: &str,
+    ) -> NodeSpaceResult<Vec<Node>> {
+        // Direct delegation to the implementation method
+        self.get_nodes_by_root_and_type_internal(root_id, r#type)
+            .await
+    }
+
+    async fn get_ordered_children(&self, parent_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        let children = self
+            .get_ordered_children_strict(&parent_id.to_string())
+            .await?;
+        Ok(children
+            .into_iter()
+            .map(|universal_node| self.universal_to_node(universal_node))
+            .collect())
+    }
+
+    async fn insert_child_at(
+        &self,
+        parent_id: &NodeId,
+        mut node: Node,
+        position: usize,
+    ) -> NodeSpaceResult<NodeId> {
+        let node_key = node.id.to_string();
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        if self.get_universal_node_arrow(&node.id).await?.is_some() {
+            return Err(DataStoreError::InvalidNode(format!(
+                "Node {} already exists; use move_node_before or move_node_after",
+                node_key
+            ))
+            .into());
+        }
+
+        let parent_key = parent_id.to_string();
+        let siblings = self.get_ordered_children_strict(&parent_key).await?;
+        let mut order: Vec<String> = siblings.iter().map(|sibling| sibling.id.clone()).collect();
+        order.insert(position.min(order.len()), node_key.clone());
+
+        node.parent_id = Some(parent_id.clone());
+        let mut rows: HashMap<String, UniversalNode> = siblings
+            .into_iter()
+            .map(|sibling| (sibling.id.clone(), sibling))
+            .collect();
+        let mut universal = self.node_to_universal(node.clone(), None);
+        self.resolve_root_id(&mut universal).await?;
+        rows.insert(node_key.clone(), universal);
+        rows.extend(
+            self.get_universal_nodes_arrow(std::slice::from_ref(&parent_key))
+                .await?,
+        );
+
+        // The new row is always written, even when it is first and needs no link
+        let mut changed = hierarchy::relink_siblings(&parent_key, &order, &mut rows);
+        if !changed.contains(&node_key) {
+            changed.push(node_key.clone());
+        }
+        if let Some(parent) = rows.get_mut(&parent_key) {
+            let index = position.min(parent.children_ids.len());
+            parent.children_ids.insert(index, node_key);
+            changed.push(parent_key);
+        }
+        self.write_changed_rows(changed, rows, &[]).await?;
+
+        Ok(node.id)
+    }
+
+    async fn move_node_before(&self, node_id: &NodeId, anchor_id: &NodeId) -> NodeSpaceResult<()> {
+        Ok(self.move_node_beside(node_id, anchor_id, false).await?)
+    }
+
+    async fn move_node_after(&self, node_id: &NodeId, anchor_id: &NodeId) -> NodeSpaceResult<()> {
+        Ok(self.move_node_beside(node_id, anchor_id, true).await?)
+    }
+
+    async fn retrieve_context(
+        &self,
+        embedding: Vec<f32>,
+        options: ContextOptions,
+    ) -> NodeSpaceResult<ContextWindow> {
+        if options.max_hits == 0 {
+            return Ok(ContextWindow::default());
+        }
+
+        let mut nodes: HashMap<String, UniversalNode> = HashMap::new();
+        let mut hits = Vec::new();
+        for (universal_node, distance) in self
+            .vector_search_column(
+                "vector",
+                embedding,
+                options.max_hits,
+                &NodeQuery::new(),
+                FilterMode::PreFilter,
+            )
+            .await?
+        {
+            let score = self.distance_metric.score_from_distance(distance);
+            hits.push((universal_node.id.clone(), score));
+            nodes.insert(universal_node.id.clone(), universal_node);
+        }
+
+        // Parent chains, one batched lookup per level
+        if options.include_ancestors {
+            let mut frontier: Vec<String> = nodes
+                .values()
+                .filter_map(|node| node.parent_id.clone())
+                .collect();
+            while !frontier.is_empty() {
+                frontier.retain(|id| !nodes.contains_key(id));
+                frontier.sort();
+                frontier.dedup();
+                let parents = self.get_universal_nodes_arrow(&frontier).await?;
+                frontier = parents
+                    .values()
+                    .filter_map(|node| node.parent_id.clone())
+                    .collect();
+                nodes.extend(parents);
+            }
+        }
+
+        // Children of the hits' parents give the siblings; children of the hits themselves
+        let mut parent_ids: Vec<String> = Vec::new();
+        for (id, _) in &hits {
+            if options.sibling_window > 0 {
+                parent_ids.extend(nodes.get(id).and_then(|node| node.parent_id.clone()));
+            }
+            if options.include_children {
+                parent_ids.push(id.clone());
+            }
+        }
+        parent_ids.sort();
+        parent_ids.dedup();
+        let mut children = HashMap::new();
+        for (parent_id, unordered) in self.get_children_arrow(&parent_ids).await? {
+            let ordered = hierarchy::order_siblings_lenient(unordered);
+            children.insert(
+                parent_id,
+                ordered
+                    .iter()
+                    .map(|node| node.id.clone())
+                    .collect::<Vec<_>>(),
+            );
+            for node in ordered {
+                nodes.entry(node.id.clone()).or_insert(node);
+            }
+        }
+
+        let (selections, used, truncated) =
+            hierarchy::assemble_context(&hits, &nodes, &children, &options);
+        let mut take = |ids: Vec<String>| -> Vec<Node> {
+            ids.iter()
+                .filter_map(|id| nodes.remove(id))
+                .map(|node| self.universal_to_node(node))
+                .collect()
+        };
+        let hits = selections
+            .into_iter()
+            .filter_map(|selection| {
+                let node = take(vec![selection.hit]).pop()?;
+                Some(ContextHit {
+                    node,
+                    score: selection.score,
+                    ancestors: take(selection.ancestors),
+                    siblings_before: take(selection.siblings_before),
+                    siblings_after: take(selection.siblings_after),
+                    children: take(selection.children),
+                })
+            })
+            .collect();
+
+        Ok(ContextWindow {
+            hits,
+            used,
+            truncated,
+        })
+    }
+}
+
+impl LanceDataStore {
+    /// Get all nodes under a specific root with single indexed query
+    /// This is the core optimization that replaces multiple O(N) database scans
+    /// with a single `root_id` filter evaluated inside LanceDB.
+    pub async fn get_nodes_by_root_internal(&self, root_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        self.find_nodes(NodeQuery::new().root_id(root_id)).await
+    }
+
+    /// Get typed nodes by root for specialized queries
+    /// Combines root filtering with node type filtering for optimal performance
+    pub async fn get_nodes_by_root_and_type_internal(
+        &self,
+        root_id: &NodeId,
+        r#type: &str,
+    ) -> NodeSpaceResult<Vec<Node>> {
+        self.find_nodes(NodeQuery::new().root_id(root_id).node_type(r#type))
+            .await
+    }
+
+    /// Create composite indexes for hierarchy query optimization
+    /// This implements the performance strategy from your architectural recommendations
+    pub async fn create_hierarchy_indexes(&self) -> NodeSpaceResult<()> {
+        let table_guard = self.table.read().await;
+        if let Some(table) = table_guard.as_ref() {
+            // Check if table has data before creating indexes
+            let stats = table
+                .count_rows(None)
+                .await
+                .map_err(|e| DataStoreError::LanceDB(format!("Failed to get row count: {}", e)))?;
+
+            if stats > 0 {
+                // Primary composite index: (root_id, node_type, created_at)
+                // This enables efficient hierarchy + type + temporal queries
+                let _ = table
+                    .create_index(
+                        &["root_id", "node_type", "created_at"],
+                        lancedb::index::Index::BTree(Default::default()),
+                    )
+                    .replace(true)
+                    .execute()
+                    .await;
+
+                // Supporting index: (root_id, parent_id) for relationship queries
+                let _ = table
+                    .create_index(
+                        &["root_id", "parent_id"],
+                        lancedb::index::Index::BTree(Default::default()),
+                    )
+                    .replace(true)
+                    .execute()
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get child nodes using Arrow storage for hierarchical relationships
+    pub async fn get_child_nodes(&self, parent_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
+        self.find_nodes(NodeQuery::new().parent_id(parent_id)).await
+    }
+
+    /// Delete a node and all of its descendants
+    ///
+    /// The node's next sibling takes over its `before_sibling_id` and the
+    /// parent drops it from `children_ids`, in the same write as the deletes.
+    /// Returns the deleted ids, the node first.
+    pub async fn delete_subtree(&self, node_id: &NodeId) -> NodeSpaceResult<Vec<NodeId>> {
+        let node_key = node_id.to_string();
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        let Some(node) = self.get_universal_node_arrow(node_id).await? else {
+            return Err(DataStoreError::NodeNotFound(format!(
+                "Node {} not found for delete",
+                node_key
+            ))
+            .into());
+        };
+
+        let mut rows = HashMap::from([(node_key.clone(), node)]);
+        let changed = self.unlink_node(&node_key, &mut rows).await?;
+        let mut deleted = vec![node_key.clone()];
+        deleted.extend(
+            self.get_descendants_arrow(&node_key)
+                .await?
+                .into_iter()
+                .map(|descendant| descendant.id),
+        );
+        self.write_changed_rows(changed, rows, &deleted).await?;
+
+        Ok(deleted.into_iter().map(NodeId::from_string).collect())
+    }
+
+    /// Move a node and its descendants to `position` among `new_parent_id`'s children
+    ///
+    /// `position` 0 is first and past the end appends. The subtree takes the
+    /// new parent's `root_id`; old and new siblings and both parents'
+    /// `children_ids` are fixed up in the same write.
+    pub async fn move_subtree(
+        &self,
+        node_id: &NodeId,
+        new_parent_id: &NodeId,
+        position: usize,
+    ) -> NodeSpaceResult<()> {
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        Ok(self
+            .relocate_subtree(
+                &node_id.to_string(),
+                &new_parent_id.to_string(),
+                SiblingPlacement::At(position),
+            )
+            .await?)
+    }
+
+    /// Copy a node and its descendants as the last child of `new_parent_id`
+    ///
+    /// Copies get fresh ids; `parent_id`, `before_sibling_id`, `children_ids`
+    /// and `mentions` pointing inside the subtree are remapped to the copies,
+    /// and embeddings are copied as stored. Returns the id of the copied node.
+    pub async fn copy_subtree(
+        &self,
+        node_id: &NodeId,
+        new_parent_id: &NodeId,
+    ) -> NodeSpaceResult<NodeId> {
+        let (node_key, parent_key) = (node_id.to_string(), new_parent_id.to_string());
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        let mut rows = self
+            .get_universal_nodes_arrow(&[node_key.clone(), parent_key.clone()])
+            .await?;
+        let (Some(node), Some(mut parent)) = (rows.remove(&node_key), rows.remove(&parent_key))
+        else {
+            return Err(DataStoreError::NodeNotFound(format!(
+                "Node {} or parent {} not found for copy",
+                node_key, parent_key
+            ))
+            .into());
+        };
+
+        let last_child = self
+            .get_ordered_children_strict(&parent_key)
+            .await?
+            .pop()
+            .map(|child| child.id);
+        let mut subtree = vec![node];
+        subtree.extend(self.get_descendants_arrow(&node_key).await?);
+
+        let copy_ids: HashMap<String, String> = subtree
+            .iter()
+            .map(|row| (row.id.clone(), NodeId::new().to_string()))
+            .collect();
+        let remap = |id: &String| copy_ids.get(id).cloned().unwrap_or_else(|| id.clone());
+        let new_root = parent.root_id.clone().unwrap_or_else(|| parent.id.clone());
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let mut copies: Vec<UniversalNode> = subtree
+            .into_iter()
+            .map(|mut row| {
+                if row.id == node_key {
+                    row.parent_id = Some(parent_key.clone());
+                    row.before_sibling_id = last_child.clone();
+                } else {
+                    row.parent_id = row.parent_id.as_ref().map(remap);
+                    row.before_sibling_id = row.before_sibling_id.as_ref().map(remap);
+                }
+                row.id = remap(&row.id);
+                row.children_ids = row.children_ids.iter().map(remap).collect();
+                row.mentions = row.mentions.iter().map(remap).collect();
+                row.root_id = Some(new_root.clone());
+                row.created_at = now.clone();
+                row.updated_at = now.clone();
+                row
+            })
+            .collect();
+
+        let copy_key = copy_ids[&node_key].clone();
+        parent.children_ids.push(copy_key.clone());
+        parent.updated_at = now;
+        copies.push(parent);
+        self.upsert_nodes_arrow(copies).await?;
+
+        Ok(NodeId::from_string(copy_key))
+    }
+
+    /// Report every hierarchy inconsistency without changing anything
+    ///
+    /// Streams only the link columns of the table, checking `children_ids`,
+    /// sibling chains and `root_id` against `parent_id`.
+    pub async fn check_integrity(&self) -> NodeSpaceResult<IntegrityReport> {
+        let links = self.scan_hierarchy_links().await?;
+        Ok(IntegrityReport {
+            nodes_scanned: links.len(),
+            issues: integrity::check_links(&links),
+        })
+    }
+
+    /// Fix what `check_integrity` reports, treating `parent_id` as the truth
+    ///
+    /// Orphans are handled per `policy` and parent cycles are broken, then
+    /// `children_ids`, sibling chains and `root_id`s are rebuilt. Every change
+    /// lands in one merge_insert. Sibling and subtree operations wait for the
+    /// repair; plain node writes made during the scan may be lost.
+    pub async fn repair(&self, policy: RepairPolicy) -> NodeSpaceResult<RepairReport> {
+        let _hierarchy = self.hierarchy_lock.lock().await;
+        let links = self.scan_hierarchy_links().await?;
+        let issues = integrity::check_links(&links);
+        if issues.is_empty() {
+            return Ok(RepairReport::default());
+        }
+
+        let plan = integrity::plan_repair(links, &policy);
+        let updated: Vec<String> = plan.updated.iter().map(|links| links.id.clone()).collect();
+        let mut rows = self.get_universal_nodes_arrow(&updated).await?;
+        for links in plan.updated {
+            if let Some(row) = rows.get_mut(&links.id) {
+                links.apply(row);
+            }
+        }
+        self.write_changed_rows(updated.clone(), rows, &plan.deleted)
+            .await?;
+
+        Ok(RepairReport {
+            issues,
+            updated: updated.into_iter().map(NodeId::from_string).collect(),
+            deleted: plan.deleted.into_iter().map(NodeId::from_string).collect(),
+        })
+    }
+
+    /// Create or update relationship using Arrow storage for entity connections
+    pub async fn update_relationship(
+        &self,
+        node_id: &NodeId,
+        parent_id: Option<NodeId>,
+        children_ids: Vec<NodeId>,
+    ) -> NodeSpaceResult<()> {
+        if let Some(mut node) = self.get_node(node_id).await? {
+            let mut metadata = node.metadata.unwrap_or_else(|| serde_json::json!({}));
+
+            if let Some(parent_id) = parent_id {
+                metadata["parent_id"] = serde_json::Value::String(parent_id.to_string());
+            } else {
+                metadata
+                    .as_object_mut()
+                    .and_then(|obj| obj.remove("parent_id"));
+            }
 
-            // Sort by similarity and limit results
-            results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-            results.truncate(limit);
+            metadata["children_ids"] = serde_json::Value::Array(
+                children_ids
+                    .into_iter()
+                    .map(|id| serde_json::Value::String(id.to_string()))
+                    .collect(),
+            );
 
-            Ok(results)
-        } else {
-            Err(DataStoreError::LanceDB("Table not initialized".to_string()))
+            node.metadata = Some(metadata);
+            self.upsert_node(node).await?;
         }
+
+        Ok(())
     }
 
-    /// Get a single node by ID using LanceDB query with application-level filtering
-    async fn get_node_arrow(&self, id: &NodeId) -> Result<Option<Node>, DataStoreError> {
-        let table_guard = self.table.read().await;
-        if let Some(table) = table_guard.as_ref() {
-            let target_id = id.to_string();
+    /// Hybrid search combining semantic search with metadata filtering using Arrow storage
+    ///
+    /// Each key of `metadata_filter` (a JSON object) must equal its value in
+    /// the node's metadata. Filters are applied before the ANN search.
+    pub async fn hybrid_search(
+        &self,
+        embedding: Vec<f32>,
+        node_type_filter: Option<String>,
+        metadata_filter: Option<serde_json::Value>,
+        limit: usize,
+    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
+        let mut filter = NodeQuery::new();
+        if let Some(node_type) = node_type_filter {
+            filter = filter.node_type(node_type);
+        }
+        match metadata_filter {
+            Some(serde_json::Value::Object(fields)) => {
+                for (key, value) in fields {
+                    filter = filter.metadata_equals(&key, value);
+                }
+            }
+            Some(serde_json::Value::Null) | None => {}
+            Some(other) => {
+                return Err(DataStoreError::InvalidQuery(format!(
+                    "Metadata filter must be a JSON object, got {}",
+                    other
+                ))
+                .into())
+            }
+        }
 
-            // Use LanceDB query with reasonable limit and filter in application
-            let results_stream = table
-                .query()
-                .limit(1000) // Reasonable limit to avoid loading entire table
-                .execute()
-                .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Query by ID failed: {}", e)))?;
+        self.filtered_vector_search(embedding, filter, limit, FilterMode::PreFilter)
+            .await
+    }
+}
 
-            // Collect the results into Vec<RecordBatch>
-            let batches: Vec<RecordBatch> = futures::TryStreamExt::try_collect(results_stream)
-                .await
-                .map_err(|e| {
-                    DataStoreError::LanceDB(format!("Failed to collect query results: {}", e))
-                })?;
+/// Row of a nullable embedding column, `None` when the column is absent or the row is null
+fn optional_vector_at(batch: &RecordBatch, column: &str, row: usize) -> Option<Vec<f32>> {
+    let vectors = batch
+        .column_by_name(column)?
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()?;
+    if vectors.is_null(row) {
+        return None;
+    }
 
-            // Process the retrieved batches and find matching ID
-            for batch in batches.iter() {
-                if batch.num_rows() > 0 {
-                    let universal_nodes = self.extract_nodes_from_batch(batch)?;
-
-                    // Find the node with matching ID
-                    for universal_node in universal_nodes {
-                        if universal_node.id == target_id {
-                            // Found matching node
-                            let node = self.universal_to_node(universal_node);
-                            return Ok(Some(node));
-                        }
-                    }
-                }
-            }
+    let values = vectors.value(row);
+    let floats = values.as_any().downcast_ref::<Float32Array>()?;
+    Some(floats.values().to_vec())
+}
 
-            Ok(None) // No matching node found
-        } else {
-            Err(DataStoreError::LanceDB("Table not initialized".to_string()))
-        }
+/// Row of a nullable string column, `None` when the column is absent or the row is null
+fn optional_string_at(batch: &RecordBatch, column: &str, row: usize) -> Option<String> {
+    let strings = batch
+        .column_by_name(column)?
+        .as_any()
+        .downcast_ref::<StringArray>()?;
+    if strings.is_null(row) {
+        return None;
     }
+    Some(strings.value(row).to_string())
+}
 
-    /// Delete a node using native LanceDB delete operations
-    async fn delete_node_arrow(&self, id: &NodeId) -> Result<(), DataStoreError> {
-        let table_guard = self.table.read().await;
-        if let Some(table) = table_guard.as_ref() {
-            // Use native LanceDB delete operation with SQL predicate
-            let _delete_result = table
-                .delete(&format!("id = '{}'", id.as_str().replace("'", "''")))
-                .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Delete operation failed: {}", e)))?;
+/// Row of a string list column, empty when the column is absent or the row is null
+fn string_list_at(batch: &RecordBatch, column: &str, row: usize) -> Vec<String> {
+    let Some(lists) = batch
+        .column_by_name(column)
+        .and_then(|col| col.as_any().downcast_ref::<ListArray>())
+    else {
+        return Vec::new();
+    };
+    if lists.is_null(row) {
+        return Vec::new();
+    }
 
-            // DeleteResult contains version info - we just verify it succeeded
-            Ok(())
-        } else {
-            Err(DataStoreError::LanceDB("Table not initialized".to_string()))
-        }
+    let values = lists.value(row);
+    match values.as_any().downcast_ref::<StringArray>() {
+        Some(strings) => strings.iter().flatten().map(str::to_string).collect(),
+        None => Vec::new(),
     }
+}
 
-    /// Convert UniversalNode back to NodeSpace Node
-    /// For TextNode and DateNode, keep metadata empty to maintain simplified approach
-    /// For other node types, preserve their type-specific metadata
-    fn universal_to_node(&self, universal: UniversalNode) -> Node {
-        let content = serde_json::Value::String(universal.content);
+/// Relationship columns of every row in a batch projected for graph walks
+fn node_links_from_batch(batch: &RecordBatch) -> Result<Vec<NodeLinks>, DataStoreError> {
+    let ids = batch
+        .column_by_name("id")
+        .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| DataStoreError::Arrow("Missing or invalid id column".to_string()))?;
+
+    Ok((0..batch.num_rows())
+        .map(|row| NodeLinks {
+            id: ids.value(row).to_string(),
+            parent_id: optional_string_at(batch, "parent_id", row),
+            children_ids: string_list_at(batch, "children_ids", row),
+            mentions: string_list_at(batch, "mentions", row),
+            root_id: optional_string_at(batch, "root_id", row),
+        })
+        .collect())
+}
 
-        // Determine if this is a simplified node type (text/date) that should have empty metadata
-        let final_metadata = match universal.r#type.as_str() {
-            "text" | "date" => {
-                // For text and date nodes: Keep metadata empty/null for simplified approach
-                // Hierarchical data is maintained in parent_id/children_ids fields in UniversalNode
-                // and will be computed by core-logic layer when needed
-                None
-            }
-            _ => {
-                // For other node types (image, task, etc.): Preserve their metadata
-                // These may have type-specific properties that need to be maintained
-                let mut metadata = universal.metadata.unwrap_or_else(|| serde_json::json!({}));
+/// Hierarchy columns of every row in a batch projected for integrity scans
+fn hierarchy_links_from_batch(batch: &RecordBatch) -> Result<Vec<HierarchyLinks>, DataStoreError> {
+    let ids = batch
+        .column_by_name("id")
+        .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| DataStoreError::Arrow("Missing or invalid id column".to_string()))?;
+
+    Ok((0..batch.num_rows())
+        .map(|row| HierarchyLinks {
+            id: ids.value(row).to_string(),
+            parent_id: optional_string_at(batch, "parent_id", row),
+            before_sibling_id: optional_string_at(batch, "before_sibling_id", row),
+            children_ids: string_list_at(batch, "children_ids", row),
+            root_id: optional_string_at(batch, "root_id", row),
+        })
+        .collect())
+}
 
-                // Only add node_type for non-simplified nodes
-                metadata["node_type"] = serde_json::Value::String(universal.r#type.clone());
+/// Explanation component for a signal added to the score as `score * weight`
+fn weighted_component(signal: ScoreSignal, score: f32, weight: f64) -> ScoreComponent {
+    ScoreComponent {
+        signal,
+        score,
+        weight: weight as f32,
+        rank: None,
+        contribution: score * weight as f32,
+    }
+}
 
-                // For non-simplified nodes, we can still include hierarchical data in metadata
-                // for backwards compatibility, but it should be computed from the canonical source
-                if let Some(parent_id) = &universal.parent_id {
-                    metadata["parent_id"] = serde_json::Value::String(parent_id.clone());
-                }
-                if !universal.children_ids.is_empty() {
-                    metadata["children_ids"] = serde_json::Value::Array(
-                        universal
-                            .children_ids
-                            .iter()
-                            .map(|id| serde_json::Value::String(id.clone()))
-                            .collect(),
-                    );
-                }
-                if !universal.mentions.is_empty() {
-                    metadata["mentions"] = serde_json::Value::Array(
-                        universal
-                            .mentions
-                            .iter()
-                            .map(|id| serde_json::Value::String(id.clone()))
-                            .collect(),
-                    );
-                }
+/// Explanation filter for `HybridSearchConfig::min_similarity_threshold`
+fn similarity_floor(threshold: f64) -> String {
+    format!("similarity >= {}", threshold)
+}
 
-                Some(metadata)
-            }
-        };
+/// Explanation filter for a `DiversityConfig::source_cap`
+fn source_cap_filter(cap: SourceCap) -> String {
+    match cap {
+        SourceCap::PerRoot(limit) => format!("at most {} per root_id", limit),
+        SourceCap::PerParent(limit) => format!("at most {} per parent_id", limit),
+    }
+}
 
-        Node {
-            id: NodeId::from_string(universal.id),
-            r#type: universal.r#type,
-            content,
-            metadata: final_metadata,
-            created_at: universal.created_at,
-            updated_at: universal.updated_at,
-            parent_id: universal.parent_id.map(NodeId::from_string),
-            before_sibling: universal.before_sibling_id.map(NodeId::from_string),
-            next_sibling: None, // TODO: Map from before_sibling_id when core-types adds before_sibling field
-            root_id: universal.root_id.map(NodeId::from_string),
-        }
+/// Fail if a batch names the same node twice
+///
+/// A merge_insert keyed on `id` can't tell which of two source rows wins,
+/// and a plain append would store both.
+fn reject_duplicate_ids(ids: &[String]) -> Result<(), DataStoreError> {
+    let mut seen = HashSet::with_capacity(ids.len());
+    match ids.iter().find(|id| !seen.insert(id.as_str())) {
+        Some(id) => Err(DataStoreError::InvalidQuery(format!(
+            "Node {} appears more than once in the batch",
+            id
+        ))),
+        None => Ok(()),
     }
 }
 
-// Implement the DataStore trait for compatibility with existing NodeSpace architecture
-#[async_trait]
-impl DataStore for LanceDataStore {
-    async fn store_node(&self, node: Node) -> NodeSpaceResult<NodeId> {
-        let universal = self.node_to_universal(node.clone(), None);
+/// `id IN (...)` predicate for a chunk of node ids
+fn id_in_predicate(ids: &[String]) -> String {
+    column_in_predicate("id", ids)
+}
 
-        // Store using Arrow persistence
-        self.store_node_arrow(universal.clone()).await?;
+/// `column IN (...)` predicate over string literals
+fn column_in_predicate(column: &str, values: &[String]) -> String {
+    let literals: Vec<String> = values
+        .iter()
+        .map(|value| sql_string_literal(value))
+        .collect();
+    format!("{} IN ({})", column, literals.join(", "))
+}
 
-        Ok(node.id)
+/// Build an ANN index on a vector column of `table`
+async fn build_vector_index(
+    table: &Table,
+    column: &str,
+    params: IndexParams,
+    distance_metric: DistanceMetric,
+) -> Result<(), DataStoreError> {
+    if !VECTOR_COLUMNS.contains(&column) {
+        return Err(DataStoreError::VectorIndexCreation(format!(
+            "'{}' is not a vector column",
+            column
+        )));
     }
 
-    async fn get_node(&self, id: &NodeId) -> NodeSpaceResult<Option<Node>> {
-        // Use Arrow-based retrieval
-        let result = self.get_node_arrow(id).await?;
-        Ok(result)
-    }
+    table
+        .create_index(&[column], params.to_index(distance_metric))
+        .replace(params.replace)
+        .execute()
+        .await
+        .map_err(|e| {
+            DataStoreError::VectorIndexCreation(format!(
+                "Failed to create vector index on '{}': {}",
+                column, e
+            ))
+        })
+}
 
-    async fn update_node(&self, node: Node) -> NodeSpaceResult<()> {
-        // First verify the node exists and get the old version
-        let existing_node = self.get_node(&node.id).await?.ok_or_else(|| {
-            DataStoreError::NodeNotFound(format!("Node {} not found for update", node.id))
-        })?;
+async fn table_indexes(table: &Table) -> Result<Vec<IndexInfo>, DataStoreError> {
+    let indexes = table
+        .list_indices()
+        .await
+        .map_err(|e| DataStoreError::LanceDBIndex(format!("Failed to list indexes: {}", e)))?;
+
+    Ok(indexes
+        .into_iter()
+        .map(|index| IndexInfo {
+            name: index.name,
+            index_type: index.index_type.to_string(),
+            columns: index.columns,
+        })
+        .collect())
+}
 
-        // Update the node's updated_at timestamp
-        let mut updated_node = node;
-        updated_node.updated_at = chrono::Utc::now().to_rfc3339();
+async fn table_index_stats(
+    table: &Table,
+    name: &str,
+) -> Result<Option<IndexStats>, DataStoreError> {
+    let stats = table.index_stats(name).await.map_err(|e| {
+        DataStoreError::LanceDBIndex(format!("Failed to read stats for index '{}': {}", name, e))
+    })?;
+
+    Ok(stats.map(|stats| IndexStats {
+        index_type: stats.index_type.to_string(),
+        num_indexed_rows: stats.num_indexed_rows,
+        num_unindexed_rows: stats.num_unindexed_rows,
+        distance_type: stats.distance_type.map(|distance| distance.to_string()),
+    }))
+}
 
-        // Check if content changed - if so, we need to regenerate embeddings
-        let content_changed = existing_node.content != updated_node.content;
+async fn optimize_table_indexes(table: &Table) -> Result<(), DataStoreError> {
+    table
+        .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+        .await
+        .map_err(|e| DataStoreError::LanceDBIndex(format!("Failed to optimize indexes: {}", e)))?;
 
-        if content_changed {
-            let embedding = if let Some(ref generator) = self.embedding_generator {
-                // Generate new embedding automatically
-                match generator
-                    .generate_embedding(&updated_node.content.to_string())
-                    .await
-                {
-                    Ok(embedding) => embedding,
-                    Err(_) => vec![0.0; self.vector_dimension],
-                }
-            } else {
-                vec![0.0; self.vector_dimension]
-            };
+    Ok(())
+}
+
+async fn count_rows_where(table: &Table, filter: Option<String>) -> Result<usize, DataStoreError> {
+    table
+        .count_rows(filter)
+        .await
+        .map_err(|e| DataStoreError::LanceDB(format!("Failed to get row count: {}", e)))
+}
+
+/// Build, retrain or optimize vector indexes as `policy` asks for the table's size
+///
+/// Takes the table handle rather than the store so it can run in a spawned
+/// task. Returns false when the table hasn't grown enough to be evaluated.
+async fn apply_vector_index_policy(
+    table: &Table,
+    policy: &VectorIndexPolicy,
+    distance_metric: DistanceMetric,
+    state: &mut VectorIndexState,
+) -> Result<bool, DataStoreError> {
+    let row_count = count_rows_where(table, None).await?;
+    if row_count < policy.min_rows {
+        return Ok(false);
+    }
+    if let Some(evaluated_at) = state.evaluated_at_rows {
+        if row_count < evaluated_at + policy.optimize_after_rows {
+            return Ok(false);
+        }
+    }
+    // Recorded up front so a failing build is retried after further growth,
+    // not on every write
+    state.evaluated_at_rows = Some(row_count);
 
-            let universal = self.node_to_universal(updated_node.clone(), Some(embedding));
+    let indexes = table_indexes(table).await?;
+    let mut needs_optimize = false;
 
-            // Use atomic delete + insert for update
-            self.delete_node_by_exact_id(&updated_node.id).await?;
-            self.store_node_arrow(universal).await?;
+    for column in VECTOR_COLUMNS {
+        let rows = if *column == "vector" {
+            row_count
         } else {
-            // Content unchanged - preserve existing embedding
-            let universal = self.node_to_universal(updated_node.clone(), None);
+            count_rows_where(table, Some(format!("{} IS NOT NULL", column))).await?
+        };
 
-            // Use atomic delete + insert for update
-            self.delete_node_by_exact_id(&updated_node.id).await?;
-            self.store_node_arrow(universal).await?;
-        }
+        let existing = indexes
+            .iter()
+            .find(|index| index.columns.len() == 1 && index.columns[0] == *column);
+
+        let rebuild = match existing {
+            None => rows >= policy.min_rows,
+            Some(index) => {
+                let built_at = match state.built_at_rows.get(*column) {
+                    Some(built_at) => *built_at,
+                    None => table_index_stats(table, &index.name)
+                        .await?
+                        .map_or(rows, |stats| stats.num_indexed_rows),
+                };
+                state.built_at_rows.insert(column.to_string(), built_at);
+                rows as f64 >= built_at as f64 * policy.retrain_growth_factor
+            }
+        };
 
-        Ok(())
+        if rebuild {
+            let params = IndexParams {
+                replace: true,
+                ..policy.index_params.clone()
+            };
+            build_vector_index(table, column, params, distance_metric).await?;
+            state.built_at_rows.insert(column.to_string(), rows);
+        } else if existing.is_some() {
+            needs_optimize = true;
+        }
     }
 
-    async fn update_node_with_embedding(
-        &self,
-        node: Node,
-        embedding: Vec<f32>,
-    ) -> NodeSpaceResult<()> {
-        // Verify the node exists
-        if self.get_node(&node.id).await?.is_none() {
-            return Err(DataStoreError::NodeNotFound(format!(
-                "Node {} not found for update",
-                node.id
+    if needs_optimize {
+        optimize_table_indexes(table).await.map_err(|e| {
+            DataStoreError::VectorIndexCreation(format!(
+                "Failed to add new rows to vector indexes: {}",
+                e
             ))
-            .into());
-        }
+        })?;
+    }
 
-        // Update the node's updated_at timestamp
-        let mut updated_node = node;
-        updated_node.updated_at = chrono::Utc::now().to_rfc3339();
+    Ok(true)
+}
 
-        // Use the provided embedding
-        let universal = self.node_to_universal(updated_node.clone(), Some(embedding));
+/// Carry over the embedding levels `updated` doesn't supply from the stored row
+///
+/// `node_to_universal` reads contextual and hierarchical vectors from
+/// metadata, which text and date nodes come back from `get_node` without.
+fn keep_stored_embeddings(updated: &mut UniversalNode, existing: &UniversalNode) {
+    if updated.contextual_vector.is_none() {
+        updated.contextual_vector = existing.contextual_vector.clone();
+    }
+    if updated.hierarchical_vector.is_none() {
+        updated.hierarchical_vector = existing.hierarchical_vector.clone();
+    }
+    if updated.embedding_model.is_none() {
+        updated.embedding_model = existing.embedding_model.clone();
+    }
+    if updated.embeddings_generated_at.is_none() {
+        updated.embeddings_generated_at = existing.embeddings_generated_at.clone();
+    }
+}
 
-        // Use atomic delete + insert for update
-        self.delete_node_by_exact_id(&updated_node.id).await?;
-        self.store_node_arrow(universal).await?;
+/// A node whose `parent_id` was cleared becomes a root again
+///
+/// The caller's `root_id` is usually the one `get_node` returned, so keeping
+/// it would leave the node and its subtree under the old document. Matches
+/// what `repair` does for detached nodes.
+fn clear_root_if_detached(updated: &mut UniversalNode, existing: &UniversalNode) {
+    if updated.parent_id.is_none() && existing.parent_id.is_some() {
+        updated.root_id = None;
+    }
+}
 
-        Ok(())
+/// Node content as stored in the `content` column (strings unquoted, other JSON serialized)
+fn content_to_string(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
     }
+}
 
-    async fn delete_node(&self, id: &NodeId) -> NodeSpaceResult<()> {
-        // Use Arrow-based deletion
-        self.delete_node_arrow(id).await?;
+/// Simple cosine similarity implementation for cases where LanceDB native scoring isn't available
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
 
-        Ok(())
+    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
     }
 
-    async fn query_nodes(&self, query: &str) -> NodeSpaceResult<Vec<Node>> {
-        // Use Arrow-based query
-        let universal_nodes = self.query_nodes_arrow(query).await?;
-        let nodes = universal_nodes
-            .into_iter()
-            .map(|universal| self.universal_to_node(universal))
-            .collect();
-        Ok(nodes)
+    dot_product / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_store::{ContextBudget, FusionStrategy, Highlight};
+    use crate::integrity::{IntegrityIssue, OrphanRepair};
+    use crate::query::{NodeOrderField, SortDirection};
+    use crate::search_budget::CancellationToken;
+    use crate::traversal::TraversalOrder;
+    use tempfile::tempdir;
+
+    async fn create_test_store() -> LanceDataStore {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        LanceDataStore::new(db_path.to_str().unwrap()).await.unwrap()
     }
 
-    async fn create_relationship(
-        &self,
-        from: &NodeId,
-        to: &NodeId,
-        _rel_type: &str,
-    ) -> NodeSpaceResult<()> {
-        // Transactional integrity: prepare both updates before committing either
-        let mut parent_node_opt = self.get_node(from).await?;
-        let mut child_node_opt = self.get_node(to).await?;
+    #[tokio::test]
+    async fn test_before_sibling_persistence() {
+        let store = create_test_store().await;
+
+        let node_a = Node::new("text".to_string(), serde_json::json!({"text": "Node A"}));
+        let node_b = Node::new("text".to_string(), serde_json::json!({"text": "Node B"}))
+            .with_before_sibling(Some(node_a.id.clone()));
+
+        // Store both nodes
+        store.store_node(node_a.clone()).await.unwrap();
+        store.store_node(node_b.clone()).await.unwrap();
+
+        // Retrieve and verify sibling relationship preserved
+        let retrieved_b = store.get_node(&node_b.id).await.unwrap().unwrap();
+        assert_eq!(retrieved_b.before_sibling, Some(node_a.id));
+    }
 
-        // Validate both nodes exist before making any changes
-        let parent_node = parent_node_opt.as_mut().ok_or_else(|| {
-            DataStoreError::NodeNotFound(format!("Parent node {} not found", from.as_str()))
-        })?;
-        let child_node = child_node_opt.as_mut().ok_or_else(|| {
-            DataStoreError::NodeNotFound(format!("Child node {} not found", to.as_str()))
-        })?;
+    #[tokio::test]
+    async fn test_null_before_sibling_handling() {
+        let store = create_test_store().await;
 
-        // Prepare parent node update
-        let mut parent_metadata = parent_node
-            .metadata
-            .clone()
-            .unwrap_or_else(|| serde_json::json!({}));
-        let mut children_ids: Vec<String> = parent_metadata
-            .get("children_ids")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
+        let node = Node::new("text".to_string(), serde_json::json!({"text": "Node"}));
+        // No before_sibling set (should be None)
 
-        let needs_parent_update = !children_ids.contains(&to.to_string());
-        if needs_parent_update {
-            children_ids.push(to.to_string());
-            parent_metadata["children_ids"] = serde_json::Value::Array(
-                children_ids
-                    .into_iter()
-                    .map(serde_json::Value::String)
-                    .collect(),
-            );
-        }
+        store.store_node(node.clone()).await.unwrap();
+        let retrieved = store.get_node(&node.id).await.unwrap().unwrap();
 
-        // Prepare child node update
-        let mut child_metadata = child_node
-            .metadata
-            .clone()
-            .unwrap_or_else(|| serde_json::json!({}));
-        let needs_child_update =
-            child_metadata.get("parent_id").and_then(|v| v.as_str()) != Some(from.as_str());
-        if needs_child_update {
-            child_metadata["parent_id"] = serde_json::Value::String(from.to_string());
-        }
-
-        // Commit both updates atomically
-        if needs_parent_update {
-            parent_node.metadata = Some(parent_metadata);
-            self.store_node(parent_node.clone()).await.map_err(|e| {
-                DataStoreError::Database(format!("Failed to update parent node: {}", e))
-            })?;
-        }
+        assert_eq!(retrieved.before_sibling, None);
+    }
 
-        if needs_child_update {
-            child_node.metadata = Some(child_metadata);
-            self.store_node(child_node.clone()).await.map_err(|e| {
-                // If child update fails, we should ideally rollback parent update
-                // For now, log the inconsistency - proper transaction support would be better
-                DataStoreError::Database(format!(
-                    "Failed to update child node (potential inconsistency): {}",
-                    e
-                ))
-            })?;
-        }
+    #[tokio::test]
+    async fn test_get_node_uses_exact_id_predicate() {
+        let store = create_test_store().await;
 
-        Ok(())
+        // Quotes in ids must be escaped in the pushed-down predicate
+        let quoted = Node::with_id(
+            NodeId::from_string("node-'quoted'".to_string()),
+            "text".to_string(),
+            serde_json::json!("Quoted id"),
+        );
+        let other = Node::new("text".to_string(), serde_json::json!("Other"));
+        store.store_node(quoted.clone()).await.unwrap();
+        store.store_node(other.clone()).await.unwrap();
+
+        let retrieved = store.get_node(&quoted.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.id, quoted.id);
+
+        let missing = NodeId::from_string("does-not-exist".to_string());
+        assert!(store.get_node(&missing).await.unwrap().is_none());
+
+        // The first write built the id index, and nothing was left failing
+        let indexes = store.list_indexes().await.unwrap();
+        assert!(indexes
+            .iter()
+            .any(|index| index.columns == vec!["id".to_string()]));
+        assert_eq!(
+            store.index_maintenance_status().await,
+            IndexMaintenanceStatus::default()
+        );
     }
 
-    async fn store_node_with_embedding(
-        &self,
-        node: Node,
-        embedding: Vec<f32>,
-    ) -> NodeSpaceResult<NodeId> {
-        let universal = self.node_to_universal(node.clone(), Some(embedding));
+    #[tokio::test]
+    async fn test_batch_node_operations() {
+        let store = create_test_store().await;
 
-        // Store using Arrow persistence
-        self.store_node_arrow(universal.clone()).await?;
+        // More rows than the old 1000-row scan window used by get_node
+        let nodes: Vec<Node> = (0..1200)
+            .map(|i| Node::new("text".to_string(), serde_json::json!(format!("Node {}", i))))
+            .collect();
+        let ids = store.store_nodes(nodes.clone()).await.unwrap();
+        assert_eq!(ids.len(), 1200);
 
-        Ok(node.id)
-    }
+        let last = store.get_node(&ids[1199]).await.unwrap().unwrap();
+        assert_eq!(last.content, serde_json::json!("Node 1199"));
 
-    async fn search_similar_nodes(
-        &self,
-        embedding: Vec<f32>,
-        limit: usize,
-    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
-        // Use Arrow-based vector search
-        let results = self.vector_search_arrow(embedding, limit).await?;
-        Ok(results)
+        let missing = NodeId::from_string("missing".to_string());
+        let fetched = store
+            .get_nodes(&[ids[5].clone(), missing, ids[1100].clone()])
+            .await
+            .unwrap();
+        assert_eq!(fetched.len(), 3);
+        assert_eq!(fetched[0].as_ref().unwrap().id, ids[5]);
+        assert!(fetched[1].is_none());
+        assert_eq!(fetched[2].as_ref().unwrap().id, ids[1100]);
+
+        let mut edited = nodes[0].clone();
+        edited.content = serde_json::json!("Edited");
+        store.update_nodes(vec![edited]).await.unwrap();
+        let reloaded = store.get_node(&ids[0]).await.unwrap().unwrap();
+        assert_eq!(reloaded.content, serde_json::json!("Edited"));
+
+        // A batch naming a node twice is refused before anything is written
+        let mut twice = nodes[1].clone();
+        twice.content = serde_json::json!("Twice");
+        assert!(store
+            .update_nodes(vec![twice.clone(), nodes[1].clone()])
+            .await
+            .is_err());
+        let fresh = Node::new("text".to_string(), serde_json::json!("Fresh"));
+        assert!(store
+            .store_nodes(vec![fresh.clone(), fresh.clone()])
+            .await
+            .is_err());
+        assert!(store.get_node(&fresh.id).await.unwrap().is_none());
+        let unchanged = store.get_node(&ids[1]).await.unwrap().unwrap();
+        assert_eq!(unchanged.content, serde_json::json!("Node 1"));
+
+        store.delete_nodes(&ids[..600]).await.unwrap();
+        assert!(store.get_node(&ids[0]).await.unwrap().is_none());
+        assert!(store.get_node(&ids[600]).await.unwrap().is_some());
     }
 
-    async fn update_node_embedding(&self, id: &NodeId, embedding: Vec<f32>) -> NodeSpaceResult<()> {
-        // Get the existing node, update its embedding, and store it back
-        if let Some(mut node) = self.get_node(id).await? {
-            // Update the embedding in metadata
-            let mut metadata = node.metadata.unwrap_or_else(|| serde_json::json!({}));
-            metadata["vector"] = serde_json::Value::Array(
-                embedding
-                    .iter()
-                    .map(|&f| {
-                        serde_json::Value::Number(serde_json::Number::from_f64(f as f64).unwrap())
-                    })
-                    .collect(),
-            );
-            node.metadata = Some(metadata);
+    #[tokio::test]
+    async fn test_upsert_replaces_row_in_place() {
+        let store = create_test_store().await;
 
-            // Re-store the node with updated embedding
-            self.store_node_with_embedding(node, embedding).await?;
-        }
+        let mut node = Node::new("text".to_string(), serde_json::json!("Original upsert"));
+        store.upsert_node(node.clone()).await.unwrap();
 
-        Ok(())
-    }
+        node.content = serde_json::json!("Changed upsert");
+        store.upsert_node(node.clone()).await.unwrap();
 
-    async fn semantic_search_with_embedding(
-        &self,
-        embedding: Vec<f32>,
-        limit: usize,
-    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
-        // Same as search_similar_nodes for this implementation
-        self.search_similar_nodes(embedding, limit).await
+        let matches = store.query_nodes("upsert").await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].content, serde_json::json!("Changed upsert"));
     }
 
-    // Cross-modal search methods
-    async fn create_image_node(&self, image_node: ImageNode) -> NodeSpaceResult<String> {
-        // Convert ImageNode to UniversalNode format
-        let universal_node = UniversalNode {
-            id: image_node.id.clone(),
-            r#type: "image".to_string(),
-            content: image_node
-                .metadata
-                .description
-                .unwrap_or_else(|| format!("Image: {}", image_node.metadata.filename)),
-            individual_vector: image_node.embedding.clone(),
-            contextual_vector: None,
-            hierarchical_vector: None,
-            embedding_model: None,
-            embeddings_generated_at: None,
-            vector: image_node.embedding,
-            parent_id: None,
-            before_sibling_id: None,
-            children_ids: vec![],
-            mentions: vec![],
-            root_id: None,   // Root hierarchy optimization
-            // root_type field removed
-            created_at: image_node.created_at.to_rfc3339(),
-            updated_at: image_node.created_at.to_rfc3339(),
-            metadata: Some(serde_json::json!({
-                "image_data": base64::prelude::BASE64_STANDARD.encode(&image_node.image_data),
-                "filename": image_node.metadata.filename,
-                "mime_type": image_node.metadata.mime_type,
-                "width": image_node.metadata.width,
-                "height": image_node.metadata.height,
-                "exif_data": image_node.metadata.exif_data
-            })),
-        };
+    #[tokio::test]
+    async fn test_update_node_keeps_embedding_when_content_unchanged() {
+        let store = create_test_store().await;
 
-        // Store in LanceDB table with proper Arrow schema
-        self.store_node_arrow(universal_node).await?;
+        let node = Node::new("text".to_string(), serde_json::json!("Stable content"));
+        let embedding = vec![0.5; 384];
+        store
+            .store_node_with_embedding(node.clone(), embedding.clone())
+            .await
+            .unwrap();
 
-        Ok(image_node.id)
+        store.update_node(node.clone()).await.unwrap();
+
+        let embeddings = store.get_node_embeddings(&node.id).await.unwrap().unwrap();
+        assert_eq!(embeddings.individual, embedding);
+        assert_eq!(store.query_nodes("Stable").await.unwrap().len(), 1);
     }
 
-    async fn get_image_node(&self, id: &str) -> NodeSpaceResult<Option<ImageNode>> {
-        // Get node from Arrow storage
-        let node_id = NodeId::from_string(id.to_string());
-        if let Some(node) = self.get_node(&node_id).await? {
-            if let Some(metadata) = &node.metadata {
-                if metadata.get("node_type").and_then(|v| v.as_str()) == Some("image") {
-                    // Convert back to ImageNode
-                    let image_data = base64::prelude::BASE64_STANDARD
-                        .decode(
-                            metadata
-                                .get("image_data")
-                                .and_then(|v| v.as_str())
-                                .ok_or_else(|| {
-                                    DataStoreError::InvalidNode("Missing image data".to_string())
-                                })?,
-                        )
-                        .map_err(|e| {
-                            DataStoreError::InvalidNode(format!("Invalid base64 image data: {}", e))
-                        })?;
+    #[tokio::test]
+    async fn test_find_nodes_structured_query() {
+        let store = create_test_store().await;
+        let root_id = NodeId::new();
 
-                    // Extract vector from metadata or use default
-                    let embedding = metadata
-                        .get("vector")
-                        .and_then(|v| v.as_array())
-                        .map(|arr| {
-                            arr.iter()
-                                .filter_map(|v| v.as_f64().map(|f| f as f32))
-                                .collect()
-                        })
-                        .unwrap_or_else(|| vec![0.0; 384]);
+        let mut nodes = Vec::new();
+        for (i, status) in ["open", "done", "open"].iter().enumerate() {
+            let mut task = Node::new("task".to_string(), serde_json::json!(format!("Task {}", i)))
+                .with_metadata(serde_json::json!({"status": status, "priority": i}));
+            task.root_id = Some(root_id.clone());
+            task.created_at = format!("2025-01-0{}T00:00:00+00:00", i + 1);
+            nodes.push(task);
+        }
+        let mut note = Node::new("text".to_string(), serde_json::json!("Task notes"));
+        note.root_id = Some(root_id.clone());
+        nodes.push(note);
+        store.store_nodes(nodes).await.unwrap();
+
+        let open_tasks = store
+            .find_nodes(
+                NodeQuery::new()
+                    .node_type("task")
+                    .root_id(&root_id)
+                    .metadata_equals("status", serde_json::json!("open"))
+                    .order_by(NodeOrderField::CreatedAt, SortDirection::Descending),
+            )
+            .await
+            .unwrap();
+        let contents: Vec<_> = open_tasks.iter().map(|n| n.content.clone()).collect();
+        assert_eq!(
+            contents,
+            vec![serde_json::json!("Task 2"), serde_json::json!("Task 0")]
+        );
+
+        let january_2nd = chrono::DateTime::parse_from_rfc3339("2025-01-02T00:00:00+00:00")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let recent = store
+            .find_nodes(
+                NodeQuery::new()
+                    .created_after(january_2nd)
+                    .content_contains("TASK")
+                    .order_by(NodeOrderField::CreatedAt, SortDirection::Ascending)
+                    .limit(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].content, serde_json::json!("Task 1"));
+
+        // 01:00 at +02:00 is still January 1st in UTC, whatever the text says
+        let mut late = Node::new("task".to_string(), serde_json::json!("Late call"));
+        late.created_at = "2025-01-02T01:00:00+02:00".to_string();
+        store.store_node(late.clone()).await.unwrap();
+        let late_call = NodeQuery::new().content_contains("late call");
+        assert!(store
+            .find_nodes(late_call.clone().created_after(january_2nd))
+            .await
+            .unwrap()
+            .is_empty());
+        let found = store
+            .find_nodes(late_call.created_before(january_2nd))
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].created_at, "2025-01-01T23:00:00.000000000Z");
+
+        assert_eq!(store.get_nodes_by_root(&root_id).await.unwrap().len(), 4);
+        assert_eq!(
+            store
+                .get_nodes_by_root_and_type(&root_id, "text")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
 
-                    let image_node = ImageNode {
-                        id: node.id.to_string(),
-                        image_data,
-                        embedding,
-                        metadata: ImageMetadata {
-                            filename: metadata
-                                .get("filename")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("unknown")
-                                .to_string(),
-                            mime_type: metadata
-                                .get("mime_type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("image/jpeg")
-                                .to_string(),
-                            width: metadata.get("width").and_then(|v| v.as_u64()).unwrap_or(0)
-                                as u32,
-                            height: metadata.get("height").and_then(|v| v.as_u64()).unwrap_or(0)
-                                as u32,
-                            exif_data: metadata.get("exif_data").cloned(),
-                            description: if let serde_json::Value::String(content) = &node.content {
-                                if content.starts_with("Image:") {
-                                    None
-                                } else {
-                                    Some(content.clone())
-                                }
-                            } else {
-                                None
-                            },
-                        },
-                        created_at: chrono::DateTime::parse_from_rfc3339(&node.created_at)
-                            .map_err(|e| {
-                                DataStoreError::InvalidNode(format!("Invalid timestamp: {}", e))
-                            })?
-                            .with_timezone(&chrono::Utc),
-                    };
+    #[tokio::test]
+    async fn test_pagination_and_streaming_cover_whole_table() {
+        let store = create_test_store().await;
 
-                    return Ok(Some(image_node));
-                }
+        let nodes: Vec<Node> = (0..1100)
+            .map(|i| {
+                let node_type = if i % 2 == 0 { "task" } else { "text" };
+                Node::new(
+                    node_type.to_string(),
+                    serde_json::json!(format!("Row {}", i)),
+                )
+            })
+            .collect();
+        store.store_nodes(nodes).await.unwrap();
+
+        // No silent truncation at 1000 rows
+        assert_eq!(store.query_nodes("row").await.unwrap().len(), 1100);
+
+        let query = NodeQuery::new().node_type("task");
+        let mut seen = std::collections::HashSet::new();
+        let mut last_id = String::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let page = store
+                .find_nodes_page(query.clone(), 200, cursor.as_ref())
+                .await
+                .unwrap();
+            pages += 1;
+            // Unordered pages come back in id order
+            for node in page.nodes {
+                assert!(node.id.to_string() > last_id);
+                last_id = node.id.to_string();
+                assert!(seen.insert(node.id.to_string()));
             }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(seen.len(), 550);
+
+        // Ordered walk: deleting a row already returned doesn't shift later pages
+        let newest_first = NodeQuery::new()
+            .node_type("task")
+            .order_by(NodeOrderField::CreatedAt, SortDirection::Descending);
+        let first = store
+            .find_nodes_page(newest_first.clone(), 200, None)
+            .await
+            .unwrap();
+        store.delete_node(&first.nodes[0].id).await.unwrap();
+        let mut walked: Vec<Node> = first.nodes;
+        let mut cursor = first.next_cursor;
+        while let Some(next) = cursor {
+            let page = store
+                .find_nodes_page(newest_first.clone(), 200, Some(&next))
+                .await
+                .unwrap();
+            walked.extend(page.nodes);
+            cursor = page.next_cursor;
         }
+        let walked_ids: std::collections::HashSet<String> =
+            walked.iter().map(|node| node.id.to_string()).collect();
+        assert_eq!(walked.len(), 550);
+        assert_eq!(walked_ids.len(), 550);
+        assert!(walked
+            .windows(2)
+            .all(|pair| pair[0].created_at >= pair[1].created_at));
+
+        // Cursors are bound to the query that produced them
+        let first = store.find_nodes_page(query, 200, None).await.unwrap();
+        assert!(store
+            .find_nodes_page(NodeQuery::new(), 200, first.next_cursor.as_ref())
+            .await
+            .is_err());
 
-        Ok(None)
-    }
+        let streamed: Vec<Node> = store
+            .stream_nodes(NodeQuery::new().content_contains("ROW 10"))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        // "Row 10" plus "Row 100".."Row 109" and "Row 1000".."Row 1099"
+        assert_eq!(streamed.len(), 111);
 
-    async fn search_multimodal(
-        &self,
-        query_embedding: Vec<f32>,
-        types: Vec<NodeType>,
-    ) -> NodeSpaceResult<Vec<Node>> {
-        // Get all nodes from Arrow storage
-        let universal_nodes = self.query_nodes_arrow("").await?;
-        let mut results = Vec::new();
+        // Ordered streams span several keyset pages
+        let by_id: Vec<Node> = store
+            .stream_nodes(NodeQuery::new().order_by(NodeOrderField::Id, SortDirection::Ascending))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(by_id.len(), 1099);
+        assert!(by_id
+            .windows(2)
+            .all(|pair| pair[0].id.as_str() < pair[1].id.as_str()));
+    }
 
-        // Convert NodeType enum to string filters
-        let type_filters: Vec<String> = types
-            .into_iter()
-            .map(|t| match t {
-                NodeType::Text => "text".to_string(),
-                NodeType::Image => "image".to_string(),
-                NodeType::Date => "date".to_string(),
-                NodeType::Task => "task".to_string(),
-            })
-            .collect();
+    #[tokio::test]
+    async fn test_contextual_and_hierarchical_vector_columns() {
+        let store = create_test_store().await;
 
-        for universal_node in universal_nodes {
-            // Filter by node types
-            if !type_filters.is_empty() && !type_filters.contains(&universal_node.r#type) {
-                continue;
+        let axis = |i: usize| {
+            let mut vector = vec![0.0; 384];
+            vector[i] = 1.0;
+            vector
+        };
+        let embeddings = |contextual: Option<Vec<f32>>, hierarchical: Option<Vec<f32>>| {
+            crate::data_store::MultiLevelEmbeddings {
+                individual: axis(0),
+                contextual,
+                hierarchical,
+                embedding_model: None,
+                generated_at: chrono::Utc::now(),
             }
+        };
 
-            let similarity = cosine_similarity(&query_embedding, &universal_node.vector);
-            if similarity > 0.1 {
-                // Basic similarity threshold
-                let node = self.universal_to_node(universal_node);
-                results.push((node, similarity));
-            }
-        }
+        let near = Node::new("text".to_string(), serde_json::json!("Near"));
+        let far = Node::new("text".to_string(), serde_json::json!("Far"));
+        let plain = Node::new("text".to_string(), serde_json::json!("Plain"));
+        store
+            .store_node_with_multi_embeddings(
+                near.clone(),
+                embeddings(Some(axis(1)), Some(axis(3))),
+            )
+            .await
+            .unwrap();
+        store
+            .store_node_with_multi_embeddings(far.clone(), embeddings(Some(axis(2)), None))
+            .await
+            .unwrap();
+        store
+            .store_node_with_multi_embeddings(plain.clone(), embeddings(None, None))
+            .await
+            .unwrap();
 
-        // Sort by similarity and return just the nodes
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        Ok(results.into_iter().map(|(node, _)| node).collect())
-    }
+        let mut query = axis(1);
+        query[2] = 0.5;
+        let results = store
+            .search_by_contextual_embedding(query, 10)
+            .await
+            .unwrap();
+        let ids: Vec<_> = results.iter().map(|(node, _)| node.id.clone()).collect();
+        assert_eq!(ids, vec![near.id.clone(), far.id.clone()]);
+        assert!(results[0].1 > results[1].1);
 
-    async fn hybrid_multimodal_search(
-        &self,
-        query_embedding: Vec<f32>,
-        config: &HybridSearchConfig,
-    ) -> NodeSpaceResult<Vec<SearchResult>> {
-        // Get all nodes from Arrow storage
-        let universal_nodes = self.query_nodes_arrow("").await?;
-        let mut results = Vec::new();
+        let results = store
+            .search_by_hierarchical_embedding(axis(3), 10)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, near.id);
+
+        // Levels survive a content-preserving update
+        store.update_node(near.clone()).await.unwrap();
+        let stored = store.get_node_embeddings(&near.id).await.unwrap().unwrap();
+        assert_eq!(stored.contextual, Some(axis(1)));
+        assert_eq!(stored.hierarchical, Some(axis(3)));
+
+        // ...and a new individual embedding, by id or with the node
+        store
+            .update_node_embedding(&near.id, axis(4))
+            .await
+            .unwrap();
+        let stored = store.get_node_embeddings(&near.id).await.unwrap().unwrap();
+        assert_eq!(stored.individual, axis(4));
+        assert_eq!(stored.contextual, Some(axis(1)));
+        assert_eq!(stored.hierarchical, Some(axis(3)));
+
+        let node = store.get_node(&near.id).await.unwrap().unwrap();
+        store
+            .update_node_with_embedding(node, axis(5))
+            .await
+            .unwrap();
+        let stored = store.get_node_embeddings(&near.id).await.unwrap().unwrap();
+        assert_eq!(stored.individual, axis(5));
+        assert_eq!(stored.contextual, Some(axis(1)));
+        let results = store
+            .search_by_hierarchical_embedding(axis(3), 10)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.id, near.id);
+
+        // Children stored or updated with embeddings join their parent's root
+        let mut child = Node::new("text".to_string(), serde_json::json!("Child"));
+        child.parent_id = Some(near.id.clone());
+        store
+            .store_node_with_multi_embeddings(child.clone(), embeddings(None, None))
+            .await
+            .unwrap();
+        let stored = store.get_node(&child.id).await.unwrap().unwrap();
+        assert_eq!(stored.root_id, Some(near.id.clone()));
+        store
+            .update_node_embeddings(&child.id, embeddings(Some(axis(1)), None))
+            .await
+            .unwrap();
+        store
+            .update_node_embedding(&child.id, axis(2))
+            .await
+            .unwrap();
+        let stored = store.get_node(&child.id).await.unwrap().unwrap();
+        assert_eq!(stored.root_id, Some(near.id.clone()));
+        assert_eq!(stored.parent_id, Some(near.id.clone()));
+    }
 
-        for universal_node in universal_nodes {
-            let semantic_score = cosine_similarity(&query_embedding, &universal_node.vector);
+    #[tokio::test]
+    async fn test_legacy_timestamps_normalized_on_open() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("timestamps.db");
+        let db_path = db_path.to_str().unwrap();
 
-            // Skip if below minimum threshold
-            if semantic_score < config.min_similarity_threshold as f32 {
-                continue;
-            }
+        // A row as written before timestamps were normalized
+        let store = LanceDataStore::new(db_path).await.unwrap();
+        let node = Node::new("text".to_string(), serde_json::json!("Legacy"));
+        store.store_node(node.clone()).await.unwrap();
+        let table = store.table.read().await.clone().unwrap();
+        table
+            .update()
+            .only_if(format!("id = {}", sql_string_literal(node.id.as_str())))
+            .column("created_at", "'2025-01-02T01:00:00+02:00'")
+            .execute()
+            .await
+            .unwrap();
+        let updated_at = store
+            .get_universal_node_arrow(&node.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .updated_at;
+        drop(store);
+
+        let store = LanceDataStore::new(db_path).await.unwrap();
+        let row = store
+            .get_universal_node_arrow(&node.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.created_at, "2025-01-01T23:00:00.000000000Z");
+        assert_eq!(row.updated_at, updated_at);
+    }
 
-            // Calculate structural score (based on relationships)
-            let structural_score =
-                if universal_node.parent_id.is_some() || !universal_node.children_ids.is_empty() {
-                    0.8 // Has relationships
-                } else {
-                    0.2 // Isolated node
-                };
+    #[tokio::test]
+    async fn test_distance_metric_scores() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("metric_test.db");
 
-            // Calculate temporal score (recent nodes get higher scores)
-            let temporal_score = if let Ok(created_at) =
-                chrono::DateTime::parse_from_rfc3339(&universal_node.created_at)
-            {
-                let age_days =
-                    (chrono::Utc::now() - created_at.with_timezone(&chrono::Utc)).num_days();
-                if age_days <= 1 {
-                    1.0
-                } else if age_days <= 7 {
-                    0.8
-                } else {
-                    0.5
-                }
-            } else {
-                0.5
-            };
+        let mut unnormalized = vec![0.0; 384];
+        unnormalized[0] = 3.0;
+        let mut orthogonal = vec![0.0; 384];
+        orthogonal[1] = 1.0;
+        let mut query = vec![0.0; 384];
+        query[0] = 1.0;
 
-            // Cross-modal bonus for image-text combinations
-            let cross_modal_score =
-                if config.enable_cross_modal && universal_node.r#type == "image" {
-                    Some(0.9) // Boost for cross-modal queries
-                } else {
-                    None
-                };
+        // Cosine ignores magnitude: a scaled copy of the query is a perfect match
+        let cosine_store = LanceDataStore::new(db_path.join("cosine").to_str().unwrap())
+            .await
+            .unwrap();
+        let target = Node::new("text".to_string(), serde_json::json!("Target"));
+        let other = Node::new("text".to_string(), serde_json::json!("Other"));
+        cosine_store
+            .store_node_with_embedding(target.clone(), unnormalized.clone())
+            .await
+            .unwrap();
+        cosine_store
+            .store_node_with_embedding(other.clone(), orthogonal.clone())
+            .await
+            .unwrap();
 
-            // Weighted final score
-            let final_score = (semantic_score * config.semantic_weight as f32)
-                + (structural_score * config.structural_weight as f32)
-                + (temporal_score * config.temporal_weight as f32)
-                + cross_modal_score.unwrap_or(0.0) * 0.1;
+        let results = cosine_store
+            .search_similar_nodes(query.clone(), 2)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.id, target.id);
+        assert!((results[0].1 - 1.0).abs() < 1e-5);
+        assert!(results[1].1.abs() < 1e-5);
+
+        // L2 uses magnitude: distance 4.0 to the target, 2.0 to the orthogonal vector
+        let config = LanceDataStoreConfig {
+            distance_metric: DistanceMetric::L2,
+            ..LanceDataStoreConfig::default()
+        };
+        let l2_store = LanceDataStore::with_config(db_path.join("l2").to_str().unwrap(), config)
+            .await
+            .unwrap();
+        l2_store
+            .store_node_with_embedding(target.clone(), unnormalized)
+            .await
+            .unwrap();
+        l2_store
+            .store_node_with_embedding(other.clone(), orthogonal)
+            .await
+            .unwrap();
 
-            let node = self.universal_to_node(universal_node);
-            let search_result = SearchResult {
-                node,
-                score: final_score,
-                relevance_factors: RelevanceFactors {
-                    semantic_score,
-                    structural_score,
-                    temporal_score,
-                    cross_modal_score,
+        let results = l2_store
+            .search_similar_nodes(query.clone(), 2)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.id, other.id);
+        assert!((results[0].1 - 1.0 / 3.0).abs() < 1e-5);
+        assert!((results[1].1 - 1.0 / 5.0).abs() < 1e-5);
+
+        // Far-apart vectors score below 0.1 under L2 and are still returned
+        let mut distant = vec![0.0; 384];
+        distant[0] = 5.0;
+        let far = Node::new("text".to_string(), serde_json::json!("Far"));
+        l2_store
+            .store_node_with_multi_embeddings(
+                far.clone(),
+                crate::data_store::MultiLevelEmbeddings {
+                    individual: distant.clone(),
+                    contextual: Some(distant),
+                    hierarchical: None,
+                    embedding_model: None,
+                    generated_at: chrono::Utc::now(),
                 },
-            };
+            )
+            .await
+            .unwrap();
+        let results = l2_store
+            .search_by_contextual_embedding(query, 2)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, far.id);
+        assert!((results[0].1 - 1.0 / 17.0).abs() < 1e-5);
+    }
 
-            results.push(search_result);
-        }
+    #[tokio::test]
+    async fn test_vector_index_lifecycle() {
+        use rand::Rng;
 
-        // Sort by final score and apply limits
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-        results.truncate(config.max_results);
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("index_test.db");
+        let config = LanceDataStoreConfig {
+            vector_dimension: 32,
+            vector_index_policy: Some(VectorIndexPolicy {
+                min_rows: 512,
+                optimize_after_rows: 100,
+                ..VectorIndexPolicy::default()
+            }),
+            ..LanceDataStoreConfig::default()
+        };
+        let store = LanceDataStore::with_config(db_path.to_str().unwrap(), config)
+            .await
+            .unwrap();
+
+        let mut rng = rand::thread_rng();
+        let mut random_rows = |count: usize| -> Vec<UniversalNode> {
+            (0..count)
+                .map(|i| {
+                    let node =
+                        Node::new("text".to_string(), serde_json::json!(format!("Row {}", i)));
+                    let embedding = (0..32).map(|_| rng.gen_range(-1.0..1.0)).collect();
+                    store.node_to_universal(node, Some(embedding))
+                })
+                .collect()
+        };
 
-        Ok(results)
+        // Writes apply the policy in the background; maintain_vector_indexes
+        // waits for that run before checking the policy again
+        store.store_nodes_arrow(random_rows(300)).await.unwrap();
+        store.maintain_vector_indexes().await.unwrap();
+        let vector_index = |indexes: Vec<IndexInfo>| {
+            indexes
+                .into_iter()
+                .find(|index| index.columns == vec!["vector".to_string()])
+        };
+        assert!(vector_index(store.list_indexes().await.unwrap()).is_none());
+
+        // Crossing the threshold builds the index over every row
+        store.store_nodes_arrow(random_rows(300)).await.unwrap();
+        store.maintain_vector_indexes().await.unwrap();
+        let index = vector_index(store.list_indexes().await.unwrap()).unwrap();
+        let stats = store.index_stats(&index.name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 600);
+
+        // Further growth is folded in by optimize rather than a retrain
+        store.store_nodes_arrow(random_rows(150)).await.unwrap();
+        store.maintain_vector_indexes().await.unwrap();
+        let stats = store.index_stats(&index.name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 750);
+        assert_eq!(stats.num_unindexed_rows, 0);
+        assert_eq!(store.index_maintenance_status().await.vector_error, None);
+
+        let err = store
+            .create_vector_index("content", IndexParams::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DataStoreError::VectorIndexCreation(_)));
+
+        store.drop_index(&index.name).await.unwrap();
+        assert!(vector_index(store.list_indexes().await.unwrap()).is_none());
     }
 
-    // Multi-level embedding methods
-    async fn store_node_with_multi_embeddings(
-        &self,
-        node: Node,
-        embeddings: crate::data_store::MultiLevelEmbeddings,
-    ) -> NodeSpaceResult<NodeId> {
-        let universal = self.node_to_universal_with_multi_embeddings(node.clone(), embeddings);
+    #[tokio::test]
+    async fn test_filtered_vector_search() {
+        let store = create_test_store().await;
 
-        // Store using Arrow persistence
-        self.store_node_arrow(universal).await?;
+        let vector = |x: f32, y: f32| {
+            let mut vector = vec![0.0; 384];
+            vector[0] = x;
+            vector[1] = y;
+            vector
+        };
+        for i in 0..3 {
+            let note = Node::new("text".to_string(), serde_json::json!(format!("Note {}", i)));
+            store
+                .store_node_with_embedding(note, vector(1.0, 0.1 * i as f32))
+                .await
+                .unwrap();
+        }
+        let open_task = Node::new("task".to_string(), serde_json::json!("Open task"))
+            .with_metadata(serde_json::json!({"status": "open"}));
+        let done_task = Node::new("task".to_string(), serde_json::json!("Done task"))
+            .with_metadata(serde_json::json!({"status": "done"}));
+        store
+            .store_node_with_embedding(open_task.clone(), vector(0.2, 1.0))
+            .await
+            .unwrap();
+        store
+            .store_node_with_embedding(done_task.clone(), vector(0.5, 1.0))
+            .await
+            .unwrap();
 
-        Ok(node.id)
+        let query = vector(1.0, 0.0);
+        let tasks = NodeQuery::new().node_type("task");
+
+        // Pre-filtering searches only tasks, so the nearest task is found
+        let results = store
+            .filtered_vector_search(query.clone(), tasks.clone(), 1, FilterMode::PreFilter)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, done_task.id);
+        assert!((results[0].1 - 0.5 / 1.25f32.sqrt()).abs() < 1e-5);
+
+        // Post-filtering takes the nearest row first, which is a note
+        let results = store
+            .filtered_vector_search(query.clone(), tasks, 1, FilterMode::PostFilter)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+
+        // `_` is a LIKE wildcard, so every nearer "Code a1b" passes the pushed
+        // down filter and fails the exact check; pre-filtering keeps widening
+        // the search until the one real match turns up
+        let lookalikes = (0..9).map(|_| {
+            let node = Node::new("text".to_string(), serde_json::json!("Code a1b"));
+            store.node_to_universal(node, Some(vector(1.0, 0.0)))
+        });
+        let exact = Node::new("text".to_string(), serde_json::json!("Code a_b"));
+        let mut rows: Vec<UniversalNode> = lookalikes.collect();
+        rows.push(store.node_to_universal(exact.clone(), Some(vector(0.0, 1.0))));
+        store.store_nodes_arrow(rows).await.unwrap();
+
+        let results = store
+            .filtered_vector_search(
+                query.clone(),
+                NodeQuery::new().content_contains("a_b"),
+                1,
+                FilterMode::PreFilter,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, exact.id);
+
+        let results = store
+            .hybrid_search(
+                query,
+                Some("task".to_string()),
+                Some(serde_json::json!({"status": "open"})),
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.id, open_task.id);
+        assert!((results[0].1 - 0.2 / 1.04f32.sqrt()).abs() < 1e-5);
     }
 
-    async fn update_node_embeddings(
-        &self,
-        node_id: &NodeId,
-        embeddings: crate::data_store::MultiLevelEmbeddings,
-    ) -> NodeSpaceResult<()> {
-        // Get the existing node
-        if let Some(node) = self.get_node(node_id).await? {
-            // Convert with new embeddings
-            let universal = self.node_to_universal_with_multi_embeddings(node, embeddings);
+    #[tokio::test]
+    async fn test_structural_score_from_anchor_hops() {
+        let store = create_test_store().await;
 
-            // Use atomic delete + insert for update
-            self.delete_node_by_exact_id(node_id).await?;
-            self.store_node_arrow(universal).await?;
+        let root = Node::new("text".to_string(), serde_json::json!("Document"));
+        let child = |content: &str, parent: &Node| {
+            let mut node = Node::new("text".to_string(), serde_json::json!(content));
+            node.parent_id = Some(parent.id.clone());
+            node.root_id = Some(root.id.clone());
+            node
+        };
+        let anchor = child("Anchor section", &root);
+        let sibling = child("Sibling section", &root);
+        let nested = child("Nested paragraph", &anchor);
+        let unrelated = Node::new("text".to_string(), serde_json::json!("Other document"));
+        store
+            .store_nodes(vec![
+                root.clone(),
+                anchor.clone(),
+                sibling.clone(),
+                nested.clone(),
+                unrelated.clone(),
+            ])
+            .await
+            .unwrap();
+
+        let mut config = HybridSearchConfig {
+            semantic_weight: 0.0,
+            structural_weight: 1.0,
+            temporal_weight: 0.0,
+            min_similarity_threshold: -1.0,
+            enable_cross_modal: false,
+            anchor_node_ids: vec![anchor.id.clone()],
+            max_structural_hops: 2,
+            ..HybridSearchConfig::default()
+        };
+        let structural_scores = |results: Vec<SearchResult>| -> HashMap<NodeId, f32> {
+            results
+                .into_iter()
+                .map(|r| (r.node.id, r.relevance_factors.structural_score))
+                .collect()
+        };
 
-            Ok(())
-        } else {
-            Err(DataStoreError::NodeNotFound(format!("Node {} not found", node_id)).into())
-        }
+        let scores = structural_scores(
+            store
+                .hybrid_multimodal_search(vec![0.0; 384], &config)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(scores[&anchor.id], 1.0);
+        assert_eq!(scores[&root.id], 0.5);
+        assert_eq!(scores[&nested.id], 0.5);
+        assert!((scores[&sibling.id] - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(scores[&unrelated.id], 0.0);
+
+        // Nodes past the hop limit get no structural credit
+        config.max_structural_hops = 1;
+        let scores = structural_scores(
+            store
+                .hybrid_multimodal_search(vec![0.0; 384], &config)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(scores[&nested.id], 0.5);
+        assert_eq!(scores[&sibling.id], 0.0);
     }
 
-    async fn get_node_embeddings(
-        &self,
-        node_id: &NodeId,
-    ) -> NodeSpaceResult<Option<crate::data_store::MultiLevelEmbeddings>> {
-        // Get the node from Arrow storage
-        let universal_nodes = self.query_nodes_arrow("").await?;
+    #[tokio::test]
+    async fn test_ordered_children_insert_and_move() {
+        let store = create_test_store().await;
+        let text = |content: &str| Node::new("text".to_string(), serde_json::json!(content));
+        let ids = |nodes: Vec<Node>| nodes.into_iter().map(|node| node.id).collect::<Vec<_>>();
 
-        for universal_node in universal_nodes {
-            if universal_node.id == node_id.to_string() {
-                let embeddings = crate::data_store::MultiLevelEmbeddings {
-                    individual: universal_node.individual_vector,
-                    contextual: universal_node.contextual_vector,
-                    hierarchical: universal_node.hierarchical_vector,
-                    embedding_model: universal_node.embedding_model,
-                    generated_at: if let Some(timestamp_str) =
-                        universal_node.embeddings_generated_at
-                    {
-                        chrono::DateTime::parse_from_rfc3339(&timestamp_str)
-                            .map(|dt| dt.with_timezone(&chrono::Utc))
-                            .unwrap_or_else(|_| chrono::Utc::now())
-                    } else {
-                        chrono::Utc::now()
-                    },
-                };
-                return Ok(Some(embeddings));
-            }
-        }
+        let parent = text("Parent");
+        let other = text("Other parent");
+        store.store_node(parent.clone()).await.unwrap();
+        store.store_node(other.clone()).await.unwrap();
 
-        Ok(None)
+        let (a, b, c) = (text("A"), text("B"), text("C"));
+        store
+            .insert_child_at(&parent.id, b.clone(), 0)
+            .await
+            .unwrap();
+        store
+            .insert_child_at(&parent.id, a.clone(), 0)
+            .await
+            .unwrap();
+        store
+            .insert_child_at(&parent.id, c.clone(), 99)
+            .await
+            .unwrap();
+        assert_eq!(
+            ids(store.get_ordered_children(&parent.id).await.unwrap()),
+            vec![a.id.clone(), b.id.clone(), c.id.clone()]
+        );
+        assert!(store
+            .insert_child_at(&parent.id, a.clone(), 1)
+            .await
+            .is_err());
+
+        store.move_node_before(&c.id, &a.id).await.unwrap();
+        store.move_node_after(&a.id, &b.id).await.unwrap();
+        assert_eq!(
+            ids(store.get_ordered_children(&parent.id).await.unwrap()),
+            vec![c.id.clone(), b.id.clone(), a.id.clone()]
+        );
+
+        // Moving under another parent relinks both lists
+        let d = text("D");
+        store
+            .insert_child_at(&other.id, d.clone(), 0)
+            .await
+            .unwrap();
+        store.move_node_after(&b.id, &d.id).await.unwrap();
+        assert_eq!(
+            ids(store.get_ordered_children(&parent.id).await.unwrap()),
+            vec![c.id.clone(), a.id.clone()]
+        );
+        assert_eq!(
+            ids(store.get_ordered_children(&other.id).await.unwrap()),
+            vec![d.id.clone(), b.id.clone()]
+        );
+        assert!(store.move_node_before(&parent.id, &a.id).await.is_err());
+
+        // A link to a deleted sibling is reported instead of silently reordered
+        store.delete_node(&d.id).await.unwrap();
+        assert!(store.get_ordered_children(&other.id).await.is_err());
     }
 
-    async fn search_by_individual_embedding(
-        &self,
-        embedding: Vec<f32>,
-        limit: usize,
-    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
-        // Use individual_vector field for search
-        let universal_nodes = self.query_nodes_arrow("").await?;
-        let mut results = Vec::new();
-
-        for universal_node in universal_nodes {
-            let similarity = cosine_similarity(&embedding, &universal_node.individual_vector);
-            if similarity > 0.1 {
-                let node = self.universal_to_node(universal_node);
-                results.push((node, similarity));
-            }
+    #[tokio::test]
+    async fn test_concurrent_sibling_inserts_keep_one_chain() {
+        let store = create_test_store().await;
+        let parent = Node::new("text".to_string(), serde_json::json!("Parent"));
+        store.store_node(parent.clone()).await.unwrap();
+
+        // Every insert appends after whatever child is last when it runs
+        let children: Vec<Node> = (0..8)
+            .map(|i| {
+                Node::new(
+                    "text".to_string(),
+                    serde_json::json!(format!("Child {}", i)),
+                )
+            })
+            .collect();
+        let inserts = children
+            .iter()
+            .map(|child| store.insert_child_at(&parent.id, child.clone(), usize::MAX));
+        for result in future::join_all(inserts).await {
+            result.unwrap();
         }
 
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results.truncate(limit);
-        Ok(results)
+        let ordered = store.get_ordered_children(&parent.id).await.unwrap();
+        assert_eq!(ordered.len(), children.len());
+        // Trees built through insert_child_at keep children_ids in step
+        assert!(store.check_integrity().await.unwrap().is_clean());
     }
 
-    async fn search_by_contextual_embedding(
-        &self,
-        embedding: Vec<f32>,
-        limit: usize,
-    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
-        // Use contextual_vector field for search
-        let universal_nodes = self.query_nodes_arrow("").await?;
-        let mut results = Vec::new();
-
-        for universal_node in universal_nodes {
-            if let Some(ref contextual_vector) = universal_node.contextual_vector {
-                let similarity = cosine_similarity(&embedding, contextual_vector);
-                if similarity > 0.1 {
-                    let node = self.universal_to_node(universal_node);
-                    results.push((node, similarity));
-                }
-            }
+    #[tokio::test]
+    async fn test_subtree_operations() {
+        let store = create_test_store().await;
+        let text = |content: &str| Node::new("text".to_string(), serde_json::json!(content));
+
+        // doc > [section > [point], tail]; archive is a second document
+        let (doc, archive) = (text("Doc"), text("Archive"));
+        store.store_node(doc.clone()).await.unwrap();
+        store.store_node(archive.clone()).await.unwrap();
+        let (section, tail, point) = (text("Section"), text("Tail"), text("Point"));
+        for (parent, child) in [(&doc, &section), (&doc, &tail), (&section, &point)] {
+            let mut child = child.clone();
+            child.root_id = Some(doc.id.clone());
+            store.insert_child_at(&parent.id, child, 99).await.unwrap();
         }
 
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results.truncate(limit);
-        Ok(results)
+        let copy_id = store.copy_subtree(&section.id, &archive.id).await.unwrap();
+        let copied = store.get_ordered_children(&copy_id).await.unwrap();
+        assert_eq!(copied.len(), 1);
+        assert_ne!(copied[0].id, point.id);
+        assert_eq!(copied[0].root_id, Some(archive.id.clone()));
+        let archive_row = store
+            .get_universal_node_arrow(&archive.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(archive_row.children_ids, vec![copy_id.to_string()]);
+
+        // Moving the section re-roots its child and closes the gap it leaves
+        store
+            .move_subtree(&section.id, &archive.id, 0)
+            .await
+            .unwrap();
+        let moved_point = store.get_node(&point.id).await.unwrap().unwrap();
+        assert_eq!(moved_point.root_id, Some(archive.id.clone()));
+        let doc_children = store.get_ordered_children(&doc.id).await.unwrap();
+        assert_eq!(doc_children.len(), 1);
+        assert_eq!(doc_children[0].id, tail.id);
+        assert_eq!(doc_children[0].before_sibling, None);
+        let archive_children: Vec<NodeId> = store
+            .get_ordered_children(&archive.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|node| node.id)
+            .collect();
+        assert_eq!(archive_children, vec![section.id.clone(), copy_id.clone()]);
+        assert!(store.move_subtree(&archive.id, &point.id, 0).await.is_err());
+
+        let deleted = store.delete_subtree(&section.id).await.unwrap();
+        assert_eq!(deleted, vec![section.id.clone(), point.id.clone()]);
+        assert!(store.get_node(&point.id).await.unwrap().is_none());
+        let remaining = store.get_ordered_children(&archive.id).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].before_sibling, None);
+        let archive_row = store
+            .get_universal_node_arrow(&archive.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(archive_row.children_ids, vec![copy_id.to_string()]);
     }
 
-    async fn search_by_hierarchical_embedding(
-        &self,
-        embedding: Vec<f32>,
-        limit: usize,
-    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
-        // Use hierarchical_vector field for search
-        let universal_nodes = self.query_nodes_arrow("").await?;
-        let mut results = Vec::new();
+    #[tokio::test]
+    async fn test_root_id_follows_parent_chain() {
+        let temp_dir = tempdir().unwrap();
+        let config = LanceDataStoreConfig {
+            hierarchy_validation: HierarchyValidation::Strict,
+            ..LanceDataStoreConfig::default()
+        };
+        let store =
+            LanceDataStore::with_config(temp_dir.path().join("strict").to_str().unwrap(), config)
+                .await
+                .unwrap();
+        let text = |content: &str| Node::new("text".to_string(), serde_json::json!(content));
+        let child_of = |content: &str, parent: &Node| {
+            let mut node = text(content);
+            node.parent_id = Some(parent.id.clone());
+            node
+        };
 
-        for universal_node in universal_nodes {
-            if let Some(ref hierarchical_vector) = universal_node.hierarchical_vector {
-                let similarity = cosine_similarity(&embedding, hierarchical_vector);
-                if similarity > 0.1 {
-                    let node = self.universal_to_node(universal_node);
-                    results.push((node, similarity));
-                }
-            }
+        let (doc, archive) = (text("Doc"), text("Archive"));
+        let mut section = child_of("Section", &doc);
+        // A stale root_id from the caller is replaced by the parent's
+        section.root_id = Some(NodeId::new());
+        let point = child_of("Point", &section);
+
+        // Strict mode won't store a child before its parent
+        assert!(store.store_node(section.clone()).await.is_err());
+        assert!(store.get_node(&section.id).await.unwrap().is_none());
+        for node in [&doc, &archive, &section, &point] {
+            store.store_node(node.clone()).await.unwrap();
+        }
+        let root_of = |node: Option<Node>| node.unwrap().root_id;
+        assert_eq!(root_of(store.get_node(&doc.id).await.unwrap()), None);
+        for node in [&section, &point] {
+            assert_eq!(
+                root_of(store.get_node(&node.id).await.unwrap()),
+                Some(doc.id.clone())
+            );
         }
+        assert_eq!(store.get_nodes_by_root(&doc.id).await.unwrap().len(), 2);
 
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results.truncate(limit);
-        Ok(results)
+        // Doc can't move under its own grandchild
+        let mut looped = store.node_to_universal(child_of("Doc", &point), None);
+        looped.id = doc.id.to_string();
+        let cycle: Vec<String> = [&doc, &point, &section, &doc]
+            .iter()
+            .map(|node| node.id.to_string())
+            .collect();
+        assert!(matches!(
+            store.resolve_root_id(&mut looped).await,
+            Err(DataStoreError::ParentCycle { node_ids }) if node_ids == cycle
+        ));
+        let mut doc_under_point = doc.clone();
+        doc_under_point.parent_id = Some(point.id.clone());
+        assert!(store.update_node(doc_under_point).await.is_err());
+
+        // Re-parenting a section carries its descendants to the new root
+        let mut moved = section.clone();
+        moved.parent_id = Some(archive.id.clone());
+        store.update_node(moved).await.unwrap();
+        assert_eq!(
+            root_of(store.get_node(&point.id).await.unwrap()),
+            Some(archive.id.clone())
+        );
+        assert!(store.get_nodes_by_root(&doc.id).await.unwrap().is_empty());
+
+        // Detaching it through a fetch-modify-update makes it a root of its own
+        let mut detached = store.get_node(&section.id).await.unwrap().unwrap();
+        detached.parent_id = None;
+        store.update_node(detached).await.unwrap();
+        assert_eq!(root_of(store.get_node(&section.id).await.unwrap()), None);
+        assert_eq!(
+            root_of(store.get_node(&point.id).await.unwrap()),
+            Some(section.id.clone())
+        );
+        assert!(store
+            .get_nodes_by_root(&archive.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Lenient mode stores orphans as given
+        let lenient = create_test_store().await;
+        let orphan_root = NodeId::new();
+        let mut orphan = child_of("Orphan", &doc);
+        orphan.root_id = Some(orphan_root.clone());
+        lenient.store_node(orphan.clone()).await.unwrap();
+        assert_eq!(
+            root_of(lenient.get_node(&orphan.id).await.unwrap()),
+            Some(orphan_root)
+        );
     }
 
-    async fn hybrid_semantic_search(
-        &self,
-        embeddings: crate::data_store::QueryEmbeddings,
-        config: crate::data_store::HybridSearchConfig,
-    ) -> NodeSpaceResult<Vec<crate::data_store::SearchResult>> {
-        let universal_nodes = self.query_nodes_arrow("").await?;
-        let mut results = Vec::new();
+    #[tokio::test]
+    async fn test_batch_writes_resolve_root_ids() {
+        let temp_dir = tempdir().unwrap();
+        let config = LanceDataStoreConfig {
+            hierarchy_validation: HierarchyValidation::Strict,
+            ..LanceDataStoreConfig::default()
+        };
+        let store =
+            LanceDataStore::with_config(temp_dir.path().join("strict").to_str().unwrap(), config)
+                .await
+                .unwrap();
+        let text = |content: &str| Node::new("text".to_string(), serde_json::json!(content));
+        let child_of = |content: &str, parent: &Node| {
+            let mut node = text(content);
+            node.parent_id = Some(parent.id.clone());
+            node.root_id = Some(NodeId::new());
+            node
+        };
 
-        for universal_node in universal_nodes {
-            // Calculate individual embedding similarity
-            let individual_score =
-                cosine_similarity(&embeddings.individual, &universal_node.individual_vector);
+        let archive = text("Archive");
+        store.store_node(archive.clone()).await.unwrap();
+        // Children ahead of their parents, with stale root_ids
+        let doc = text("Doc");
+        let section = child_of("Section", &doc);
+        let point = child_of("Point", &section);
+        let filed = child_of("Filed", &archive);
+        store
+            .store_nodes(vec![
+                point.clone(),
+                section.clone(),
+                doc.clone(),
+                filed.clone(),
+            ])
+            .await
+            .unwrap();
+        let root_of = |nodes: Vec<Option<Node>>| -> Vec<Option<NodeId>> {
+            nodes
+                .into_iter()
+                .map(|node| node.unwrap().root_id)
+                .collect()
+        };
+        let ids = [
+            doc.id.clone(),
+            section.id.clone(),
+            point.id.clone(),
+            filed.id.clone(),
+        ];
+        assert_eq!(
+            root_of(store.get_nodes(&ids).await.unwrap()),
+            vec![
+                None,
+                Some(doc.id.clone()),
+                Some(doc.id.clone()),
+                Some(archive.id.clone())
+            ]
+        );
+
+        // Strict mode and cycle checks hold for the whole batch before anything is written
+        let stray = child_of("Stray", &text("Missing"));
+        assert!(store.store_nodes(vec![text("Fine"), stray]).await.is_err());
+        let mut looped_doc = doc.clone();
+        looped_doc.parent_id = Some(point.id.clone());
+        assert!(store.update_nodes(vec![looped_doc]).await.is_err());
+
+        // Moving the section in a batch re-roots the point stored under it
+        let mut moved = section.clone();
+        moved.parent_id = Some(archive.id.clone());
+        store
+            .update_nodes(vec![moved, filed.clone()])
+            .await
+            .unwrap();
+        assert_eq!(
+            root_of(store.get_nodes(&ids).await.unwrap()),
+            vec![
+                None,
+                Some(archive.id.clone()),
+                Some(archive.id.clone()),
+                Some(archive.id.clone())
+            ]
+        );
+    }
 
-            // Calculate contextual embedding similarity if available
-            let contextual_score = if let (Some(ref query_contextual), Some(ref node_contextual)) =
-                (&embeddings.contextual, &universal_node.contextual_vector)
-            {
-                cosine_similarity(query_contextual, node_contextual)
-            } else {
-                0.0
-            };
+    #[tokio::test]
+    async fn test_check_integrity_and_repair() {
+        let store = create_test_store().await;
+        let text = |content: &str| Node::new("text".to_string(), serde_json::json!(content));
+        let child_of = |content: &str, parent: &NodeId| {
+            let mut node = text(content);
+            node.parent_id = Some(parent.clone());
+            node
+        };
 
-            // Calculate hierarchical embedding similarity if available
-            let hierarchical_score =
-                if let (Some(ref query_hierarchical), Some(ref node_hierarchical)) = (
-                    &embeddings.hierarchical,
-                    &universal_node.hierarchical_vector,
-                ) {
-                    cosine_similarity(query_hierarchical, node_hierarchical)
-                } else {
-                    0.0
-                };
+        // Raw rows as an old import left them: two first children nobody
+        // lists, a stale root_id, and an orphan with a child of its own
+        let doc = text("Doc");
+        let first = child_of("First", &doc.id);
+        let mut second = child_of("Second", &doc.id);
+        second.root_id = Some(NodeId::new());
+        let orphan = child_of("Orphan", &NodeId::new());
+        let leaf = child_of("Leaf", &orphan.id);
+        let rows = [&doc, &first, &second, &orphan, &leaf]
+            .into_iter()
+            .map(|node| store.node_to_universal(node.clone(), None))
+            .collect();
+        store.store_nodes_arrow(rows).await.unwrap();
+
+        let report = store.check_integrity().await.unwrap();
+        assert_eq!(report.nodes_scanned, 5);
+        assert!(!report.is_clean());
+        assert!(report.issues.contains(&IntegrityIssue::OrphanedNode {
+            node_id: orphan.id.to_string(),
+            parent_id: orphan.parent_id.clone().unwrap().to_string(),
+        }));
+
+        let repaired = store
+            .repair(RepairPolicy {
+                orphans: OrphanRepair::Delete,
+            })
+            .await
+            .unwrap();
+        assert_eq!(repaired.issues, report.issues);
+        assert_eq!(repaired.deleted, vec![orphan.id.clone(), leaf.id.clone()]);
+        assert!(store.check_integrity().await.unwrap().is_clean());
+
+        let children = store.get_ordered_children(&doc.id).await.unwrap();
+        let ids: Vec<NodeId> = children.iter().map(|node| node.id.clone()).collect();
+        assert_eq!(ids, vec![first.id.clone(), second.id.clone()]);
+        assert!(children
+            .iter()
+            .all(|node| node.root_id == Some(doc.id.clone())));
+        let doc_row = store
+            .get_universal_node_arrow(&doc.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            doc_row.children_ids,
+            vec![first.id.to_string(), second.id.to_string()]
+        );
+        assert!(store.get_node(&leaf.id).await.unwrap().is_none());
+
+        // Nothing left to fix
+        assert_eq!(
+            store.repair(RepairPolicy::default()).await.unwrap(),
+            RepairReport::default()
+        );
+    }
 
-            // Calculate weighted final score
-            let final_score = (individual_score * config.individual_weight as f32)
-                + (contextual_score * config.contextual_weight as f32)
-                + (hierarchical_score * config.hierarchical_weight as f32);
+    #[tokio::test]
+    async fn test_update_keeps_hierarchy_links() {
+        let store = create_test_store().await;
+        let parent = Node::new("text".to_string(), serde_json::json!("Parent"));
+        store.store_node(parent.clone()).await.unwrap();
+        for content in ["First", "Second"] {
+            let child = Node::new("text".to_string(), serde_json::json!(content));
+            store
+                .insert_child_at(&parent.id, child, usize::MAX)
+                .await
+                .unwrap();
+        }
 
-            // Skip if below minimum threshold
-            if final_score < config.min_similarity_threshold as f32 {
-                continue;
-            }
+        // A plain edit of each node as get_node returned it, no metadata
+        let mut edited = store.get_node(&parent.id).await.unwrap().unwrap();
+        edited.content = serde_json::json!("Parent, renamed");
+        store.update_node(edited).await.unwrap();
+        let children = store.get_ordered_children(&parent.id).await.unwrap();
+        let mut second = children[1].clone();
+        second.before_sibling = None;
+        store.update_node(second).await.unwrap();
+
+        assert!(store.check_integrity().await.unwrap().is_clean());
+        let parent_row = store
+            .get_universal_node_arrow(&parent.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let ids: Vec<String> = children.iter().map(|node| node.id.to_string()).collect();
+        assert_eq!(parent_row.children_ids, ids);
+    }
+
+    #[tokio::test]
+    async fn test_hierarchy_relationship_moves_child() {
+        let store = create_test_store().await;
+        let text = |content: &str| Node::new("text".to_string(), serde_json::json!(content));
+        let (old_parent, new_parent, existing, moved) =
+            (text("Old"), text("New"), text("Existing"), text("Moved"));
+        store.store_node(old_parent.clone()).await.unwrap();
+        store.store_node(new_parent.clone()).await.unwrap();
+        store
+            .insert_child_at(&new_parent.id, existing.clone(), 0)
+            .await
+            .unwrap();
+        store
+            .insert_child_at(&old_parent.id, moved.clone(), 0)
+            .await
+            .unwrap();
 
-            let node = self.universal_to_node(universal_node);
-            let search_result = crate::data_store::SearchResult {
-                node,
-                score: final_score,
-                relevance_factors: crate::data_store::RelevanceFactors {
-                    semantic_score: individual_score,
-                    structural_score: contextual_score,
-                    temporal_score: hierarchical_score,
-                    cross_modal_score: None,
-                },
-            };
+        store
+            .create_relationship(&new_parent.id, &moved.id, "contains")
+            .await
+            .unwrap();
+        let children = store.get_ordered_children(&new_parent.id).await.unwrap();
+        let ids: Vec<NodeId> = children.iter().map(|node| node.id.clone()).collect();
+        assert_eq!(ids, vec![existing.id.clone(), moved.id.clone()]);
+        assert_eq!(children[1].parent_id, Some(new_parent.id.clone()));
+        assert_eq!(children[1].root_id, Some(new_parent.id.clone()));
+        assert!(store
+            .get_ordered_children(&old_parent.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store.check_integrity().await.unwrap().is_clean());
 
-            results.push(search_result);
+        // Cycles and missing nodes are refused
+        assert!(store
+            .create_relationship(&moved.id, &new_parent.id, "parent")
+            .await
+            .is_err());
+        assert!(store
+            .create_relationship(&new_parent.id, &NodeId::new(), "child")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_typed_relationships() {
+        let store = create_test_store().await;
+        let (task, customer, blocker) = (
+            Node::new("task".to_string(), serde_json::json!("Ship release")),
+            Node::new("customer".to_string(), serde_json::json!("Acme")),
+            Node::new("task".to_string(), serde_json::json!("Fix build")),
+        );
+        for node in [&task, &customer, &blocker] {
+            store.store_node(node.clone()).await.unwrap();
         }
 
-        // Sort by final score and apply limits
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-        results.truncate(config.max_results);
+        store
+            .create_relationship(&task.id, &customer.id, "assigned_to")
+            .await
+            .unwrap();
+        store
+            .create_relationship_with_properties(
+                &blocker.id,
+                &task.id,
+                "blocks",
+                serde_json::json!({"reason": "red CI"}),
+            )
+            .await
+            .unwrap();
+        assert!(store
+            .create_relationship(&task.id, &NodeId::new(), "references")
+            .await
+            .is_err());
+        assert_eq!(
+            store.index_maintenance_status().await.relationship_error,
+            None
+        );
+
+        // Hierarchy types make `to` a child of `from` and write no edge
+        store
+            .create_relationship(&task.id, &blocker.id, "Parent")
+            .await
+            .unwrap();
+        let child = store.get_node(&blocker.id).await.unwrap().unwrap();
+        assert_eq!(child.parent_id, Some(task.id.clone()));
+        assert!(store
+            .create_relationship_with_properties(
+                &task.id,
+                &blocker.id,
+                "contains",
+                serde_json::json!({}),
+            )
+            .await
+            .is_err());
 
-        Ok(results)
-    }
+        let types = |edges: Vec<Relationship>| -> Vec<String> {
+            let mut types: Vec<String> = edges.into_iter().map(|edge| edge.rel_type).collect();
+            types.sort();
+            types
+        };
+        let outgoing = store
+            .get_relationships(&task.id, RelationshipDirection::Outgoing, None)
+            .await
+            .unwrap();
+        assert_eq!(types(outgoing), vec!["assigned_to"]);
+        let blocked_by = store
+            .get_relationships(&task.id, RelationshipDirection::Incoming, Some("blocks"))
+            .await
+            .unwrap();
+        assert_eq!(blocked_by.len(), 1);
+        assert_eq!(blocked_by[0].from, blocker.id);
+        assert_eq!(
+            blocked_by[0].properties,
+            Some(serde_json::json!({"reason": "red CI"}))
+        );
+
+        // Same endpoints and type: properties replaced, created_at kept
+        store
+            .create_relationship(&blocker.id, &task.id, "blocks")
+            .await
+            .unwrap();
+        let again = store
+            .get_relationships(&blocker.id, RelationshipDirection::Outgoing, Some("blocks"))
+            .await
+            .unwrap();
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].properties, None);
+        assert_eq!(again[0].created_at, blocked_by[0].created_at);
 
-    // Implement DataStore trait methods for root-based hierarchy queries
-    async fn get_nodes_by_root(&self, root_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
-        // Direct delegation to the implementation method
-        self.get_nodes_by_root_internal(root_id).await
-    }
+        store
+            .delete_relationship(&task.id, &customer.id, "assigned_to")
+            .await
+            .unwrap();
+        let both = store
+            .get_relationships(&task.id, RelationshipDirection::Both, None)
+            .await
+            .unwrap();
+        assert_eq!(types(both), vec!["blocks"]);
 
-    async fn get_nodes_by_root_and_type(
-        &self,
-        root_id: &NodeId,
-        r#type: &str,
-    ) -> NodeSpaceResult<Vec<Node>> {
-        // Direct delegation to the implementation method
-        self.get_nodes_by_root_and_type_internal(root_id, r#type)
+        // Deleting a node takes its edges with it
+        store.delete_node(&blocker.id).await.unwrap();
+        assert!(store
+            .get_relationships(&task.id, RelationshipDirection::Both, None)
             .await
+            .unwrap()
+            .is_empty());
     }
-}
 
-impl LanceDataStore {
-    /// Get all nodes under a specific root with single indexed query
-    /// This is the core optimization that replaces multiple O(N) database scans
-    /// with a single O(1) LanceDB indexed filter operation.
-    ///
-    /// NOTE: This is a basic implementation - the filter will be optimized once
-    /// LanceDB's filter API is properly integrated with root_id indexing.
-    pub async fn get_nodes_by_root_internal(&self, root_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
-        // For now, use the existing query and filter in memory
-        // TODO: Replace with native LanceDB filter once filter API is working
-        let all_nodes = self.query_nodes_arrow("").await?;
-        let root_id_str = root_id.to_string();
-
-        let mut matching_nodes = Vec::new();
-        for universal_node in all_nodes {
-            if let Some(ref node_root_id) = universal_node.root_id {
-                if node_root_id == &root_id_str {
-                    let node = self.universal_to_node(universal_node);
-                    matching_nodes.push(node);
-                }
-            }
+    #[tokio::test]
+    async fn test_backlinks_from_content_mentions() {
+        let store = create_test_store().await;
+        let spec = Node::new("text".to_string(), serde_json::json!("Spec"));
+        let wiki = Node::new(
+            "text".to_string(),
+            serde_json::json!(format!("Notes on [[{}|the spec]]", spec.id)),
+        );
+        let mut task = Node::new(
+            "task".to_string(),
+            serde_json::json!(format!("Review @{} before Friday", spec.id)),
+        );
+        task.metadata = Some(serde_json::json!({ "mentions": ["release"] }));
+        let email = Node::new(
+            "text".to_string(),
+            serde_json::json!(format!("Mail me{}@acme.com", spec.id)),
+        );
+        for node in [&spec, &wiki, &task, &email] {
+            store.store_node(node.clone()).await.unwrap();
         }
 
-        Ok(matching_nodes)
+        let backlink_ids = |nodes: Vec<Node>| -> Vec<NodeId> {
+            let mut ids: Vec<NodeId> = nodes.into_iter().map(|node| node.id).collect();
+            ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            ids
+        };
+        let mut expected = vec![wiki.id.clone(), task.id.clone()];
+        expected.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(
+            backlink_ids(store.get_backlinks(&spec.id).await.unwrap()),
+            expected
+        );
+
+        // Explicit mentions are kept alongside the extracted ones
+        let release = NodeId::from_string("release".to_string());
+        assert_eq!(
+            backlink_ids(store.get_backlinks(&release).await.unwrap()),
+            vec![task.id.clone()]
+        );
+
+        // Dropping the link from the text drops the backlink
+        let mut task = store.get_node(&task.id).await.unwrap().unwrap();
+        task.content = serde_json::json!("Review before Friday");
+        store.update_node(task.clone()).await.unwrap();
+        assert_eq!(
+            backlink_ids(store.get_backlinks(&spec.id).await.unwrap()),
+            vec![wiki.id.clone()]
+        );
+        assert_eq!(
+            backlink_ids(store.get_backlinks(&release).await.unwrap()),
+            vec![task.id]
+        );
     }
 
-    /// Get typed nodes by root for specialized queries
-    /// Combines root filtering with node type filtering for optimal performance
-    pub async fn get_nodes_by_root_and_type_internal(
-        &self,
-        root_id: &NodeId,
-        r#type: &str,
-    ) -> NodeSpaceResult<Vec<Node>> {
-        // For now, use the existing query and filter in memory
-        // TODO: Replace with native LanceDB filter once filter API is working
-        let all_nodes = self.query_nodes_arrow("").await?;
-        let root_id_str = root_id.to_string();
-
-        let mut matching_nodes = Vec::new();
-        for universal_node in all_nodes {
-            // Check both root_id and node_type match
-            if let Some(ref node_root_id) = universal_node.root_id {
-                if node_root_id == &root_id_str && universal_node.r#type == r#type {
-                    let node = self.universal_to_node(universal_node);
-                    matching_nodes.push(node);
-                }
-            }
+    #[tokio::test]
+    async fn test_traverse_hierarchy_mentions_and_relationships() {
+        let store = create_test_store().await;
+        // project > [design > [spike], build]; spike references project; note mentions design
+        let project = Node::new("project".to_string(), serde_json::json!("Launch"));
+        let mut design = Node::new("task".to_string(), serde_json::json!("Design"));
+        design.parent_id = Some(project.id.clone());
+        let mut build = Node::new("task".to_string(), serde_json::json!("Build"))
+            .with_before_sibling(Some(design.id.clone()));
+        build.parent_id = Some(project.id.clone());
+        let mut spike = Node::new("task".to_string(), serde_json::json!("Spike"));
+        spike.parent_id = Some(design.id.clone());
+        let note = Node::new(
+            "text".to_string(),
+            serde_json::json!(format!("Meeting: went through [[{}]]", design.id)),
+        );
+        for node in [&project, &build, &design, &spike, &note] {
+            store.store_node(node.clone()).await.unwrap();
+        }
+        store
+            .create_relationship(&spike.id, &project.id, "references")
+            .await
+            .unwrap();
+
+        async fn walk(
+            store: &LanceDataStore,
+            start: &NodeId,
+            options: TraversalOptions,
+        ) -> Vec<TraversalStep> {
+            let steps = store.traverse(start, options).await.unwrap();
+            steps.try_collect().await.unwrap()
         }
+        let ids = |steps: &[TraversalStep]| -> Vec<NodeId> {
+            steps.iter().map(|step| step.node.id.clone()).collect()
+        };
 
-        Ok(matching_nodes)
-    }
+        let follow_all = vec![
+            TraversalEdge::Children,
+            TraversalEdge::Relationship {
+                rel_type: Some("references".to_string()),
+                direction: RelationshipDirection::Outgoing,
+            },
+        ];
+        let breadth_first = walk(
+            &store,
+            &project.id,
+            TraversalOptions {
+                edges: follow_all.clone(),
+                ..Default::default()
+            },
+        )
+        .await;
+        // The reference back to the project doesn't visit it again
+        assert_eq!(
+            ids(&breadth_first),
+            vec![
+                project.id.clone(),
+                design.id.clone(),
+                build.id.clone(),
+                spike.id.clone()
+            ]
+        );
+        assert_eq!(
+            breadth_first[3].path,
+            vec![project.id.clone(), design.id.clone(), spike.id.clone()]
+        );
+        assert_eq!(breadth_first[3].depth, 2);
+        assert_eq!(breadth_first[0].via, None);
+
+        let depth_first = walk(
+            &store,
+            &project.id,
+            TraversalOptions {
+                order: TraversalOrder::DepthFirst,
+                edges: follow_all,
+                max_depth: Some(2),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(
+            ids(&depth_first),
+            vec![
+                project.id.clone(),
+                design.id.clone(),
+                spike.id.clone(),
+                build.id.clone()
+            ]
+        );
+
+        // Tasks under the project, then the notes that mention them
+        let noted = walk(
+            &store,
+            &project.id,
+            TraversalOptions {
+                edges: vec![TraversalEdge::Children, TraversalEdge::MentionedBy],
+                max_depth: Some(2),
+                step_types: vec![vec!["task".to_string()], vec!["text".to_string()]],
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(
+            ids(&noted),
+            vec![
+                project.id.clone(),
+                design.id.clone(),
+                build.id.clone(),
+                note.id.clone()
+            ]
+        );
+        assert_eq!(noted[3].via, Some(TraversalEdge::MentionedBy));
+        assert_eq!(
+            noted[3].path,
+            vec![project.id.clone(), design.id.clone(), note.id.clone()]
+        );
+
+        let upward = walk(
+            &store,
+            &spike.id,
+            TraversalOptions {
+                edges: vec![TraversalEdge::Parent],
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(ids(&upward), vec![spike.id.clone(), design.id, project.id]);
 
-    /// Create composite indexes for hierarchy query optimization
-    /// This implements the performance strategy from your architectural recommendations
-    pub async fn create_hierarchy_indexes(&self) -> NodeSpaceResult<()> {
-        let table_guard = self.table.read().await;
-        if let Some(table) = table_guard.as_ref() {
-            // Check if table has data before creating indexes
-            let stats = table
-                .count_rows(None)
-                .await
-                .map_err(|e| DataStoreError::LanceDB(format!("Failed to get row count: {}", e)))?;
+        assert!(store
+            .traverse(&NodeId::new(), TraversalOptions::default())
+            .await
+            .is_err());
+    }
 
-            if stats > 0 {
-                // Primary composite index: (root_id, node_type, created_at)
-                // This enables efficient hierarchy + type + temporal queries
-                let _ = table
-                    .create_index(
-                        &["root_id", "node_type", "created_at"],
-                        lancedb::index::Index::BTree(Default::default()),
-                    )
-                    .replace(true)
-                    .execute()
-                    .await;
+    #[tokio::test]
+    async fn test_retrieve_context() {
+        let store = create_test_store().await;
 
-                // Supporting index: (root_id, parent_id) for relationship queries
-                let _ = table
-                    .create_index(
-                        &["root_id", "parent_id"],
-                        lancedb::index::Index::BTree(Default::default()),
-                    )
-                    .replace(true)
-                    .execute()
-                    .await;
-            }
+        let vector = |x: f32, y: f32| {
+            let mut vector = vec![0.0; 384];
+            vector[0] = x;
+            vector[1] = y;
+            vector
+        };
+        // Doc > [first, hit, last], hit > [detail]; stored out of sibling order
+        let doc = Node::new("text".to_string(), serde_json::json!("Doc"));
+        let mut first = Node::new("text".to_string(), serde_json::json!("First"));
+        first.parent_id = Some(doc.id.clone());
+        let mut hit = Node::new("text".to_string(), serde_json::json!("Hit"))
+            .with_before_sibling(Some(first.id.clone()));
+        hit.parent_id = Some(doc.id.clone());
+        let mut last = Node::new("text".to_string(), serde_json::json!("Last"))
+            .with_before_sibling(Some(hit.id.clone()));
+        last.parent_id = Some(doc.id.clone());
+        let mut detail = Node::new("text".to_string(), serde_json::json!("Detail"));
+        detail.parent_id = Some(hit.id.clone());
+        for (node, embedding) in [
+            (last.clone(), vector(0.0, 1.0)),
+            (hit.clone(), vector(1.0, 0.0)),
+            (doc.clone(), vector(0.0, 1.0)),
+            (first.clone(), vector(0.0, 1.0)),
+            (detail.clone(), vector(0.0, 1.0)),
+        ] {
+            store
+                .store_node_with_embedding(node, embedding)
+                .await
+                .unwrap();
         }
 
-        Ok(())
+        let options = ContextOptions {
+            max_hits: 1,
+            ..ContextOptions::default()
+        };
+        let window = store
+            .retrieve_context(vector(1.0, 0.0), options.clone())
+            .await
+            .unwrap();
+        assert!(!window.truncated);
+        assert_eq!(window.hits.len(), 1);
+        let context = &window.hits[0];
+        assert_eq!(context.node.id, hit.id);
+        let ids = |nodes: &[Node]| nodes.iter().map(|node| node.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&context.ancestors), vec![doc.id.clone()]);
+        assert_eq!(ids(&context.siblings_before), vec![first.id.clone()]);
+        assert_eq!(ids(&context.siblings_after), vec![last.id.clone()]);
+        assert_eq!(ids(&context.children), vec![detail.id.clone()]);
+
+        // "Hit" + "Doc" fit in 8 characters, "First" doesn't
+        let window = store
+            .retrieve_context(
+                vector(1.0, 0.0),
+                ContextOptions {
+                    budget: ContextBudget::Characters(8),
+                    ..options
+                },
+            )
+            .await
+            .unwrap();
+        assert!(window.truncated);
+        assert_eq!(window.used, 6);
+        assert_eq!(ids(&window.hits[0].ancestors), vec![doc.id]);
+        assert!(window.hits[0].siblings_before.is_empty());
+        assert!(window.hits[0].children.is_empty());
     }
 
-    /// Get child nodes using Arrow storage for hierarchical relationships
-    pub async fn get_child_nodes(&self, parent_id: &NodeId) -> NodeSpaceResult<Vec<Node>> {
-        // Get all nodes from Arrow storage
-        let universal_nodes = self.query_nodes_arrow("").await?;
+    #[tokio::test]
+    async fn test_search_similar_nodes_diverse() {
+        let store = create_test_store().await;
 
-        let mut children = Vec::new();
-        for universal_node in universal_nodes {
-            if let Some(ref pid) = universal_node.parent_id {
-                if pid == parent_id.as_str() {
-                    let node = self.universal_to_node(universal_node);
-                    children.push(node);
-                }
-            }
+        let vector = |x: f32, y: f32| {
+            let mut vector = vec![0.0; 384];
+            vector[0] = x;
+            vector[1] = y;
+            vector
+        };
+        // Two near-identical bullets from one document and a distinct note elsewhere
+        let document = NodeId::new();
+        let mut first = Node::new("text".to_string(), serde_json::json!("Bullet one"));
+        first.root_id = Some(document.clone());
+        let mut second = Node::new("text".to_string(), serde_json::json!("Bullet two"));
+        second.root_id = Some(document);
+        let other = Node::new("text".to_string(), serde_json::json!("Other note"));
+        for (node, embedding) in [
+            (first.clone(), vector(1.0, 0.0)),
+            (second.clone(), vector(0.99, 0.1)),
+            (other.clone(), vector(0.6, 0.8)),
+        ] {
+            store
+                .store_node_with_embedding(node, embedding)
+                .await
+                .unwrap();
         }
 
-        Ok(children)
+        let plain = store
+            .search_similar_nodes(vector(1.0, 0.0), 2)
+            .await
+            .unwrap();
+        assert_eq!(plain[1].0.id, second.id);
+
+        let diverse = store
+            .search_similar_nodes_diverse(
+                vector(1.0, 0.0),
+                2,
+                DiversityConfig {
+                    mmr_lambda: Some(0.3),
+                    ..DiversityConfig::default()
+                },
+            )
+            .await
+            .unwrap();
+        let ids: Vec<NodeId> = diverse.iter().map(|(node, _)| node.id.clone()).collect();
+        assert_eq!(ids, vec![first.id.clone(), other.id.clone()]);
+        // Scores stay similarities to the query
+        assert!((diverse[1].1 - 0.6).abs() < 1e-5);
+
+        let capped = store
+            .search_similar_nodes_diverse(
+                vector(1.0, 0.0),
+                3,
+                DiversityConfig {
+                    mmr_lambda: None,
+                    source_cap: Some(SourceCap::PerRoot(1)),
+                    candidate_multiplier: 2,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(capped.len(), 2);
+        assert_eq!(capped[1].0.id, other.id);
     }
 
-    /// Create or update relationship using Arrow storage for entity connections
-    pub async fn update_relationship(
-        &self,
-        node_id: &NodeId,
-        parent_id: Option<NodeId>,
-        children_ids: Vec<NodeId>,
-    ) -> NodeSpaceResult<()> {
-        if let Some(mut node) = self.get_node(node_id).await? {
-            let mut metadata = node.metadata.unwrap_or_else(|| serde_json::json!({}));
+    #[tokio::test]
+    async fn test_hybrid_semantic_search_fuses_levels() {
+        let store = create_test_store().await;
 
-            if let Some(parent_id) = parent_id {
-                metadata["parent_id"] = serde_json::Value::String(parent_id.to_string());
-            } else {
-                metadata
-                    .as_object_mut()
-                    .and_then(|obj| obj.remove("parent_id"));
+        let vector = |x: f32, y: f32| {
+            let mut vector = vec![0.0; 384];
+            vector[0] = x;
+            vector[1] = y;
+            vector
+        };
+        let embeddings = |individual: Vec<f32>, contextual: Option<Vec<f32>>| {
+            crate::data_store::MultiLevelEmbeddings {
+                individual,
+                contextual,
+                hierarchical: None,
+                embedding_model: None,
+                generated_at: chrono::Utc::now(),
             }
+        };
 
-            metadata["children_ids"] = serde_json::Value::Array(
-                children_ids
-                    .into_iter()
-                    .map(|id| serde_json::Value::String(id.to_string()))
-                    .collect(),
-            );
+        // `solo` has no contextual embedding; `paired` matches on both levels
+        let solo = Node::new("text".to_string(), serde_json::json!("Solo"));
+        let paired = Node::new("text".to_string(), serde_json::json!("Paired"));
+        store
+            .store_node_with_multi_embeddings(solo.clone(), embeddings(vector(1.0, 0.0), None))
+            .await
+            .unwrap();
+        store
+            .store_node_with_multi_embeddings(
+                paired.clone(),
+                embeddings(vector(0.6, 0.8), Some(vector(0.0, 1.0))),
+            )
+            .await
+            .unwrap();
 
-            node.metadata = Some(metadata);
-            self.store_node(node).await?;
-        }
+        let query = crate::data_store::QueryEmbeddings {
+            individual: vector(1.0, 0.0),
+            contextual: Some(vector(0.0, 1.0)),
+            hierarchical: None,
+        };
+        let mut config = HybridSearchConfig {
+            individual_weight: 0.5,
+            contextual_weight: 0.5,
+            hierarchical_weight: 0.5,
+            min_similarity_threshold: 0.0,
+            ..HybridSearchConfig::default()
+        };
 
-        Ok(())
+        // The missing contextual level doesn't drag `solo` down
+        let results = store
+            .hybrid_semantic_search(query.clone(), config.clone())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].node.id, solo.id);
+        assert!((results[0].score - 1.0).abs() < 1e-5);
+        assert_eq!(results[1].node.id, paired.id);
+        assert!((results[1].score - 0.8).abs() < 1e-5);
+
+        // The contextual similarity is reported as its own level, not as a structural score
+        let components = &results[1].explanation.components;
+        assert_eq!(components[1].signal, ScoreSignal::Contextual);
+        assert!((components[1].score - 1.0).abs() < 1e-5);
+        assert!((components[1].contribution - 0.5).abs() < 1e-5);
+        assert_eq!(results[1].relevance_factors.structural_score, 0.0);
+
+        // Ranked on both levels, `paired` wins under reciprocal rank fusion
+        config.fusion_strategy = FusionStrategy::ReciprocalRank { k: 60.0 };
+        let results = store
+            .hybrid_semantic_search(query.clone(), config.clone())
+            .await
+            .unwrap();
+        assert_eq!(results[0].node.id, paired.id);
+        assert!((results[0].score - (0.5 / 62.0 + 0.5 / 61.0)).abs() < 1e-6);
+
+        // Without cross-level fusion only the individual level is searched
+        config.enable_cross_level_fusion = false;
+        config.fusion_strategy = FusionStrategy::WeightedSum;
+        let results = store.hybrid_semantic_search(query, config).await.unwrap();
+        assert_eq!(results[1].node.id, paired.id);
+        assert!((results[1].score - 0.6).abs() < 1e-5);
+        let signals: Vec<ScoreSignal> = results[1]
+            .explanation
+            .components
+            .iter()
+            .map(|component| component.signal)
+            .collect();
+        assert_eq!(signals, vec![ScoreSignal::Individual]);
     }
 
-    /// Hybrid search combining semantic search with metadata filtering using Arrow storage
-    pub async fn hybrid_search(
-        &self,
-        _embedding: Vec<f32>,
-        node_type_filter: Option<String>,
-        _metadata_filter: Option<serde_json::Value>,
-        limit: usize,
-    ) -> NodeSpaceResult<Vec<(Node, f32)>> {
-        // Get all nodes from Arrow storage
-        let universal_nodes = self.query_nodes_arrow("").await?;
-
-        let mut similarities = Vec::new();
-
-        for universal_node in universal_nodes {
-            // Apply node type filter if specified
-            if let Some(ref filter_type) = node_type_filter {
-                if &universal_node.r#type != filter_type {
-                    continue;
-                }
-            }
+    #[tokio::test]
+    async fn test_full_text_search() {
+        let store = create_test_store().await;
 
-            // Use LanceDB's native vector similarity instead of manual calculation
-            // This is a fallback for hybrid search - ideally should use vector_search_arrow
-            let node = self.universal_to_node(universal_node);
-            similarities.push((node, 1.0)); // Placeholder score
-        }
+        let ownership = Node::new(
+            "text".to_string(),
+            serde_json::json!("Rust ownership and borrowing in Rust"),
+        );
+        let engine = Node::new(
+            "text".to_string(),
+            serde_json::json!("Rust vector search engine"),
+        );
+        let notebook = Node::new(
+            "task".to_string(),
+            serde_json::json!("Python vector search notebook"),
+        );
+        let garden = Node::new("text".to_string(), serde_json::json!("Gardening notes"));
+        store
+            .store_nodes(vec![
+                ownership.clone(),
+                engine.clone(),
+                notebook.clone(),
+                garden.clone(),
+            ])
+            .await
+            .unwrap();
 
-        // Sort by similarity descending and take limit
-        similarities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        similarities.truncate(limit);
+        let ids = |results: Vec<SearchResult>| -> Vec<NodeId> {
+            results.into_iter().map(|result| result.node.id).collect()
+        };
 
-        Ok(similarities)
-    }
-}
+        // More occurrences in a shorter document rank higher under BM25
+        let results = store.full_text_search("rust", 10).await.unwrap();
+        assert!(results[0].score > results[1].score);
+        assert_eq!(ids(results), vec![ownership.id.clone(), engine.id.clone()]);
 
-/// Simple cosine similarity implementation for cases where LanceDB native scoring isn't available
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.len() != b.len() {
-        return 0.0;
-    }
+        let results = store
+            .full_text_search(r#""vector search" type:task"#, 10)
+            .await
+            .unwrap();
+        assert_eq!(ids(results), vec![notebook.id.clone()]);
 
-    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let results = store
+            .full_text_search(r#""search vector""#, 10)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
 
-    if norm_a == 0.0 || norm_b == 0.0 {
-        return 0.0;
-    }
+        let results = store.full_text_search("borrow*", 10).await.unwrap();
+        assert_eq!(ids(results), vec![ownership.id.clone()]);
 
-    dot_product / (norm_a * norm_b)
-}
+        // Short reversed-word notes outrank the real phrase under BM25; the
+        // search widens past them instead of returning nothing
+        let mut notes: Vec<Node> = (0..6)
+            .map(|_| Node::new("text".to_string(), serde_json::json!("partitions index")))
+            .collect();
+        let phrase = Node::new(
+            "text".to_string(),
+            serde_json::json!("Notes on how the search walks index partitions"),
+        );
+        notes.push(phrase.clone());
+        // Prefix-only queries keep a bounded running top list while scanning
+        let best_prefix = Node::new(
+            "text".to_string(),
+            serde_json::json!("borrowed borrows borrowing"),
+        );
+        notes.extend((0..4).map(|_| Node::new("text".to_string(), serde_json::json!("borrowed"))));
+        notes.insert(2, best_prefix.clone());
+        store.store_nodes(notes).await.unwrap();
+
+        let results = store
+            .full_text_search(r#""index partitions""#, 1)
+            .await
+            .unwrap();
+        assert_eq!(ids(results), vec![phrase.id.clone()]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+        let results = store.full_text_search("borrow*", 1).await.unwrap();
+        assert_eq!(ids(results), vec![best_prefix.id.clone()]);
+        assert_eq!(
+            store.full_text_search("borrow*", 10).await.unwrap().len(),
+            6
+        );
 
-    async fn create_test_store() -> LanceDataStore {
-        let temp_dir = tempdir().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        LanceDataStore::new(db_path.to_str().unwrap()).await.unwrap()
+        assert!(store.full_text_search("  ", 10).await.is_err());
     }
 
     #[tokio::test]
-    async fn test_before_sibling_persistence() {
+    async fn test_hybrid_text_vector_search() {
         let store = create_test_store().await;
 
-        let node_a = Node::new("text".to_string(), serde_json::json!({"text": "Node A"}));
-        let node_b = Node::new("text".to_string(), serde_json::json!({"text": "Node B"}))
-            .with_before_sibling(Some(node_a.id.clone()));
-
-        // Store both nodes
-        store.store_node(node_a.clone()).await.unwrap();
-        store.store_node(node_b.clone()).await.unwrap();
+        let vector = |x: f32, y: f32| {
+            let mut vector = vec![0.0; 384];
+            vector[0] = x;
+            vector[1] = y;
+            vector
+        };
+        let keyword_only = Node::new(
+            "text".to_string(),
+            serde_json::json!("Quarterly budget spreadsheet for the finance team"),
+        );
+        let semantic_only = Node::new("text".to_string(), serde_json::json!("Planning notes"));
+        let both = Node::new("text".to_string(), serde_json::json!("Budget review"));
+        for (node, embedding) in [
+            (keyword_only.clone(), vector(0.0, 1.0)),
+            (semantic_only.clone(), vector(1.0, 0.0)),
+            (both.clone(), vector(0.8, 0.6)),
+        ] {
+            store
+                .store_node_with_embedding(node, embedding)
+                .await
+                .unwrap();
+        }
 
-        // Retrieve and verify sibling relationship preserved
-        let retrieved_b = store.get_node(&node_b.id).await.unwrap().unwrap();
-        assert_eq!(retrieved_b.before_sibling, Some(node_a.id));
+        let config = HybridSearchConfig {
+            semantic_weight: 0.5,
+            keyword_weight: 0.5,
+            min_similarity_threshold: 0.0,
+            fusion_strategy: FusionStrategy::ReciprocalRank { k: 60.0 },
+            ..HybridSearchConfig::default()
+        };
+        let results = store
+            .hybrid_text_vector_search("budget", vector(1.0, 0.0), config)
+            .await
+            .unwrap();
+
+        // Ranked on both sides, `both` beats the best hit of either side alone
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].node.id, both.id);
+        assert!((results[0].relevance_factors.semantic_score - 0.8).abs() < 1e-5);
+        assert!(results[0].relevance_factors.keyword_score > 0.0);
+
+        let explanation = &results[0].explanation;
+        assert_eq!(
+            explanation.fusion,
+            Some(FusionStrategy::ReciprocalRank { k: 60.0 })
+        );
+        let signals: Vec<ScoreSignal> = explanation
+            .components
+            .iter()
+            .map(|component| component.signal)
+            .collect();
+        assert_eq!(signals, vec![ScoreSignal::Individual, ScoreSignal::Keyword]);
+        let contributions: f32 = explanation
+            .components
+            .iter()
+            .map(|component| component.contribution)
+            .sum();
+        assert!((contributions - results[0].score).abs() < 1e-6);
+        assert_eq!(
+            explanation.highlights,
+            vec![Highlight {
+                start: 0,
+                end: 6,
+                term: "budget".to_string(),
+            }]
+        );
+
+        let semantic_hit = results
+            .iter()
+            .find(|result| result.node.id == semantic_only.id)
+            .unwrap();
+        assert_eq!(semantic_hit.relevance_factors.keyword_score, 0.0);
     }
 
     #[tokio::test]
-    async fn test_null_before_sibling_handling() {
+    async fn test_hybrid_search_cancellation_and_limits() {
         let store = create_test_store().await;
+        let node = Node::new("text".to_string(), serde_json::json!("Budget review"));
+        store
+            .store_node_with_embedding(node, vec![0.1; 384])
+            .await
+            .unwrap();
 
-        let node = Node::new("text".to_string(), serde_json::json!({"text": "Node"}));
-        // No before_sibling set (should be None)
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = HybridSearchConfig {
+            cancellation: Some(token),
+            ..HybridSearchConfig::default()
+        };
+        assert!(store
+            .hybrid_text_vector_search("budget", vec![0.1; 384], cancelled)
+            .await
+            .is_err());
 
-        store.store_node(node.clone()).await.unwrap();
-        let retrieved = store.get_node(&node.id).await.unwrap().unwrap();
+        let empty = HybridSearchConfig {
+            max_results: 0,
+            ..HybridSearchConfig::default()
+        };
+        assert!(store
+            .hybrid_text_vector_search("budget", vec![0.1; 384], empty)
+            .await
+            .unwrap()
+            .is_empty());
 
-        assert_eq!(retrieved.before_sibling, None);
+        // A generous deadline leaves results untouched
+        let results = store
+            .hybrid_text_vector_search("budget", vec![0.1; 384], HybridSearchConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].explanation.truncated);
     }
 }
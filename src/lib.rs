@@ -1,20 +1,44 @@
 mod data_store;
 mod error;
+mod hierarchy;
+mod integrity;
 
 // LanceDB implementation modules
 mod lance_data_store;
 mod lance_data_store_simple;
+mod mentions;
 pub mod performance;
+mod query;
+mod relationships;
 mod schema;
+mod scoring;
+mod search_budget;
+mod traversal;
 
 pub use data_store::{
-    DataStore, HybridSearchConfig, ImageMetadata, ImageNode, MultiLevelEmbeddings, NodeType,
-    QueryEmbeddings, RelevanceFactors, SearchResult,
+    ContextBudget, ContextHit, ContextOptions, ContextWindow, DataStore, DiversityConfig,
+    FusionStrategy, Highlight, HybridSearchConfig, ImageMetadata, ImageNode, MultiLevelEmbeddings,
+    NodeType, QueryEmbeddings, Relationship, RelationshipDirection, RelevanceFactors,
+    ScoreComponent, ScoreSignal, SearchExplanation, SearchResult, SearchTimeoutBehavior, SourceCap,
+    TemporalField, TemporalScoring,
 };
 
 pub use error::DataStoreError;
+pub use integrity::{IntegrityIssue, IntegrityReport, OrphanRepair, RepairPolicy, RepairReport};
 pub use lance_data_store::{
     LanceDBConfig, LanceDataStore as LanceDataStoreFull, UniversalDocument,
 };
-pub use lance_data_store_simple::{EmbeddingGenerator, LanceDataStore};
+pub use lance_data_store_simple::{
+    DistanceMetric, EmbeddingGenerator, HierarchyValidation, IndexInfo, IndexMaintenanceStatus,
+    IndexParams, IndexStats, LanceDataStore, LanceDataStoreConfig, VectorIndexAlgorithm,
+    VectorIndexPolicy,
+};
 pub use performance::{OperationType, PerformanceConfig, PerformanceMonitor, PerformanceSummary};
+pub use query::{
+    FilterMode, MetadataPredicate, NodeOrderField, NodePage, NodeQuery, NodeStream, PageCursor,
+    SortDirection,
+};
+pub use search_budget::CancellationToken;
+pub use traversal::{
+    TraversalEdge, TraversalOptions, TraversalOrder, TraversalStep, TraversalStream,
+};
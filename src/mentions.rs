@@ -0,0 +1,95 @@
+//! Wiki-style references in node content
+//!
+//! `[[node-id]]` (or `[[node-id|label]]`) and `@node-id` in a node's text are
+//! added to `UniversalNode::mentions` on every write, after any mentions the
+//! caller supplies. `get_backlinks` reads them back through the LabelList
+//! index on that column.
+
+use std::collections::HashSet;
+
+/// Characters allowed in an `@` reference
+fn is_reference_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Ids referenced from `content`, in order of first appearance
+pub(crate) fn extract_mentions(content: &str) -> Vec<String> {
+    let mut found: Vec<(usize, &str)> = Vec::new();
+
+    let mut offset = 0;
+    while let Some(start) = content[offset..].find("[[") {
+        let open = offset + start + 2;
+        let Some(length) = content[open..].find("]]") else {
+            break;
+        };
+        // In "[[a [[b]]" only the innermost brackets are a link
+        let inner = &content[open..open + length];
+        let (inner_start, inner) = match inner.rfind("[[") {
+            Some(nested) => (open + nested + 2, &inner[nested + 2..]),
+            None => (open, inner),
+        };
+        let target = inner.split('|').next().unwrap_or_default().trim();
+        if !target.is_empty() && !target.contains('\n') {
+            found.push((inner_start, target));
+        }
+        offset = open + length + 2;
+    }
+
+    let mut previous = None;
+    for (index, c) in content.char_indices() {
+        // An @ inside a word is an email address, not a mention
+        if c == '@' && !matches!(previous, Some(before) if is_reference_char(before)) {
+            let rest = &content[index + 1..];
+            let end = rest
+                .find(|c: char| !is_reference_char(c))
+                .unwrap_or(rest.len());
+            if end > 0 {
+                found.push((index, &rest[..end]));
+            }
+        }
+        previous = Some(c);
+    }
+
+    found.sort_by_key(|(position, _)| *position);
+    let mut seen = HashSet::new();
+    found
+        .into_iter()
+        .filter(|(_, id)| seen.insert(*id))
+        .map(|(_, id)| id.to_string())
+        .collect()
+}
+
+/// The caller's mentions followed by any new ones found in `content`
+pub(crate) fn merge_mentions(explicit: Vec<String>, content: &str) -> Vec<String> {
+    let mut mentions = explicit;
+    for id in extract_mentions(content) {
+        if !mentions.contains(&id) {
+            mentions.push(id);
+        }
+    }
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_mentions() {
+        assert_eq!(
+            extract_mentions(
+                "See [[spec-1]] and @alice, then [[ spec-2 | the spec ]] again [[spec-1]]"
+            ),
+            vec!["spec-1", "alice", "spec-2"]
+        );
+        // Emails, empty links and unclosed brackets aren't references
+        assert!(extract_mentions("mail bob@acme.com about [[ ]] and [[open").is_empty());
+        assert_eq!(extract_mentions("[[a [[b]]"), vec!["b"]);
+        assert_eq!(extract_mentions("(@task_7)."), vec!["task_7"]);
+
+        assert_eq!(
+            merge_mentions(vec!["x".to_string(), "a".to_string()], "[[a]] [[b]]"),
+            vec!["x", "a", "b"]
+        );
+    }
+}
@@ -0,0 +1,952 @@
+//! Structured node queries compiled to LanceDB SQL filters
+//!
+//! `NodeQuery` is a typed builder over the Universal Document Schema columns.
+//! Column predicates are compiled into a single SQL filter for
+//! `table.query().only_if(...)`. Content matching and metadata (stored as a
+//! JSON string) push down a conservative `LIKE` prefilter and are then
+//! evaluated exactly in Rust. Full-text search strings are parsed into a
+//! `TextQuery` the same way: ranked terms for the inverted index, plus
+//! phrases and prefixes pushed down as `LIKE` and checked exactly.
+
+use crate::data_store::Highlight;
+use crate::error::DataStoreError;
+use crate::lance_data_store_simple::UniversalNode;
+use base64::prelude::*;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::stream::BoxStream;
+use nodespace_core_types::{Node, NodeId, NodeSpaceResult};
+use serde_json::Value;
+
+/// Stream of nodes returned by `DataStore::stream_nodes`
+pub type NodeStream<'a> = BoxStream<'a, NodeSpaceResult<Node>>;
+
+/// Column used to order `find_nodes` results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeOrderField {
+    Id,
+    Type,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl NodeOrderField {
+    fn column(self) -> &'static str {
+        match self {
+            NodeOrderField::Id => "id",
+            NodeOrderField::Type => "type",
+            NodeOrderField::CreatedAt => "created_at",
+            NodeOrderField::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+/// Sort direction for `NodeQuery::order_by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// Where a filter runs relative to the nearest-neighbour search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterMode {
+    /// Filter first, then search only matching rows: always returns up to
+    /// `limit` matches, at the cost of evaluating the filter up front. When
+    /// the exact check of `content_contains` / metadata predicates rejects
+    /// candidates, the search is repeated with twice as many until it fills
+    #[default]
+    PreFilter,
+    /// Search first, then drop non-matching hits: cheaper for broad filters,
+    /// but can return fewer than `limit` results when matches are rare
+    PostFilter,
+}
+
+/// Predicate on a JSON path inside node metadata (`"status"`, `"project.owner"`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataPredicate {
+    Equals { path: Vec<String>, value: Value },
+    Exists { path: Vec<String> },
+}
+
+/// Half-open timestamp range: `from` inclusive, `to` exclusive
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TimeRange {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Typed query over stored nodes
+///
+/// ```ignore
+/// let query = NodeQuery::new()
+///     .node_type("task")
+///     .root_id(&project_id)
+///     .created_after(last_week)
+///     .metadata_equals("status", json!("open"))
+///     .order_by(NodeOrderField::CreatedAt, SortDirection::Descending)
+///     .limit(20);
+/// let tasks = store.find_nodes(query).await?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeQuery {
+    pub(crate) node_types: Vec<String>,
+    // Some(None) selects nodes without a parent
+    pub(crate) parent_id: Option<Option<String>>,
+    pub(crate) root_id: Option<String>,
+    pub(crate) mentions: Vec<String>,
+    pub(crate) content_contains: Option<String>,
+    created: TimeRange,
+    updated: TimeRange,
+    pub(crate) metadata: Vec<MetadataPredicate>,
+    pub(crate) order_by: Option<(NodeOrderField, SortDirection)>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: usize,
+    // Keyset position of a page walk; not part of the query's identity
+    pub(crate) after: Option<PageKey>,
+}
+
+impl NodeQuery {
+    /// Query matching every node
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match nodes of this type; repeated calls match any of the given types
+    pub fn node_type(mut self, node_type: impl Into<String>) -> Self {
+        self.node_types.push(node_type.into());
+        self
+    }
+
+    /// Match direct children of `parent_id`
+    pub fn parent_id(mut self, parent_id: &NodeId) -> Self {
+        self.parent_id = Some(Some(parent_id.to_string()));
+        self
+    }
+
+    /// Match only nodes without a parent
+    pub fn without_parent(mut self) -> Self {
+        self.parent_id = Some(None);
+        self
+    }
+
+    /// Match nodes belonging to the hierarchy rooted at `root_id`
+    pub fn root_id(mut self, root_id: &NodeId) -> Self {
+        self.root_id = Some(root_id.to_string());
+        self
+    }
+
+    /// Match nodes whose `mentions` include `node_id`; repeated calls match
+    /// nodes mentioning any of the given ids
+    pub fn mentions(mut self, node_id: &NodeId) -> Self {
+        self.mentions.push(node_id.to_string());
+        self
+    }
+
+    /// Match nodes whose content contains `text`, ignoring case
+    pub fn content_contains(mut self, text: impl Into<String>) -> Self {
+        self.content_contains = Some(text.into());
+        self
+    }
+
+    /// Match nodes created at or after `from`
+    pub fn created_after(mut self, from: DateTime<Utc>) -> Self {
+        self.created.from = Some(from);
+        self
+    }
+
+    /// Match nodes created strictly before `to`
+    pub fn created_before(mut self, to: DateTime<Utc>) -> Self {
+        self.created.to = Some(to);
+        self
+    }
+
+    /// Match nodes with `from <= created_at < to`
+    pub fn created_between(self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.created_after(from).created_before(to)
+    }
+
+    /// Match nodes updated at or after `from`
+    pub fn updated_after(mut self, from: DateTime<Utc>) -> Self {
+        self.updated.from = Some(from);
+        self
+    }
+
+    /// Match nodes updated strictly before `to`
+    pub fn updated_before(mut self, to: DateTime<Utc>) -> Self {
+        self.updated.to = Some(to);
+        self
+    }
+
+    /// Match nodes with `from <= updated_at < to`
+    pub fn updated_between(self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.updated_after(from).updated_before(to)
+    }
+
+    /// Match nodes whose metadata has `value` at the dot-separated `path`
+    ///
+    /// Never matches "text" or "date" nodes: their metadata isn't stored.
+    pub fn metadata_equals(mut self, path: &str, value: Value) -> Self {
+        self.metadata.push(MetadataPredicate::Equals {
+            path: split_path(path),
+            value,
+        });
+        self
+    }
+
+    /// Match nodes whose metadata has any value at the dot-separated `path`
+    ///
+    /// Never matches "text" or "date" nodes: their metadata isn't stored.
+    pub fn metadata_exists(mut self, path: &str) -> Self {
+        self.metadata.push(MetadataPredicate::Exists {
+            path: split_path(path),
+        });
+        self
+    }
+
+    /// Order results by a column
+    pub fn order_by(mut self, field: NodeOrderField, direction: SortDirection) -> Self {
+        self.order_by = Some((field, direction));
+        self
+    }
+
+    /// Return at most `limit` nodes
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip the first `offset` matching nodes
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Compile every pushable predicate into one LanceDB SQL filter
+    ///
+    /// Timestamps are stored as RFC 3339 strings in UTC, so range predicates
+    /// compare them lexicographically against bounds in the same format.
+    pub fn to_sql_filter(&self) -> Option<String> {
+        let mut clauses = Vec::new();
+
+        match self.node_types.as_slice() {
+            [] => {}
+            [node_type] => clauses.push(format!("type = {}", sql_string_literal(node_type))),
+            node_types => {
+                let literals: Vec<String> =
+                    node_types.iter().map(|t| sql_string_literal(t)).collect();
+                clauses.push(format!("type IN ({})", literals.join(", ")));
+            }
+        }
+
+        match &self.parent_id {
+            Some(Some(parent_id)) => {
+                clauses.push(format!("parent_id = {}", sql_string_literal(parent_id)))
+            }
+            Some(None) => clauses.push("parent_id IS NULL".to_string()),
+            None => {}
+        }
+
+        if let Some(root_id) = &self.root_id {
+            clauses.push(format!("root_id = {}", sql_string_literal(root_id)));
+        }
+
+        if !self.mentions.is_empty() {
+            let literals: Vec<String> = self
+                .mentions
+                .iter()
+                .map(|id| sql_string_literal(id))
+                .collect();
+            clauses.push(format!(
+                "array_has_any(mentions, [{}])",
+                literals.join(", ")
+            ));
+        }
+
+        if let Some(text) = &self.content_contains {
+            let pattern = format!("%{}%", text.to_lowercase());
+            if !pattern.contains('\\') {
+                clauses.push(format!(
+                    "lower(content) LIKE {}",
+                    sql_string_literal(&pattern)
+                ));
+            }
+        }
+
+        push_range_clauses(&mut clauses, "created_at", &self.created);
+        push_range_clauses(&mut clauses, "updated_at", &self.updated);
+
+        for predicate in &self.metadata {
+            if let Some(pattern) = metadata_like_pattern(predicate) {
+                clauses.push(format!("metadata LIKE {}", sql_string_literal(&pattern)));
+            }
+        }
+
+        if let Some(after) = &self.after {
+            clauses.push(self.after_clause(after));
+        }
+
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses.join(" AND "))
+        }
+    }
+
+    /// Whether some predicates must be re-checked after the SQL filter ran
+    ///
+    /// `LIKE` wildcards in user text only widen the pushed-down match, so the
+    /// exact check happens in Rust.
+    pub(crate) fn has_residual_predicates(&self) -> bool {
+        self.content_contains.is_some() || !self.metadata.is_empty()
+    }
+
+    /// Exact evaluation of the predicates the SQL filter only approximates
+    pub(crate) fn matches_residual(&self, node: &UniversalNode) -> bool {
+        if let Some(text) = &self.content_contains {
+            if !node.content.to_lowercase().contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+
+        self.metadata.iter().all(|predicate| match predicate {
+            MetadataPredicate::Equals { path, value } => {
+                lookup_path(node.metadata.as_ref(), path) == Some(value)
+            }
+            MetadataPredicate::Exists { path } => {
+                lookup_path(node.metadata.as_ref(), path).is_some()
+            }
+        })
+    }
+
+    /// Identifies the query a page cursor belongs to
+    ///
+    /// FNV-1a over an explicit serialization of every predicate, so saved
+    /// cursors survive toolchain upgrades (`DefaultHasher` output may change
+    /// between Rust releases).
+    fn fingerprint(&self) -> String {
+        let range = |range: &TimeRange| {
+            serde_json::json!([
+                range.from.as_ref().map(format_timestamp),
+                range.to.as_ref().map(format_timestamp)
+            ])
+        };
+        let metadata: Vec<Value> = self
+            .metadata
+            .iter()
+            .map(|predicate| match predicate {
+                MetadataPredicate::Equals { path, value } => {
+                    serde_json::json!(["equals", path, value])
+                }
+                MetadataPredicate::Exists { path } => serde_json::json!(["exists", path]),
+            })
+            .collect();
+        let order_by = self.order_by.map(|(field, direction)| {
+            let direction = match direction {
+                SortDirection::Ascending => "asc",
+                SortDirection::Descending => "desc",
+            };
+            [field.column(), direction]
+        });
+        // Some(None) ("no parent") and None ("any parent") must not collide
+        let parent_id = self
+            .parent_id
+            .as_ref()
+            .map(|parent_id| parent_id.iter().collect::<Vec<_>>());
+
+        let canonical = serde_json::json!([
+            self.node_types,
+            parent_id,
+            self.root_id,
+            self.mentions,
+            self.content_contains,
+            range(&self.created),
+            range(&self.updated),
+            metadata,
+            order_by,
+            self.limit,
+            self.offset,
+        ]);
+        format!("{:016x}", fnv1a_64(canonical.to_string().as_bytes()))
+    }
+
+    /// Order used for page walks: the requested one, or by id
+    pub(crate) fn page_order(&self) -> (NodeOrderField, SortDirection) {
+        self.order_by
+            .unwrap_or((NodeOrderField::Id, SortDirection::Ascending))
+    }
+
+    /// Position of `node` in the page order
+    pub(crate) fn page_key(&self, node: &UniversalNode) -> PageKey {
+        let (field, _) = self.page_order();
+        PageKey {
+            key: sort_key(node, field).to_string(),
+            id: node.id.clone(),
+        }
+    }
+
+    /// Rows strictly after `after` in the page order; ties on the sort key
+    /// are broken by ascending id
+    fn after_clause(&self, after: &PageKey) -> String {
+        let (field, direction) = self.page_order();
+        let op = match direction {
+            SortDirection::Ascending => ">",
+            SortDirection::Descending => "<",
+        };
+        let id = sql_string_literal(&after.id);
+        if field == NodeOrderField::Id {
+            return format!("id {} {}", op, id);
+        }
+        let column = field.column();
+        let key = sql_string_literal(&after.key);
+        format!("({column} {op} {key} OR ({column} = {key} AND id > {id}))")
+    }
+
+    /// Sort nodes by the requested order, if any, ties broken by id
+    pub(crate) fn sort(&self, nodes: &mut [UniversalNode]) {
+        if let Some((field, direction)) = self.order_by {
+            nodes.sort_by(|a, b| {
+                let ordering = sort_key(a, field).cmp(sort_key(b, field));
+                match direction {
+                    SortDirection::Ascending => ordering,
+                    SortDirection::Descending => ordering.reverse(),
+                }
+                .then_with(|| a.id.cmp(&b.id))
+            });
+        }
+    }
+}
+
+/// Sort key and id of the last row on a page; the next page starts after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PageKey {
+    pub(crate) key: String,
+    pub(crate) id: String,
+}
+
+/// Where a page walk resumes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PagePosition {
+    pub(crate) after: Option<PageKey>,
+    pub(crate) returned: usize, // Rows already returned, checked against the query's limit
+}
+
+/// Opaque continuation token for `DataStore::find_nodes_page`
+///
+/// Cursors are tied to the query that produced them and record the sort key
+/// and id of the last row returned; the next page starts strictly after it.
+/// Rows inserted or deleted between pages don't shift later pages, so no row
+/// is returned twice or skipped, except rows inserted before the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor(String);
+
+impl PageCursor {
+    pub fn from_string(cursor: String) -> Self {
+        Self(cursor)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub(crate) fn new(query: &NodeQuery, position: &PagePosition) -> Self {
+        let payload = serde_json::json!({
+            "query": query.fingerprint(),
+            "after": position.after.as_ref().map(|after| [&after.key, &after.id]),
+            "returned": position.returned,
+        });
+        Self(BASE64_URL_SAFE_NO_PAD.encode(payload.to_string()))
+    }
+
+    /// Position encoded in the cursor, checked against the query it is used with
+    pub(crate) fn position(&self, query: &NodeQuery) -> Result<PagePosition, DataStoreError> {
+        let invalid = || DataStoreError::InvalidQuery("Invalid page cursor".to_string());
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(&self.0)
+            .map_err(|_| invalid())?;
+        let payload: Value = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+        if payload.get("query").and_then(|v| v.as_str()) != Some(query.fingerprint().as_str()) {
+            return Err(DataStoreError::InvalidQuery(
+                "Page cursor was created for a different query".to_string(),
+            ));
+        }
+
+        let returned = payload
+            .get("returned")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(invalid)? as usize;
+        let after = match payload.get("after") {
+            Some(Value::Null) => None,
+            Some(Value::Array(key)) => match key.as_slice() {
+                [Value::String(key), Value::String(id)] => Some(PageKey {
+                    key: key.clone(),
+                    id: id.clone(),
+                }),
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(PagePosition { after, returned })
+    }
+}
+
+impl std::fmt::Display for PageCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// One page of query results
+#[derive(Debug, Clone)]
+pub struct NodePage {
+    pub nodes: Vec<Node>,
+    /// Cursor for the following page, `None` once the results are exhausted
+    pub next_cursor: Option<PageCursor>,
+}
+
+/// Parsed `DataStore::full_text_search` query
+///
+/// Syntax: bare words are BM25-ranked terms, `"quoted words"` must appear as a
+/// phrase, `word*` must match the start of a word, and `type:task` restricts
+/// node types (repeat for several). Words are split on non-alphanumeric
+/// characters and compared case-insensitively, like the index tokenizer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TextQuery {
+    pub(crate) terms: Vec<String>,
+    pub(crate) phrases: Vec<Vec<String>>,
+    pub(crate) prefixes: Vec<String>,
+    pub(crate) filter: NodeQuery,
+}
+
+impl TextQuery {
+    pub(crate) fn parse(input: &str) -> Result<Self, DataStoreError> {
+        let mut query = TextQuery::default();
+        let mut rest = input;
+
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            rest = &rest[start..];
+
+            if let Some(quoted) = rest.strip_prefix('"') {
+                // An unterminated quote runs to the end of the input
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let phrase = words(&quoted[..end]);
+                match phrase.len() {
+                    0 => {}
+                    1 => query.terms.extend(phrase),
+                    _ => query.phrases.push(phrase),
+                }
+                rest = quoted.get(end + 1..).unwrap_or("");
+                continue;
+            }
+
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = &rest[end..];
+
+            if let Some(node_type) = token.strip_prefix("type:") {
+                if !node_type.is_empty() {
+                    query.filter = query.filter.node_type(node_type);
+                }
+            } else if let Some(prefix) = token.strip_suffix('*') {
+                query.prefixes.extend(words(prefix));
+            } else {
+                query.terms.extend(words(token));
+            }
+        }
+
+        if query.terms.is_empty() && query.phrases.is_empty() && query.prefixes.is_empty() {
+            return Err(DataStoreError::InvalidQuery(format!(
+                "Full-text query '{}' has no search terms",
+                input
+            )));
+        }
+        Ok(query)
+    }
+
+    /// Text for the BM25 match: every term and phrase word, `None` for prefix-only queries
+    pub(crate) fn ranked_text(&self) -> Option<String> {
+        let ranked: Vec<&str> = self
+            .terms
+            .iter()
+            .chain(self.phrases.iter().flatten())
+            .map(String::as_str)
+            .collect();
+        if ranked.is_empty() {
+            None
+        } else {
+            Some(ranked.join(" "))
+        }
+    }
+
+    /// Type filter plus a `LIKE` prefilter for every phrase word and prefix
+    ///
+    /// Words are alphanumeric, so the patterns need no escaping.
+    pub(crate) fn to_sql_filter(&self) -> Option<String> {
+        let mut clauses: Vec<String> = self.filter.to_sql_filter().into_iter().collect();
+        for word in self.phrases.iter().flatten().chain(&self.prefixes) {
+            clauses.push(format!(
+                "lower(content) LIKE {}",
+                sql_string_literal(&format!("%{}%", word))
+            ));
+        }
+
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses.join(" AND "))
+        }
+    }
+
+    /// Whether phrases or prefixes must be checked after the SQL filter ran
+    pub(crate) fn has_residual_predicates(&self) -> bool {
+        !self.phrases.is_empty() || !self.prefixes.is_empty()
+    }
+
+    /// Exact phrase and prefix check against node content
+    pub(crate) fn matches(&self, content: &str) -> bool {
+        let content_words = words(content);
+        self.phrases.iter().all(|phrase| {
+            content_words
+                .windows(phrase.len())
+                .any(|window| window == phrase.as_slice())
+        }) && self.prefixes.iter().all(|prefix| {
+            content_words
+                .iter()
+                .any(|word| word.starts_with(prefix.as_str()))
+        })
+    }
+
+    /// Spans of `content` matching a term, phrase or prefix, in start order
+    pub(crate) fn highlights(&self, content: &str) -> Vec<Highlight> {
+        let spans = word_spans(content);
+        let mut highlights = Vec::new();
+
+        for (start, end, word) in &spans {
+            if self.terms.contains(word) {
+                highlights.push(Highlight {
+                    start: *start,
+                    end: *end,
+                    term: word.clone(),
+                });
+            }
+            for prefix in &self.prefixes {
+                if word.starts_with(prefix.as_str()) {
+                    highlights.push(Highlight {
+                        start: *start,
+                        end: *end,
+                        term: format!("{}*", prefix),
+                    });
+                }
+            }
+        }
+
+        for phrase in &self.phrases {
+            for window in spans.windows(phrase.len()) {
+                if window.iter().map(|(_, _, word)| word).eq(phrase.iter()) {
+                    highlights.push(Highlight {
+                        start: window[0].0,
+                        end: window[window.len() - 1].1,
+                        term: phrase.join(" "),
+                    });
+                }
+            }
+        }
+
+        highlights.sort_by_key(|highlight| (highlight.start, highlight.end));
+        highlights.dedup();
+        highlights
+    }
+
+    /// Number of content words matching any prefix, used to rank prefix-only queries
+    pub(crate) fn prefix_matches(&self, content: &str) -> usize {
+        words(content)
+            .iter()
+            .filter(|word| {
+                self.prefixes
+                    .iter()
+                    .any(|prefix| word.starts_with(prefix.as_str()))
+            })
+            .count()
+    }
+}
+
+/// Lowercased alphanumeric words of `text`
+fn words(text: &str) -> Vec<String> {
+    word_spans(text)
+        .into_iter()
+        .map(|(_, _, word)| word)
+        .collect()
+}
+
+/// Lowercased words of `text` with their character offsets, `end` exclusive
+fn word_spans(text: &str) -> Vec<(usize, usize, String)> {
+    let mut spans = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (offset, c) in text.chars().enumerate() {
+        if c.is_alphanumeric() {
+            current
+                .get_or_insert_with(|| (offset, String::new()))
+                .1
+                .extend(c.to_lowercase());
+        } else if let Some((start, word)) = current.take() {
+            spans.push((start, offset, word));
+        }
+    }
+    if let Some((start, word)) = current {
+        spans.push((start, text.chars().count(), word));
+    }
+
+    spans
+}
+
+fn sort_key(node: &UniversalNode, field: NodeOrderField) -> &str {
+    match field {
+        NodeOrderField::Id => &node.id,
+        NodeOrderField::Type => &node.r#type,
+        NodeOrderField::CreatedAt => &node.created_at,
+        NodeOrderField::UpdatedAt => &node.updated_at,
+    }
+}
+
+/// 64-bit FNV-1a, fixed across platforms and Rust releases
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+fn push_range_clauses(clauses: &mut Vec<String>, column: &str, range: &TimeRange) {
+    if let Some(from) = &range.from {
+        clauses.push(format!(
+            "{} >= {}",
+            column,
+            sql_string_literal(&format_timestamp(from))
+        ));
+    }
+    if let Some(to) = &range.to {
+        clauses.push(format!(
+            "{} < {}",
+            column,
+            sql_string_literal(&format_timestamp(to))
+        ));
+    }
+}
+
+/// Fixed-width UTC form node timestamps are stored and compared in
+///
+/// `created_at` / `updated_at` are text columns, so range predicates and
+/// ordering compare strings; one offset (`Z`) and one precision (nanoseconds)
+/// make that the same as comparing instants.
+pub(crate) fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// `format_timestamp` for an RFC 3339 string in any offset or precision;
+/// text that doesn't parse is kept as is
+pub(crate) fn normalize_timestamp(value: &str) -> String {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(timestamp) => format_timestamp(&timestamp.with_timezone(&Utc)),
+        Err(_) => value.to_string(),
+    }
+}
+
+/// `LIKE` pattern matched by every row that satisfies the predicate
+///
+/// Stored metadata is compact serde_json output, so `"key":value` appears
+/// verbatim for a match. Wildcards in the pattern only widen the match, but
+/// backslashes would act as escapes, so those predicates skip the pushdown.
+/// Numbers are not pushed down because `1` and `1.0` serialize differently.
+fn metadata_like_pattern(predicate: &MetadataPredicate) -> Option<String> {
+    let (path, value) = match predicate {
+        MetadataPredicate::Equals { path, value } => (path, Some(value)),
+        MetadataPredicate::Exists { path } => (path, None),
+    };
+    let key = serde_json::to_string(path.last()?).ok()?;
+
+    let pattern = match value {
+        Some(value @ (Value::String(_) | Value::Bool(_) | Value::Null)) => {
+            format!("%{}:{}%", key, value)
+        }
+        Some(_) => return None,
+        None => format!("%{}:%", key),
+    };
+
+    if pattern.contains('\\') {
+        None
+    } else {
+        Some(pattern)
+    }
+}
+
+fn lookup_path<'a>(metadata: Option<&'a Value>, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(metadata?, |value, segment| value.get(segment.as_str()))
+}
+
+/// Quote a string for use as a literal in a LanceDB SQL predicate
+pub(crate) fn sql_string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lance_data_store_simple::test_universal_node;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_empty_query_has_no_filter() {
+        assert_eq!(NodeQuery::new().to_sql_filter(), None);
+    }
+
+    #[test]
+    fn test_column_predicates_compile_to_sql() {
+        let from = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+        let query = NodeQuery::new()
+            .node_type("task")
+            .node_type("project")
+            .parent_id(&NodeId::from_string("p'1".to_string()))
+            .created_between(from, to);
+
+        assert_eq!(
+            query.to_sql_filter().unwrap(),
+            "type IN ('task', 'project') AND parent_id = 'p''1' \
+             AND created_at >= '2025-01-01T00:00:00.000000000Z' \
+             AND created_at < '2025-02-01T00:00:00.000000000Z'"
+        );
+
+        let backlinks = NodeQuery::new()
+            .mentions(&NodeId::from_string("a".to_string()))
+            .mentions(&NodeId::from_string("b".to_string()));
+        assert_eq!(
+            backlinks.to_sql_filter().unwrap(),
+            "array_has_any(mentions, ['a', 'b'])"
+        );
+
+        let after = PageKey {
+            key: "2025-01-01T00:00:00+00:00".to_string(),
+            id: "n'1".to_string(),
+        };
+        let newest_first = NodeQuery {
+            after: Some(after.clone()),
+            ..NodeQuery::new().order_by(NodeOrderField::CreatedAt, SortDirection::Descending)
+        };
+        assert_eq!(
+            newest_first.to_sql_filter().unwrap(),
+            "(created_at < '2025-01-01T00:00:00+00:00' \
+             OR (created_at = '2025-01-01T00:00:00+00:00' AND id > 'n''1'))"
+        );
+        let by_id = NodeQuery {
+            after: Some(after),
+            ..NodeQuery::new()
+        };
+        assert_eq!(by_id.to_sql_filter().unwrap(), "id > 'n''1'");
+    }
+
+    #[test]
+    fn test_page_cursor_round_trip() {
+        let query = NodeQuery::new().node_type("task");
+        let position = PagePosition {
+            after: Some(PageKey {
+                key: "2025-01-01T00:00:00+00:00".to_string(),
+                id: "n40".to_string(),
+            }),
+            returned: 40,
+        };
+        let cursor = PageCursor::new(&query, &position);
+        assert_eq!(cursor.position(&query).unwrap(), position);
+
+        let restored = PageCursor::from_string(cursor.to_string());
+        assert_eq!(restored.position(&query).unwrap(), position);
+
+        // The fingerprint depends only on the predicates, not on the walk position
+        let walking = NodeQuery {
+            after: position.after.clone(),
+            ..query.clone()
+        };
+        assert_eq!(walking.fingerprint(), query.fingerprint());
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(
+            NodeQuery::new().without_parent().fingerprint(),
+            NodeQuery::new().fingerprint()
+        );
+
+        assert!(cursor
+            .position(&NodeQuery::new().node_type("text"))
+            .is_err());
+        assert!(PageCursor::from_string("garbage".to_string())
+            .position(&query)
+            .is_err());
+    }
+
+    #[test]
+    fn test_metadata_predicates_prefilter_and_refine() {
+        let query = NodeQuery::new()
+            .metadata_equals("details.status", serde_json::json!("open"))
+            .metadata_equals("priority", serde_json::json!(2));
+
+        // Only the string predicate can be pushed down
+        assert_eq!(
+            query.to_sql_filter().unwrap(),
+            "metadata LIKE '%\"status\":\"open\"%'"
+        );
+
+        let mut node = UniversalNode {
+            r#type: "task".to_string(),
+            metadata: Some(serde_json::json!({"details": {"status": "open"}, "priority": 2})),
+            ..test_universal_node("n1")
+        };
+        assert!(query.matches_residual(&node));
+
+        node.metadata = Some(serde_json::json!({"status": "open", "priority": 2}));
+        assert!(!query.matches_residual(&node));
+    }
+
+    #[test]
+    fn test_text_query_parsing_and_matching() {
+        let query = TextQuery::parse(r#"Rust "vector search" index* type:text type:task"#).unwrap();
+        assert_eq!(query.terms, vec!["rust"]);
+        assert_eq!(query.phrases, vec![vec!["vector", "search"]]);
+        assert_eq!(query.prefixes, vec!["index"]);
+        assert_eq!(query.ranked_text().as_deref(), Some("rust vector search"));
+        assert_eq!(
+            query.to_sql_filter().unwrap(),
+            "type IN ('text', 'task') AND lower(content) LIKE '%vector%' \
+             AND lower(content) LIKE '%search%' AND lower(content) LIKE '%index%'"
+        );
+
+        assert!(query.matches("Fast Vector-Search with indexes"));
+        assert!(!query.matches("search vector indexes"));
+        assert!(!query.matches("vector search without the prefix"));
+
+        let prefixes = TextQuery::parse("emb*").unwrap();
+        assert_eq!(prefixes.ranked_text(), None);
+        assert_eq!(prefixes.prefix_matches("Embedding embeds members"), 2);
+
+        assert!(TextQuery::parse("type:task \"\" *").is_err());
+
+        // Offsets count characters, so the accented word doesn't shift them
+        let highlights = query.highlights("Café: Rust vector search indexes");
+        let spans: Vec<(usize, usize, &str)> = highlights
+            .iter()
+            .map(|h| (h.start, h.end, h.term.as_str()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (6, 10, "rust"),
+                (11, 24, "vector search"),
+                (25, 32, "index*"),
+            ]
+        );
+    }
+}
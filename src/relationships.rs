@@ -0,0 +1,196 @@
+//! Typed edges between nodes, kept in their own Lance table
+//!
+//! Hierarchy stays in the node rows (`parent_id`, `children_ids`); every other
+//! kind of link ("references", "blocks", "assigned_to", ...) is one row here,
+//! keyed by `(from_id, to_id, rel_type)`.
+
+use crate::data_store::{Relationship, RelationshipDirection};
+use crate::error::DataStoreError;
+use crate::query::sql_string_literal;
+use arrow_array::{Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use nodespace_core_types::NodeId;
+use std::sync::Arc;
+
+/// Relationship types stored as hierarchy fields rather than as edge rows
+///
+/// Before the relationships table every type was written to the hierarchy,
+/// with `from` as the parent; these are the names callers used for that and
+/// keep doing so. Matched case-insensitively.
+pub(crate) const HIERARCHY_REL_TYPES: &[&str] =
+    &["contains", "parent", "child", "parent_child", "has_child"];
+
+/// Whether `rel_type` links `from` as the parent of `to` instead of writing an edge
+pub(crate) fn is_hierarchy_type(rel_type: &str) -> bool {
+    HIERARCHY_REL_TYPES
+        .iter()
+        .any(|hierarchy_type| hierarchy_type.eq_ignore_ascii_case(rel_type))
+}
+
+/// Columns that together identify an edge
+pub(crate) const RELATIONSHIP_KEY_COLUMNS: &[&str] = &["from_id", "to_id", "rel_type"];
+
+/// Columns that get a BTree index so edges are found from either end
+pub(crate) const RELATIONSHIP_INDEX_COLUMNS: &[&str] = &["from_id", "to_id"];
+
+pub(crate) fn relationship_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("from_id", DataType::Utf8, false),
+        Field::new("to_id", DataType::Utf8, false),
+        Field::new("rel_type", DataType::Utf8, false),
+        Field::new("properties", DataType::Utf8, true), // Nullable JSON string
+        Field::new("created_at", DataType::Utf8, false),
+    ]))
+}
+
+pub(crate) fn relationships_to_batch(
+    relationships: &[Relationship],
+) -> Result<RecordBatch, DataStoreError> {
+    let column = |value: fn(&Relationship) -> String| -> Arc<dyn Array> {
+        Arc::new(StringArray::from(
+            relationships.iter().map(value).collect::<Vec<_>>(),
+        ))
+    };
+    let properties: Vec<Option<String>> = relationships
+        .iter()
+        .map(|relationship| {
+            relationship
+                .properties
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+        })
+        .collect::<Result<_, _>>()?;
+
+    RecordBatch::try_new(
+        relationship_schema(),
+        vec![
+            column(|relationship| relationship.from.to_string()),
+            column(|relationship| relationship.to.to_string()),
+            column(|relationship| relationship.rel_type.clone()),
+            Arc::new(StringArray::from(properties)),
+            column(|relationship| relationship.created_at.clone()),
+        ],
+    )
+    .map_err(|e| DataStoreError::Arrow(format!("Failed to build relationship batch: {}", e)))
+}
+
+pub(crate) fn relationships_from_batch(
+    batch: &RecordBatch,
+) -> Result<Vec<Relationship>, DataStoreError> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .and_then(|col| col.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| DataStoreError::Arrow(format!("Missing or invalid {} column", name)))
+    };
+    let (from, to, rel_type, properties, created_at) = (
+        column("from_id")?,
+        column("to_id")?,
+        column("rel_type")?,
+        column("properties")?,
+        column("created_at")?,
+    );
+
+    (0..batch.num_rows())
+        .map(|row| -> Result<Relationship, DataStoreError> {
+            let properties = if properties.is_null(row) {
+                None
+            } else {
+                Some(serde_json::from_str(properties.value(row))?)
+            };
+            Ok(Relationship {
+                from: NodeId::from_string(from.value(row).to_string()),
+                to: NodeId::from_string(to.value(row).to_string()),
+                rel_type: rel_type.value(row).to_string(),
+                properties,
+                created_at: created_at.value(row).to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The one edge with these endpoints and type
+pub(crate) fn edge_predicate(from: &str, to: &str, rel_type: &str) -> String {
+    format!(
+        "from_id = {} AND to_id = {} AND rel_type = {}",
+        sql_string_literal(from),
+        sql_string_literal(to),
+        sql_string_literal(rel_type)
+    )
+}
+
+/// Edges touching `node_key` in `direction`, optionally of one type
+pub(crate) fn direction_predicate(
+    node_key: &str,
+    direction: RelationshipDirection,
+    rel_type: Option<&str>,
+) -> String {
+    let node = sql_string_literal(node_key);
+    let endpoints = match direction {
+        RelationshipDirection::Outgoing => format!("from_id = {}", node),
+        RelationshipDirection::Incoming => format!("to_id = {}", node),
+        RelationshipDirection::Both => format!("(from_id = {node} OR to_id = {node})"),
+    };
+    match rel_type {
+        Some(rel_type) => format!(
+            "{} AND rel_type = {}",
+            endpoints,
+            sql_string_literal(rel_type)
+        ),
+        None => endpoints,
+    }
+}
+
+/// Edges with either end in `ids`
+pub(crate) fn touching_predicate(ids: &[String]) -> String {
+    let literals = ids
+        .iter()
+        .map(|id| sql_string_literal(id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("from_id IN ({literals}) OR to_id IN ({literals})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relationship_batch_round_trip() {
+        let relationships = vec![
+            Relationship {
+                from: NodeId::from_string("task".to_string()),
+                to: NodeId::from_string("customer".to_string()),
+                rel_type: "assigned_to".to_string(),
+                properties: Some(serde_json::json!({"role": "owner"})),
+                created_at: "2025-01-01T00:00:00+00:00".to_string(),
+            },
+            Relationship {
+                from: NodeId::from_string("task".to_string()),
+                to: NodeId::from_string("other".to_string()),
+                rel_type: "blocks".to_string(),
+                properties: None,
+                created_at: "2025-01-02T00:00:00+00:00".to_string(),
+            },
+        ];
+        let batch = relationships_to_batch(&relationships).unwrap();
+        assert_eq!(relationships_from_batch(&batch).unwrap(), relationships);
+    }
+
+    #[test]
+    fn test_relationship_predicates_escape_ids() {
+        assert_eq!(
+            direction_predicate("it's", RelationshipDirection::Incoming, Some("blocks")),
+            "to_id = 'it''s' AND rel_type = 'blocks'"
+        );
+        assert_eq!(
+            direction_predicate("a", RelationshipDirection::Both, None),
+            "(from_id = 'a' OR to_id = 'a')"
+        );
+        assert_eq!(
+            touching_predicate(&["a".to_string(), "b".to_string()]),
+            "from_id IN ('a', 'b') OR to_id IN ('a', 'b')"
+        );
+    }
+}
@@ -0,0 +1,587 @@
+//! Pure scoring helpers for hybrid search
+//!
+//! Kept free of storage concerns so the scoring rules can be tested directly;
+//! `LanceDataStore` gathers the inputs and applies the results.
+
+use crate::data_store::{
+    DiversityConfig, FusionStrategy, ScoreComponent, ScoreSignal, SourceCap, TemporalField,
+    TemporalScoring,
+};
+use crate::lance_data_store_simple::{cosine_similarity, UniversalNode};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// Relationship columns of one row, enough to walk the node graph
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct NodeLinks {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub children_ids: Vec<String>,
+    pub mentions: Vec<String>,
+    pub root_id: Option<String>,
+}
+
+/// Ids one hop away from `frontier` through the given row
+///
+/// Edges are undirected: a row in the frontier reaches its parent, children,
+/// mentions and root, and a row pointing at a frontier node through any of
+/// those links is reached from it.
+pub(crate) fn adjacent_ids(links: &NodeLinks, frontier: &HashSet<String>) -> Vec<String> {
+    let mut adjacent = Vec::new();
+
+    if frontier.contains(&links.id) {
+        adjacent.extend(links.parent_id.iter().cloned());
+        adjacent.extend(links.children_ids.iter().cloned());
+        adjacent.extend(links.mentions.iter().cloned());
+        adjacent.extend(links.root_id.iter().cloned());
+    }
+
+    let points_at_frontier = links
+        .parent_id
+        .iter()
+        .chain(links.root_id.iter())
+        .chain(links.children_ids.iter())
+        .chain(links.mentions.iter())
+        .any(|id| frontier.contains(id));
+    if points_at_frontier {
+        adjacent.push(links.id.clone());
+    }
+
+    adjacent
+}
+
+/// Structural relevance for a node `hops` edges from the nearest anchor
+///
+/// `1 / (1 + hops)`: 1.0 for an anchor itself, 0.5 for its direct neighbours,
+/// and 0.0 for nodes that are unreachable within the hop limit.
+pub(crate) fn structural_score(hops: Option<usize>) -> f32 {
+    match hops {
+        Some(hops) => 1.0 / (1.0 + hops as f32),
+        None => 0.0,
+    }
+}
+
+/// The node timestamp selected by `field`, `None` when missing or unparseable
+pub(crate) fn node_timestamp(node: &UniversalNode, field: &TemporalField) -> Option<DateTime<Utc>> {
+    match field {
+        TemporalField::CreatedAt => parse_timestamp(&node.created_at),
+        TemporalField::UpdatedAt => parse_timestamp(&node.updated_at),
+        TemporalField::Metadata(key) if node.r#type == "date" => node
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(key))
+            .and_then(|value| value.as_str())
+            .and_then(parse_timestamp)
+            .or_else(|| parse_timestamp(&node.content)),
+        TemporalField::Metadata(key) => node
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(key))
+            .and_then(|value| value.as_str())
+            .and_then(parse_timestamp),
+    }
+}
+
+/// RFC 3339 timestamp, or a `YYYY-MM-DD` date taken as midnight UTC
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|midnight| midnight.and_utc())
+        })
+}
+
+/// Temporal relevance of `timestamp` relative to `reference`
+pub(crate) fn temporal_score(
+    scoring: &TemporalScoring,
+    timestamp: Option<DateTime<Utc>>,
+    reference: DateTime<Utc>,
+) -> f32 {
+    let distance = timestamp.map(|timestamp| (reference - timestamp).abs());
+
+    match (scoring, distance) {
+        (TemporalScoring::None, _) => 0.0,
+        (TemporalScoring::Stepped, None) => 0.5,
+        (TemporalScoring::Stepped, Some(distance)) => match distance.num_days() {
+            0..=1 => 1.0,
+            2..=7 => 0.8,
+            _ => 0.5,
+        },
+        (_, None) => 0.0,
+        (TemporalScoring::ExponentialDecay { half_life }, Some(distance)) => {
+            match span_fraction(distance, *half_life) {
+                Some(half_lives) => 0.5f64.powf(half_lives) as f32,
+                None => 0.0,
+            }
+        }
+        (TemporalScoring::LinearWindow { window }, Some(distance)) => {
+            match span_fraction(distance, *window) {
+                Some(fraction) => (1.0 - fraction).max(0.0) as f32,
+                None => 0.0,
+            }
+        }
+    }
+}
+
+/// `distance / span`; a non-positive span only admits a zero distance
+fn span_fraction(distance: chrono::Duration, span: chrono::Duration) -> Option<f64> {
+    let span_ms = span.num_milliseconds() as f64;
+    let distance_ms = distance.num_milliseconds() as f64;
+    if span_ms > 0.0 {
+        Some(distance_ms / span_ms)
+    } else if distance_ms == 0.0 {
+        Some(0.0)
+    } else {
+        None
+    }
+}
+
+/// Hits from one retrieval level as `(node id, similarity)`, best first
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LevelRanking {
+    pub weight: f32,
+    pub hits: Vec<(String, f32)>,
+}
+
+/// A node's place in one level's ranking and what it added to the fused score
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LevelHit {
+    pub similarity: f32,
+    /// 1-based
+    pub rank: usize,
+    pub contribution: f32,
+}
+
+/// A node's fused score and its hit on each level, `None` where it wasn't retrieved
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FusedHit {
+    pub id: String,
+    /// Sum of the level contributions
+    pub score: f32,
+    pub levels: Vec<Option<LevelHit>>,
+}
+
+impl FusedHit {
+    /// Best similarity on any level
+    pub(crate) fn best_similarity(&self) -> f32 {
+        self.levels
+            .iter()
+            .flatten()
+            .map(|level| level.similarity)
+            .fold(0.0, f32::max)
+    }
+
+    /// Explanation components for the levels this node was retrieved from
+    pub(crate) fn components(
+        &self,
+        levels: &[LevelRanking],
+        signals: &[ScoreSignal],
+    ) -> Vec<ScoreComponent> {
+        self.levels
+            .iter()
+            .zip(levels.iter().zip(signals))
+            .filter_map(|(level_hit, (ranking, signal))| {
+                let level_hit = level_hit.as_ref()?;
+                Some(ScoreComponent {
+                    signal: *signal,
+                    score: level_hit.similarity,
+                    weight: ranking.weight,
+                    rank: Some(level_hit.rank),
+                    contribution: level_hit.contribution,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Merge per-level rankings into one list, best first
+///
+/// Levels with a non-positive weight are ignored. If a level lists a node
+/// twice, its first (best) hit is used.
+pub(crate) fn fuse_rankings(strategy: FusionStrategy, levels: &[LevelRanking]) -> Vec<FusedHit> {
+    let mut fused: Vec<FusedHit> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+
+    for (level, ranking) in levels.iter().enumerate() {
+        if ranking.weight <= 0.0 {
+            continue;
+        }
+        for (rank, (id, similarity)) in ranking.hits.iter().enumerate() {
+            let position = *positions.entry(id.as_str()).or_insert_with(|| {
+                fused.push(FusedHit {
+                    id: id.clone(),
+                    score: 0.0,
+                    levels: vec![None; levels.len()],
+                });
+                fused.len() - 1
+            });
+            if fused[position].levels[level].is_none() {
+                fused[position].levels[level] = Some(LevelHit {
+                    similarity: *similarity,
+                    rank: rank + 1,
+                    contribution: 0.0,
+                });
+            }
+        }
+    }
+
+    for hit in fused.iter_mut() {
+        let total_weight: f32 = levels
+            .iter()
+            .zip(&hit.levels)
+            .filter(|(_, level_hit)| level_hit.is_some())
+            .map(|(ranking, _)| ranking.weight)
+            .sum();
+        // Max credits only the first level holding the best similarity
+        let best_level = hit
+            .levels
+            .iter()
+            .enumerate()
+            .filter_map(|(level, level_hit)| Some((level, level_hit.as_ref()?.similarity)))
+            .fold(
+                None,
+                |best: Option<(usize, f32)>, (level, similarity)| match best {
+                    Some((_, best_similarity)) if best_similarity >= similarity => best,
+                    _ => Some((level, similarity)),
+                },
+            )
+            .map(|(level, _)| level);
+
+        for (level, (ranking, level_hit)) in levels.iter().zip(hit.levels.iter_mut()).enumerate() {
+            let Some(level_hit) = level_hit else {
+                continue;
+            };
+            level_hit.contribution = match strategy {
+                FusionStrategy::WeightedSum => ranking.weight * level_hit.similarity / total_weight,
+                FusionStrategy::ReciprocalRank { k } => {
+                    ranking.weight / (k + level_hit.rank as f32)
+                }
+                FusionStrategy::Max if Some(level) == best_level => level_hit.similarity,
+                FusionStrategy::Max => 0.0,
+            };
+        }
+        hit.score = hit
+            .levels
+            .iter()
+            .flatten()
+            .map(|level_hit| level_hit.contribution)
+            .sum();
+    }
+
+    fused.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    fused
+}
+
+/// Source a node counts against under `cap`: its root or parent, else itself
+fn source_key<'a>(node: &'a UniversalNode, cap: &SourceCap) -> &'a str {
+    let source = match cap {
+        SourceCap::PerRoot(_) => node.root_id.as_deref(),
+        SourceCap::PerParent(_) => node.parent_id.as_deref(),
+    };
+    source.unwrap_or(&node.id)
+}
+
+/// Pick up to `limit` candidates by maximal marginal relevance
+///
+/// `candidates` are `(node, relevance)` in relevance order; the returned
+/// indices are in pick order. Ties go to the earlier candidate, so without
+/// `mmr_lambda` and a source cap this is just the first `limit` indices.
+pub(crate) fn diversify(
+    candidates: &[(&UniversalNode, f32)],
+    limit: usize,
+    config: &DiversityConfig,
+) -> Vec<usize> {
+    let best = candidates
+        .iter()
+        .map(|(_, relevance)| *relevance)
+        .fold(0.0_f32, f32::max);
+    let scale = if best > 0.0 { best } else { 1.0 };
+
+    // Highest similarity of each candidate to anything picked so far
+    let mut redundancy: Vec<Option<f32>> = vec![None; candidates.len()];
+    let mut available = vec![true; candidates.len()];
+    let mut per_source: HashMap<&str, usize> = HashMap::new();
+    let mut picked = Vec::new();
+
+    while picked.len() < limit {
+        let mut choice: Option<(usize, f32)> = None;
+        for (index, (node, relevance)) in candidates.iter().enumerate() {
+            if !available[index] {
+                continue;
+            }
+            if let Some(cap) = &config.source_cap {
+                let limit = match cap {
+                    SourceCap::PerRoot(limit) | SourceCap::PerParent(limit) => *limit,
+                };
+                if per_source.get(source_key(node, cap)).copied().unwrap_or(0) >= limit {
+                    continue;
+                }
+            }
+
+            let relevance = relevance / scale;
+            let score = match config.mmr_lambda {
+                Some(lambda) => {
+                    lambda * relevance - (1.0 - lambda) * redundancy[index].unwrap_or(0.0)
+                }
+                None => relevance,
+            };
+            match choice {
+                Some((_, best_score)) if best_score >= score => {}
+                _ => choice = Some((index, score)),
+            }
+        }
+
+        let Some((index, _)) = choice else {
+            break;
+        };
+        available[index] = false;
+        picked.push(index);
+
+        let node = candidates[index].0;
+        if let Some(cap) = &config.source_cap {
+            *per_source.entry(source_key(node, cap)).or_insert(0) += 1;
+        }
+        if config.mmr_lambda.is_some() {
+            for (other, (candidate, _)) in candidates.iter().enumerate() {
+                if available[other] {
+                    let similarity = cosine_similarity(&node.vector, &candidate.vector);
+                    redundancy[other] =
+                        Some(redundancy[other].map_or(similarity, |r| r.max(similarity)));
+                }
+            }
+        }
+    }
+
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lance_data_store_simple::test_universal_node;
+    use chrono::Duration;
+
+    fn links(id: &str, parent: Option<&str>, root: Option<&str>) -> NodeLinks {
+        NodeLinks {
+            id: id.to_string(),
+            parent_id: parent.map(str::to_string),
+            root_id: root.map(str::to_string),
+            ..NodeLinks::default()
+        }
+    }
+
+    #[test]
+    fn test_adjacent_ids_follow_links_both_ways() {
+        let frontier: HashSet<String> = ["a".to_string()].into_iter().collect();
+
+        // Outgoing: the frontier row reaches its parent and root
+        let mut anchor = links("a", Some("p"), Some("r"));
+        anchor.mentions = vec!["m".to_string()];
+        assert_eq!(adjacent_ids(&anchor, &frontier), vec!["p", "m", "r"]);
+
+        // Incoming: rows pointing at the frontier are reached
+        assert_eq!(
+            adjacent_ids(&links("c", Some("a"), None), &frontier),
+            vec!["c"]
+        );
+        let mut mentioning = links("x", None, None);
+        mentioning.mentions = vec!["a".to_string()];
+        assert_eq!(adjacent_ids(&mentioning, &frontier), vec!["x"]);
+
+        assert!(adjacent_ids(&links("u", Some("q"), Some("r")), &frontier).is_empty());
+    }
+
+    #[test]
+    fn test_structural_score_decays_with_hops() {
+        assert_eq!(structural_score(Some(0)), 1.0);
+        assert_eq!(structural_score(Some(1)), 0.5);
+        assert_eq!(structural_score(Some(3)), 0.25);
+        assert_eq!(structural_score(None), 0.0);
+    }
+
+    #[test]
+    fn test_temporal_score_curves() {
+        let reference = parse_timestamp("2025-06-20T12:00:00Z").unwrap();
+        let at = |days: i64| Some(reference + Duration::days(days));
+
+        let stepped = TemporalScoring::Stepped;
+        assert_eq!(temporal_score(&stepped, at(-1), reference), 1.0);
+        assert_eq!(temporal_score(&stepped, at(-5), reference), 0.8);
+        assert_eq!(temporal_score(&stepped, at(-30), reference), 0.5);
+        assert_eq!(temporal_score(&stepped, None, reference), 0.5);
+
+        let decay = TemporalScoring::ExponentialDecay {
+            half_life: Duration::days(7),
+        };
+        assert_eq!(temporal_score(&decay, at(0), reference), 1.0);
+        assert!((temporal_score(&decay, at(-7), reference) - 0.5).abs() < 1e-6);
+        assert!((temporal_score(&decay, at(14), reference) - 0.25).abs() < 1e-6);
+        assert_eq!(temporal_score(&decay, None, reference), 0.0);
+
+        let linear = TemporalScoring::LinearWindow {
+            window: Duration::days(10),
+        };
+        assert!((temporal_score(&linear, at(3), reference) - 0.7).abs() < 1e-6);
+        assert_eq!(temporal_score(&linear, at(-12), reference), 0.0);
+
+        assert_eq!(
+            temporal_score(&TemporalScoring::None, at(0), reference),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_node_timestamp_fields() {
+        let mut node = UniversalNode {
+            r#type: "task".to_string(),
+            created_at: "2025-06-01T08:00:00Z".to_string(),
+            updated_at: "2025-06-02T08:00:00+02:00".to_string(),
+            metadata: Some(serde_json::json!({"due": "2025-06-30"})),
+            ..test_universal_node("n1")
+        };
+        let due = TemporalField::Metadata("due".to_string());
+
+        assert_eq!(
+            node_timestamp(&node, &TemporalField::CreatedAt),
+            parse_timestamp("2025-06-01T08:00:00Z")
+        );
+        assert_eq!(
+            node_timestamp(&node, &TemporalField::UpdatedAt),
+            parse_timestamp("2025-06-02T06:00:00Z")
+        );
+        assert_eq!(
+            node_timestamp(&node, &due),
+            parse_timestamp("2025-06-30T00:00:00Z")
+        );
+
+        // Date nodes have no stored metadata and fall back to their content
+        node.r#type = "date".to_string();
+        node.metadata = None;
+        node.content = "2025-06-20".to_string();
+        assert_eq!(
+            node_timestamp(&node, &due),
+            parse_timestamp("2025-06-20T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_fuse_rankings_strategies() {
+        let levels = vec![
+            LevelRanking {
+                weight: 0.6,
+                hits: vec![("a".to_string(), 0.9), ("b".to_string(), 0.8)],
+            },
+            LevelRanking {
+                weight: 0.4,
+                hits: vec![("b".to_string(), 0.7), ("c".to_string(), 0.95)],
+            },
+            LevelRanking {
+                weight: 0.0,
+                hits: vec![("d".to_string(), 1.0)],
+            },
+        ];
+        let scores = |strategy| -> Vec<(String, f32)> {
+            fuse_rankings(strategy, &levels)
+                .into_iter()
+                .map(|hit| (hit.id, hit.score))
+                .collect()
+        };
+
+        // A node missing from a level is averaged over the levels it was found on
+        let weighted = scores(FusionStrategy::WeightedSum);
+        assert_eq!(weighted[0].0, "c");
+        assert!((weighted[0].1 - 0.95).abs() < 1e-6);
+        assert_eq!(weighted[1].0, "a");
+        assert!((weighted[2].1 - (0.6 * 0.8 + 0.4 * 0.7)).abs() < 1e-6);
+        assert_eq!(weighted.len(), 3);
+
+        // b is ranked on both levels, so RRF puts it first
+        let rrf = scores(FusionStrategy::ReciprocalRank { k: 60.0 });
+        assert_eq!(rrf[0].0, "b");
+        assert!((rrf[0].1 - (0.6 / 62.0 + 0.4 / 61.0)).abs() < 1e-6);
+
+        let max = scores(FusionStrategy::Max);
+        assert_eq!(max[0], ("c".to_string(), 0.95));
+
+        let fused = fuse_rankings(FusionStrategy::Max, &levels);
+        let b = fused.iter().find(|hit| hit.id == "b").unwrap();
+        let similarities: Vec<Option<f32>> = b
+            .levels
+            .iter()
+            .map(|level_hit| level_hit.as_ref().map(|level_hit| level_hit.similarity))
+            .collect();
+        assert_eq!(similarities, vec![Some(0.8), Some(0.7), None]);
+
+        // Contributions add up to the fused score
+        let weighted = fuse_rankings(FusionStrategy::WeightedSum, &levels);
+        let b = weighted.iter().find(|hit| hit.id == "b").unwrap();
+        let components = b.components(
+            &levels,
+            &[
+                ScoreSignal::Individual,
+                ScoreSignal::Contextual,
+                ScoreSignal::Hierarchical,
+            ],
+        );
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[1].rank, Some(1));
+        assert!((components[0].contribution - 0.48).abs() < 1e-6);
+        assert!((components.iter().map(|c| c.contribution).sum::<f32>() - b.score).abs() < 1e-6);
+    }
+
+    fn vector_node(id: &str, vector: Vec<f32>, root: Option<&str>) -> UniversalNode {
+        UniversalNode {
+            individual_vector: vector.clone(),
+            vector,
+            root_id: root.map(str::to_string),
+            ..test_universal_node(id)
+        }
+    }
+
+    #[test]
+    fn test_diversify_mmr_and_source_caps() {
+        // Two near-duplicates from one document, then a distinct one elsewhere
+        let a = vector_node("a", vec![1.0, 0.0], Some("doc1"));
+        let b = vector_node("b", vec![0.99, 0.1], Some("doc1"));
+        let c = vector_node("c", vec![0.0, 1.0], Some("doc2"));
+        let candidates = vec![(&a, 0.9), (&b, 0.88), (&c, 0.6)];
+
+        let relevance_only = DiversityConfig {
+            mmr_lambda: None,
+            source_cap: None,
+            candidate_multiplier: 1,
+        };
+        assert_eq!(diversify(&candidates, 3, &relevance_only), vec![0, 1, 2]);
+        assert_eq!(diversify(&candidates, 2, &relevance_only), vec![0, 1]);
+
+        // MMR pushes the near-duplicate below the distinct node
+        let mmr = DiversityConfig {
+            mmr_lambda: Some(0.5),
+            ..relevance_only.clone()
+        };
+        assert_eq!(diversify(&candidates, 3, &mmr), vec![0, 2, 1]);
+
+        // A per-root cap drops the duplicate entirely
+        let capped = DiversityConfig {
+            source_cap: Some(SourceCap::PerRoot(1)),
+            ..relevance_only.clone()
+        };
+        assert_eq!(diversify(&candidates, 3, &capped), vec![0, 2]);
+
+        // Nodes without a parent count as their own source
+        let per_parent = DiversityConfig {
+            source_cap: Some(SourceCap::PerParent(1)),
+            ..relevance_only
+        };
+        assert_eq!(diversify(&candidates, 3, &per_parent), vec![0, 1, 2]);
+    }
+}
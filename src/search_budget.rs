@@ -0,0 +1,226 @@
+//! Deadlines and cancellation for hybrid searches
+//!
+//! `SearchBudget` wraps each LanceDB call a search makes, racing it against
+//! `HybridSearchConfig::search_timeout_ms` and the caller's `CancellationToken`.
+
+use crate::data_store::{HybridSearchConfig, SearchResult, SearchTimeoutBehavior};
+use crate::error::DataStoreError;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Handle for aborting in-flight searches
+///
+/// Clones share state, so keep one and put a clone in `HybridSearchConfig`;
+/// calling `cancel` makes every search using it fail with
+/// `DataStoreError::SearchCancelled` at its next await point.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking so a concurrent cancel can't be missed
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Time and cancellation budget for one search
+pub(crate) struct SearchBudget {
+    started: Instant,
+    timeout_ms: u64,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    on_timeout: SearchTimeoutBehavior,
+    truncated: bool,
+}
+
+impl SearchBudget {
+    /// Start the clock; a `search_timeout_ms` of 0 means no deadline
+    pub(crate) fn start(config: &HybridSearchConfig) -> Self {
+        let started = Instant::now();
+        let deadline = (config.search_timeout_ms > 0)
+            .then(|| started + Duration::from_millis(config.search_timeout_ms));
+
+        Self {
+            started,
+            timeout_ms: config.search_timeout_ms,
+            deadline,
+            cancellation: config.cancellation.clone(),
+            on_timeout: config.on_timeout,
+            truncated: false,
+        }
+    }
+
+    /// Fail fast if the search was cancelled before doing any work
+    pub(crate) fn check(&self) -> Result<(), DataStoreError> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(DataStoreError::SearchCancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Await one step of the search within the budget
+    ///
+    /// Returns `Ok(None)` once the deadline has passed and partial results are
+    /// allowed; later steps then return `Ok(None)` without running.
+    pub(crate) async fn run<T, E>(
+        &mut self,
+        step: impl Future<Output = Result<T, E>>,
+    ) -> Result<Option<T>, DataStoreError>
+    where
+        E: Into<DataStoreError>,
+    {
+        self.check()?;
+        if self.truncated {
+            return Ok(None);
+        }
+
+        let cancellation = self.cancellation.clone();
+        let deadline = self.deadline;
+        tokio::select! {
+            biased;
+            _ = async {
+                match &cancellation {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending().await,
+                }
+            } => Err(DataStoreError::SearchCancelled),
+            _ = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                    None => std::future::pending().await,
+                }
+            } => self.deadline_reached(),
+            result = step => result.map(Some).map_err(Into::into),
+        }
+    }
+
+    /// Mark results of a search cut short by its deadline as truncated
+    ///
+    /// A search that ran out of time before finding anything has nothing to
+    /// flag, so it fails with `SearchTimeout` instead.
+    pub(crate) fn finish(
+        &self,
+        mut results: Vec<SearchResult>,
+    ) -> Result<Vec<SearchResult>, DataStoreError> {
+        if self.truncated {
+            if results.is_empty() {
+                return Err(self.timeout_error());
+            }
+            for result in &mut results {
+                result.explanation.truncated = true;
+            }
+        }
+        Ok(results)
+    }
+
+    fn deadline_reached<T>(&mut self) -> Result<Option<T>, DataStoreError> {
+        match self.on_timeout {
+            SearchTimeoutBehavior::ReturnPartial => {
+                self.truncated = true;
+                Ok(None)
+            }
+            SearchTimeoutBehavior::Error => Err(self.timeout_error()),
+        }
+    }
+
+    fn timeout_error(&self) -> DataStoreError {
+        DataStoreError::SearchTimeout {
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            timeout_ms: self.timeout_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(timeout_ms: u64, on_timeout: SearchTimeoutBehavior) -> SearchBudget {
+        SearchBudget::start(&HybridSearchConfig {
+            search_timeout_ms: timeout_ms,
+            on_timeout,
+            ..HybridSearchConfig::default()
+        })
+    }
+
+    fn stalled() -> impl Future<Output = Result<(), DataStoreError>> {
+        std::future::pending()
+    }
+
+    #[tokio::test]
+    async fn test_deadline_truncates_or_errors() {
+        let mut partial = budget(10, SearchTimeoutBehavior::ReturnPartial);
+        assert!(matches!(
+            partial.run(async { Ok::<_, DataStoreError>(1) }).await,
+            Ok(Some(1))
+        ));
+        assert!(matches!(partial.run(stalled()).await, Ok(None)));
+        // Once truncated, later steps are skipped
+        assert!(matches!(
+            partial.run(async { Ok::<_, DataStoreError>(2) }).await,
+            Ok(None)
+        ));
+        assert!(matches!(
+            partial.finish(Vec::new()),
+            Err(DataStoreError::SearchTimeout { timeout_ms: 10, .. })
+        ));
+
+        let mut strict = budget(10, SearchTimeoutBehavior::Error);
+        assert!(matches!(
+            strict.run(stalled()).await,
+            Err(DataStoreError::SearchTimeout { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_aborts_pending_step() {
+        let token = CancellationToken::new();
+        let mut budget = SearchBudget::start(&HybridSearchConfig {
+            search_timeout_ms: 0,
+            cancellation: Some(token.clone()),
+            ..HybridSearchConfig::default()
+        });
+
+        let cancel = async {
+            tokio::task::yield_now().await;
+            token.cancel();
+        };
+        let (result, _) = tokio::join!(budget.run(stalled()), cancel);
+        assert!(matches!(result, Err(DataStoreError::SearchCancelled)));
+        assert!(matches!(
+            budget.check(),
+            Err(DataStoreError::SearchCancelled)
+        ));
+    }
+}
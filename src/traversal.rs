@@ -0,0 +1,279 @@
+//! Graph walks over hierarchy, mentions and typed relationships
+//!
+//! The store looks up each visited node's neighbours; `Frontier` decides the
+//! visiting order, keeps the path that led to each node and returns every
+//! node at most once, so cycles through mentions or relationships end the
+//! walk. A node is walked from again only when a shorter path reaches it,
+//! which keeps depth-first walks complete up to `max_depth`.
+
+use crate::data_store::RelationshipDirection;
+use crate::hierarchy::SiblingLinks;
+use futures::stream::BoxStream;
+use nodespace_core_types::{Node, NodeId, NodeSpaceResult};
+use std::collections::{HashMap, VecDeque};
+
+/// Stream of nodes returned by `DataStore::traverse`, in visiting order
+pub type TraversalStream<'a> = BoxStream<'a, NodeSpaceResult<TraversalStep>>;
+
+/// Order in which `traverse` visits nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Nearest first; each node is reached by a shortest path
+    #[default]
+    BreadthFirst,
+    /// Follows the first edge of each node as deep as it goes before the next;
+    /// a node's reported path is the first one found, not necessarily the
+    /// shortest
+    DepthFirst,
+}
+
+/// Kind of link `traverse` follows out of each node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraversalEdge {
+    Parent,
+    Children,    // In `before_sibling_id` order
+    Mentions,    // Ids in the node's `mentions`
+    MentionedBy, // Nodes whose `mentions` include the node
+    Relationship {
+        rel_type: Option<String>, // None follows every type
+        direction: RelationshipDirection,
+    },
+}
+
+/// How `traverse` walks out from its start node
+#[derive(Debug, Clone)]
+pub struct TraversalOptions {
+    pub order: TraversalOrder,
+    pub edges: Vec<TraversalEdge>, // Followed in this order from every node
+    pub max_depth: Option<usize>,  // Hops from the start; None walks until exhausted
+    /// `step_types[n]` lists the node types allowed `n + 1` hops from the
+    /// start; an empty or missing entry allows any type. Nodes filtered out
+    /// are neither returned nor walked through.
+    pub step_types: Vec<Vec<String>>,
+}
+
+impl Default for TraversalOptions {
+    fn default() -> Self {
+        Self {
+            order: TraversalOrder::BreadthFirst,
+            edges: vec![TraversalEdge::Children],
+            max_depth: None,
+            step_types: Vec::new(),
+        }
+    }
+}
+
+/// A node reached by `traverse`
+#[derive(Debug, Clone)]
+pub struct TraversalStep {
+    pub node: Node,
+    pub depth: usize,               // Hops from the start, 0 for the start itself
+    pub path: Vec<NodeId>,          // Start first, this node last
+    pub via: Option<TraversalEdge>, // Kind of the last hop, None for the start
+}
+
+/// Whether a node of `node_type` may be visited `depth` hops from the start
+pub(crate) fn type_allowed(options: &TraversalOptions, depth: usize, node_type: &str) -> bool {
+    match depth
+        .checked_sub(1)
+        .and_then(|step| options.step_types.get(step))
+    {
+        Some(types) if !types.is_empty() => types.iter().any(|allowed| allowed == node_type),
+        _ => true,
+    }
+}
+
+/// A node waiting to be visited, with how it was reached
+pub(crate) struct Visit<T> {
+    pub node: T,
+    pub depth: usize,
+    pub path: Vec<NodeId>,
+    pub via: Option<TraversalEdge>,
+    // False when a shorter path reached an already returned node: walk on
+    // from it, but don't return it again
+    pub first: bool,
+}
+
+/// Nodes still to visit and the shallowest depth each node was visited at
+pub(crate) struct Frontier<T> {
+    order: TraversalOrder,
+    max_depth: Option<usize>,
+    pending: VecDeque<Visit<T>>,
+    visited: HashMap<String, usize>,
+}
+
+impl<T: SiblingLinks> Frontier<T> {
+    pub(crate) fn new(start: T, options: &TraversalOptions) -> Self {
+        let path = vec![NodeId::from_string(start.node_id().to_string())];
+        Self {
+            order: options.order,
+            max_depth: options.max_depth,
+            pending: VecDeque::from([Visit {
+                node: start,
+                depth: 0,
+                path,
+                via: None,
+                first: true,
+            }]),
+            visited: HashMap::new(),
+        }
+    }
+
+    /// Next node not yet visited at this depth or shallower, now marked as visited
+    pub(crate) fn next_visit(&mut self) -> Option<Visit<T>> {
+        loop {
+            let mut visit = match self.order {
+                TraversalOrder::BreadthFirst => self.pending.pop_front(),
+                TraversalOrder::DepthFirst => self.pending.pop_back(),
+            }?;
+            let seen = self.visited.get(visit.node.node_id()).copied();
+            if matches!(seen, Some(depth) if depth <= visit.depth) {
+                continue;
+            }
+            self.visited
+                .insert(visit.node.node_id().to_string(), visit.depth);
+            visit.first = seen.is_none();
+            return Some(visit);
+        }
+    }
+
+    /// Whether the walk continues past `visit`
+    pub(crate) fn expands(&self, visit: &Visit<T>) -> bool {
+        !matches!(self.max_depth, Some(max_depth) if visit.depth >= max_depth)
+    }
+
+    /// Queue the neighbours of `visit`, first neighbour visited first
+    pub(crate) fn push_neighbours(
+        &mut self,
+        visit: &Visit<T>,
+        neighbours: Vec<(TraversalEdge, T)>,
+    ) {
+        let queued: Vec<Visit<T>> = neighbours
+            .into_iter()
+            .filter(|(_, node)| {
+                !matches!(self.visited.get(node.node_id()), Some(&depth) if depth <= visit.depth + 1)
+            })
+            .map(|(edge, node)| {
+                let mut path = visit.path.clone();
+                path.push(NodeId::from_string(node.node_id().to_string()));
+                Visit {
+                    node,
+                    depth: visit.depth + 1,
+                    path,
+                    via: Some(edge),
+                    first: true,
+                }
+            })
+            .collect();
+        match self.order {
+            TraversalOrder::BreadthFirst => self.pending.extend(queued),
+            // The stack pops the last push first
+            TraversalOrder::DepthFirst => self.pending.extend(queued.into_iter().rev()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Stub(&'static str);
+
+    impl SiblingLinks for Stub {
+        fn node_id(&self) -> &str {
+            self.0
+        }
+        fn before_sibling(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    /// a -> [b, c], b -> [d, a], c -> [d], d -> [a]
+    fn cyclic(id: &str) -> Vec<&'static str> {
+        match id {
+            "a" => vec!["b", "c"],
+            "b" => vec!["d", "a"],
+            "c" => vec!["d"],
+            _ => vec!["a"],
+        }
+    }
+
+    /// Walk an adjacency list from "a", returning "id@depth" in visiting order
+    fn walk(
+        graph: fn(&str) -> Vec<&'static str>,
+        order: TraversalOrder,
+        max_depth: Option<usize>,
+    ) -> Vec<String> {
+        let options = TraversalOptions {
+            order,
+            max_depth,
+            ..Default::default()
+        };
+        let mut frontier = Frontier::new(Stub("a"), &options);
+        let mut visited = Vec::new();
+        while let Some(visit) = frontier.next_visit() {
+            if frontier.expands(&visit) {
+                let neighbours = graph(visit.node.0)
+                    .into_iter()
+                    .map(|id| (TraversalEdge::Children, Stub(id)))
+                    .collect();
+                frontier.push_neighbours(&visit, neighbours);
+            }
+            if visit.first {
+                visited.push(format!("{}@{}", visit.node.0, visit.depth));
+            }
+        }
+        visited
+    }
+
+    #[test]
+    fn test_frontier_order_depth_and_cycles() {
+        assert_eq!(
+            walk(cyclic, TraversalOrder::BreadthFirst, None),
+            vec!["a@0", "b@1", "c@1", "d@2"]
+        );
+        assert_eq!(
+            walk(cyclic, TraversalOrder::DepthFirst, None),
+            vec!["a@0", "b@1", "d@2", "c@1"]
+        );
+        assert_eq!(
+            walk(cyclic, TraversalOrder::BreadthFirst, Some(1)),
+            vec!["a@0", "b@1", "c@1"]
+        );
+    }
+
+    #[test]
+    fn test_depth_first_reaches_everything_within_max_depth() {
+        // x is first reached at the limit through b, then directly from a
+        fn shortcut(id: &str) -> Vec<&'static str> {
+            match id {
+                "a" => vec!["b", "x"],
+                "b" => vec!["x"],
+                "x" => vec!["y"],
+                _ => vec![],
+            }
+        }
+        assert_eq!(
+            walk(shortcut, TraversalOrder::DepthFirst, Some(2)),
+            vec!["a@0", "b@1", "x@2", "y@2"]
+        );
+        assert_eq!(
+            walk(shortcut, TraversalOrder::BreadthFirst, Some(2)),
+            vec!["a@0", "b@1", "x@1", "y@2"]
+        );
+    }
+
+    #[test]
+    fn test_type_allowed_per_step() {
+        let options = TraversalOptions {
+            step_types: vec![vec!["task".to_string()], Vec::new()],
+            ..Default::default()
+        };
+        assert!(type_allowed(&options, 0, "project"));
+        assert!(type_allowed(&options, 1, "task"));
+        assert!(!type_allowed(&options, 1, "text"));
+        assert!(type_allowed(&options, 2, "text"));
+        assert!(type_allowed(&options, 3, "text"));
+    }
+}